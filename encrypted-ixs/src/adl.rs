@@ -0,0 +1,342 @@
+use arcis_imports::*;
+
+/// Encrypted instructions for auto-deleveraging (ADL)
+/// When the insurance fund cannot cover a bankrupt account, profitable
+/// positions on the opposite side are ranked by PnL × leverage and the
+/// top-ranked ones are closed against the bankrupt position at its
+/// bankruptcy price. Only the ranking order is revealed.
+#[encrypted]
+pub mod circuits {
+    use arcis_imports::*;
+
+    /// Fixed-point scale shared by prices and sizes (1e6)
+    const PRICE_SCALE: u128 = 1_000_000;
+
+    /// Leverage is expressed in basis points
+    const BPS_DENOMINATOR: u128 = 10_000;
+
+    /// Candidate positions per ranking and per deleveraging (must match the program)
+    const ADL_ACCOUNTS: usize = 8;
+
+    /// Encrypted balances stored on a margin account
+    /// Ciphertext order matches `encrypted_collateral`, `encrypted_debt`
+    #[derive(Clone, Copy)]
+    pub struct MarginBalances {
+        collateral: u64,
+        debt: u64,
+    }
+
+    /// Encrypted net position stored on a margin account
+    /// Layout must match `Position` in the settle_trade circuit
+    #[derive(Clone, Copy)]
+    pub struct Position {
+        size: u64,
+        side: u8,
+        entry_notional: u64,
+    }
+
+    /// Encrypted open interest per side
+    /// Layout must match `OpenInterest` in the settle_trade circuit
+    #[derive(Clone, Copy)]
+    pub struct OpenInterest {
+        long: u64,
+        short: u64,
+    }
+
+    /// Unrealized PnL of a position at the mark price, floored at zero,
+    /// and its ADL score: PnL × leverage (position value over equity, in bps).
+    /// Both are capped at u64::MAX so the score fits in u128.
+    fn adl_score(balances: MarginBalances, position: Position, mark_price: u64) -> (u128, u128) {
+        // Using u128 to prevent overflow
+        let value = position.size as u128 * mark_price as u128 / PRICE_SCALE;
+        let entry = position.entry_notional as u128;
+        let (pnl, assets, liabilities) = if position.side == 1 {
+            (
+                if value > entry { value - entry } else { 0 },
+                balances.collateral as u128 + value,
+                balances.debt as u128,
+            )
+        } else {
+            (
+                if entry > value { entry - value } else { 0 },
+                balances.collateral as u128,
+                balances.debt as u128 + value,
+            )
+        };
+        let equity = if assets > liabilities {
+            assets - liabilities
+        } else {
+            0
+        };
+
+        // Both branches are evaluated in MPC, so the divisor is kept non-zero
+        let divisor = if equity == 0 { 1 } else { equity };
+        let leverage_bps = value * BPS_DENOMINATOR / divisor;
+        let leverage_bps = if leverage_bps > u64::MAX as u128 {
+            u64::MAX as u128
+        } else {
+            leverage_bps
+        };
+        let pnl = if pnl > u64::MAX as u128 {
+            u64::MAX as u128
+        } else {
+            pnl
+        };
+        let score = if equity == 0 { 0 } else { pnl * leverage_bps };
+
+        (pnl, score)
+    }
+
+    /// Rank up to ADL_ACCOUNTS candidates for deleveraging against a
+    /// bankrupt position
+    ///
+    /// A candidate is eligible if its slot is active (`active_mask`, bit i =
+    /// account i), it is on the opposite side of the bankrupt position and
+    /// it is in profit at the mark price. Eligible candidates are ranked by
+    /// PnL × leverage, highest first; ties and ineligible candidates keep
+    /// slot order after them, so the order does not tell which are eligible.
+    ///
+    /// Reveals only the ranking (entry k = slot of the k-th ranked account).
+    /// The computation offset and state versions are passed through so the
+    /// callback can bind the ranking to the states it was computed from.
+    #[instruction]
+    pub fn rank_adl(
+        bankrupt_position_ctxt: Enc<Mxe, Position>,
+        balances: [Enc<Mxe, MarginBalances>; ADL_ACCOUNTS],
+        positions: [Enc<Mxe, Position>; ADL_ACCOUNTS],
+        active_mask: u8,
+        mark_price: u64,
+        computation_offset: u64,
+        bankrupt_state_version: u64,
+        state_versions: [u64; ADL_ACCOUNTS],
+    ) -> ([u8; ADL_ACCOUNTS], u64, u64, [u64; ADL_ACCOUNTS]) {
+        let bankrupt = bankrupt_position_ctxt.to_arcis();
+
+        let mut scores = [0u128; ADL_ACCOUNTS];
+        for i in 0..ADL_ACCOUNTS {
+            let position = positions[i].to_arcis();
+            let (pnl, score) = adl_score(balances[i].to_arcis(), position, mark_price);
+            let active = (active_mask >> i) & 1 == 1;
+            let eligible = active && position.side != bankrupt.side && pnl > 0;
+            scores[i] = if eligible { score } else { 0 };
+        }
+
+        // Rank of slot i: slots scoring higher, plus earlier slots scoring the same
+        let mut ranking = [0u8; ADL_ACCOUNTS];
+        for i in 0..ADL_ACCOUNTS {
+            let mut rank: u8 = 0;
+            for j in 0..ADL_ACCOUNTS {
+                if scores[j] > scores[i] || (scores[j] == scores[i] && j < i) {
+                    rank += 1;
+                }
+            }
+            for k in 0..ADL_ACCOUNTS {
+                if rank as usize == k {
+                    ranking[k] = i as u8;
+                }
+            }
+        }
+
+        let mut revealed = [0u8; ADL_ACCOUNTS];
+        for k in 0..ADL_ACCOUNTS {
+            revealed[k] = ranking[k].reveal();
+        }
+
+        (
+            revealed,
+            computation_offset,
+            bankrupt_state_version,
+            state_versions,
+        )
+    }
+
+    /// Close candidates against a bankrupt position at its bankruptcy price
+    ///
+    /// Candidates come in ranking order. The bankruptcy price is the price
+    /// at which the bankrupt account's equity is zero:
+    ///     long:  (debt - collateral) * 1e6 / size
+    ///     short: (collateral - debt) * 1e6 / size (0 if debt >= collateral)
+    /// Each eligible candidate (same rule as `rank_adl`) closes as much of
+    /// its position as is still open on the bankrupt side, in order, both
+    /// sides settling the closed size at that price like a spot trade. The
+    /// closed size leaves the open interest of both sides.
+    ///
+    /// Nothing changes if the account is not bankrupt at the mark price or
+    /// a balance would overflow. Reveals nothing; the state versions are
+    /// passed through so the callback can drop results for outdated states.
+    #[instruction]
+    pub fn auto_deleverage(
+        bankrupt_balances_ctxt: Enc<Mxe, MarginBalances>,
+        bankrupt_position_ctxt: Enc<Mxe, Position>,
+        balances: [Enc<Mxe, MarginBalances>; ADL_ACCOUNTS],
+        positions: [Enc<Mxe, Position>; ADL_ACCOUNTS],
+        open_interest_ctxt: Enc<Mxe, OpenInterest>,
+        active_mask: u8,
+        mark_price: u64,
+        bankrupt_state_version: u64,
+        state_versions: [u64; ADL_ACCOUNTS],
+    ) -> (
+        Enc<Mxe, MarginBalances>,
+        Enc<Mxe, Position>,
+        [Enc<Mxe, MarginBalances>; ADL_ACCOUNTS],
+        [Enc<Mxe, Position>; ADL_ACCOUNTS],
+        Enc<Mxe, OpenInterest>,
+        u64,
+        [u64; ADL_ACCOUNTS],
+    ) {
+        let mut bankrupt_balances = bankrupt_balances_ctxt.to_arcis();
+        let mut bankrupt = bankrupt_position_ctxt.to_arcis();
+        let mut open_interest = open_interest_ctxt.to_arcis();
+
+        // Using u128 to prevent overflow
+        let held = bankrupt.size as u128;
+        let is_long = bankrupt.side == 1;
+        let value = held * mark_price as u128 / PRICE_SCALE;
+        let collateral = bankrupt_balances.collateral as u128;
+        let debt = bankrupt_balances.debt as u128;
+        let is_bankrupt = held > 0
+            && if is_long {
+                collateral + value < debt
+            } else {
+                collateral < debt + value
+            };
+
+        // Both branches are evaluated in MPC, so divisors are kept non-zero
+        let held_divisor = if held == 0 { 1 } else { held };
+        let shortfall = if is_long {
+            if debt > collateral {
+                debt - collateral
+            } else {
+                0
+            }
+        } else if collateral > debt {
+            collateral - debt
+        } else {
+            0
+        };
+        let bankruptcy_price = shortfall * PRICE_SCALE / held_divisor;
+
+        let mut held_balances = [MarginBalances {
+            collateral: 0,
+            debt: 0,
+        }; ADL_ACCOUNTS];
+        let mut held_positions = [Position {
+            size: 0,
+            side: 0,
+            entry_notional: 0,
+        }; ADL_ACCOUNTS];
+        let mut updated_balances = held_balances;
+        let mut updated_positions = held_positions;
+
+        let mut remaining = held;
+        let mut bankrupt_collateral = collateral;
+        let mut bankrupt_debt = debt;
+        let mut ok = true;
+        for i in 0..ADL_ACCOUNTS {
+            held_balances[i] = balances[i].to_arcis();
+            held_positions[i] = positions[i].to_arcis();
+            let candidate = held_balances[i];
+            let position = held_positions[i];
+
+            let (pnl, _) = adl_score(candidate, position, mark_price);
+            let active = (active_mask >> i) & 1 == 1;
+            let eligible = is_bankrupt && active && position.side != bankrupt.side && pnl > 0;
+            let size = position.size as u128;
+            let close_size = if !eligible {
+                0
+            } else if size < remaining {
+                size
+            } else {
+                remaining
+            };
+            let closed_value = close_size * bankruptcy_price / PRICE_SCALE;
+
+            // The long side receives the closed value, the short side pays it
+            let (candidate_collateral, candidate_debt) = if position.side == 1 {
+                (
+                    candidate.collateral as u128 + closed_value,
+                    candidate.debt as u128,
+                )
+            } else if closed_value <= candidate.collateral as u128 {
+                (
+                    candidate.collateral as u128 - closed_value,
+                    candidate.debt as u128,
+                )
+            } else {
+                (
+                    0,
+                    candidate.debt as u128 + closed_value - candidate.collateral as u128,
+                )
+            };
+            let (new_collateral, new_debt) = if is_long {
+                (bankrupt_collateral + closed_value, bankrupt_debt)
+            } else if closed_value <= bankrupt_collateral {
+                (bankrupt_collateral - closed_value, bankrupt_debt)
+            } else {
+                (0, bankrupt_debt + closed_value - bankrupt_collateral)
+            };
+            bankrupt_collateral = new_collateral;
+            bankrupt_debt = new_debt;
+
+            let size_divisor = if size == 0 { 1 } else { size };
+            let left = size - close_size;
+            updated_balances[i] = MarginBalances {
+                collateral: candidate_collateral as u64,
+                debt: candidate_debt as u64,
+            };
+            updated_positions[i] = Position {
+                size: left as u64,
+                side: position.side,
+                entry_notional: (position.entry_notional as u128 * left / size_divisor) as u64,
+            };
+            ok = ok
+                && candidate_collateral <= u64::MAX as u128
+                && candidate_debt <= u64::MAX as u128;
+            remaining -= close_size;
+        }
+
+        let closed = held - remaining;
+        ok = ok
+            && bankrupt_collateral <= u64::MAX as u128
+            && bankrupt_debt <= u64::MAX as u128
+            && closed <= open_interest.long as u128
+            && closed <= open_interest.short as u128;
+
+        if ok {
+            bankrupt_balances.collateral = bankrupt_collateral as u64;
+            bankrupt_balances.debt = bankrupt_debt as u64;
+            bankrupt.entry_notional =
+                (bankrupt.entry_notional as u128 * remaining / held_divisor) as u64;
+            bankrupt.size = remaining as u64;
+            open_interest.long = (open_interest.long as u128 - closed) as u64;
+            open_interest.short = (open_interest.short as u128 - closed) as u64;
+        }
+
+        let mut balance_outputs = balances;
+        let mut position_outputs = positions;
+        for i in 0..ADL_ACCOUNTS {
+            let candidate = if ok {
+                updated_balances[i]
+            } else {
+                held_balances[i]
+            };
+            let position = if ok {
+                updated_positions[i]
+            } else {
+                held_positions[i]
+            };
+            balance_outputs[i] = balance_outputs[i].owner.from_arcis(candidate);
+            position_outputs[i] = position_outputs[i].owner.from_arcis(position);
+        }
+
+        (
+            bankrupt_balances_ctxt.owner.from_arcis(bankrupt_balances),
+            bankrupt_position_ctxt.owner.from_arcis(bankrupt),
+            balance_outputs,
+            position_outputs,
+            open_interest_ctxt.owner.from_arcis(open_interest),
+            bankrupt_state_version,
+            state_versions,
+        )
+    }
+}
@@ -0,0 +1,131 @@
+use arcis_imports::*;
+
+/// Encrypted instruction aggregating the on-chain book into public depth
+/// Only coarse per-level size buckets are revealed, never individual orders
+#[encrypted]
+pub mod circuits {
+    use arcis_imports::*;
+
+    /// Price levels in a snapshot, centered on the mark price (must match the program)
+    const DEPTH_LEVELS: usize = 16;
+
+    /// Orders aggregated per computation (must match the program)
+    const BOOK_BATCH_SIZE: usize = 8;
+
+    /// Width of a price level: $1 in 1e6 fixed-point
+    const PRICE_UNIT: u64 = 1_000_000;
+
+    /// Size bucket bounds in 1e6 fixed-point: 1, 10 and 100 units
+    const BUCKET_1: u64 = 1_000_000;
+    const BUCKET_10: u64 = 10_000_000;
+    const BUCKET_100: u64 = 100_000_000;
+
+    /// Running per-level totals of visible size, encrypted for the MXE
+    /// while a snapshot is being built
+    pub struct BookDepth {
+        bids: [u64; DEPTH_LEVELS],
+        asks: [u64; DEPTH_LEVELS],
+    }
+
+    /// Order fields stored on-chain, encrypted for the MXE
+    /// Layout must match `OrderData` in the match_orders circuit
+    pub struct OrderData {
+        size: u64,
+        price: u64,
+        side: u8,
+        display_size: u64,
+        visible_size: u64,
+        stp_mode: u8,
+        owner_id: u128,
+    }
+
+    /// Size bucket of a level total:
+    /// 0 = empty, 1 = '0-1', 2 = '1-10', 3 = '10-100', 4 = '100+'
+    fn size_bucket(total: u64) -> u8 {
+        if total == 0 {
+            0
+        } else if total < BUCKET_1 {
+            1
+        } else if total < BUCKET_10 {
+            2
+        } else if total < BUCKET_100 {
+            3
+        } else {
+            4
+        }
+    }
+
+    /// Add a batch of open orders to the depth being built
+    /// Prices are rounded to the nearest $1 level; level `i` is
+    /// `base_level + i` dollars. Orders outside the window are ignored, and
+    /// only the visible part of icebergs is counted.
+    ///
+    /// `reset` starts a new snapshot (the incoming depth is discarded).
+    /// When `finalize` is set, the size bucket of every level is revealed;
+    /// otherwise the revealed buckets are all zero. The computation offset is
+    /// passed through so the callback can drop results of a superseded batch.
+    #[instruction]
+    pub fn aggregate_book(
+        depth_ctxt: Enc<Mxe, BookDepth>,
+        orders: [Enc<Mxe, OrderData>; BOOK_BATCH_SIZE],
+        active_mask: u8,
+        base_level: u64,
+        reset: bool,
+        finalize: bool,
+        computation_offset: u64,
+    ) -> (
+        Enc<Mxe, BookDepth>,
+        [u8; DEPTH_LEVELS],
+        [u8; DEPTH_LEVELS],
+        u64,
+    ) {
+        let mut depth = depth_ctxt.to_arcis();
+
+        if reset {
+            for level in 0..DEPTH_LEVELS {
+                depth.bids[level] = 0;
+                depth.asks[level] = 0;
+            }
+        }
+
+        for i in 0..BOOK_BATCH_SIZE {
+            let order = orders[i].to_arcis();
+            let active = (active_mask >> i) & 1 == 1;
+            let price_level = (order.price + PRICE_UNIT / 2) / PRICE_UNIT;
+
+            for level in 0..DEPTH_LEVELS {
+                let hit = active && price_level == base_level + level as u64;
+                if hit && order.side == 1 {
+                    depth.bids[level] += order.visible_size;
+                }
+                if hit && order.side == 0 {
+                    depth.asks[level] += order.visible_size;
+                }
+            }
+        }
+
+        let mut bid_buckets = [0u8; DEPTH_LEVELS];
+        let mut ask_buckets = [0u8; DEPTH_LEVELS];
+        for level in 0..DEPTH_LEVELS {
+            let bid = if finalize {
+                size_bucket(depth.bids[level])
+            } else {
+                0
+            };
+            let ask = if finalize {
+                size_bucket(depth.asks[level])
+            } else {
+                0
+            };
+            bid_buckets[level] = bid.reveal();
+            ask_buckets[level] = ask.reveal();
+        }
+
+        (
+            depth_ctxt.owner.from_arcis(depth),
+            bid_buckets,
+            ask_buckets,
+            computation_offset,
+        )
+    }
+}
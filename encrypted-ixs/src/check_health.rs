@@ -1,53 +1,162 @@
 use arcis_imports::*;
 
-/// Encrypted instruction for private health check (LTV calculation)
+/// Encrypted instruction for private health check (maintenance margin)
 /// This is CRITICAL for liquidations without revealing user's exact collateral/debt
 #[encrypted]
 pub mod circuits {
     use arcis_imports::*;
 
-    /// Liquidation threshold: 150% (collateral must be 1.5x debt)
-    /// If LTV > 150%, position is liquidatable
-    const LIQUIDATION_THRESHOLD: u64 = 150;
+    /// Fixed-point scale shared by prices and sizes (1e6)
+    const PRICE_SCALE: u128 = 1_000_000;
 
-    /// Input values for health check calculation
-    pub struct HealthCheckInput {
-        /// Total collateral value (in smallest units, e.g., wZEC lamports)
+    /// Margin ratios are expressed in basis points
+    const BPS_DENOMINATOR: u128 = 10_000;
+
+    /// Encrypted balances stored on a margin account
+    /// Ciphertext order matches `encrypted_collateral`, `encrypted_debt`
+    pub struct MarginBalances {
         collateral: u64,
-        /// Total debt value (in smallest units)
         debt: u64,
-        /// Oracle price (scaled by 10^8 for precision)
-        /// For simplicity, we assume collateral is already in USD terms
-        /// In production, multiply collateral by price
-        price: u64,
     }
 
-    /// Check if position is liquidatable
-    /// Returns encrypted boolean: true = liquidatable, false = healthy
-    /// 
-    /// Formula: LTV = (debt * 100) / collateral
-    /// If LTV > LIQUIDATION_THRESHOLD (150%), position is underwater
-    #[instruction]
-    pub fn check_health(
-        input_ctxt: Enc<Shared, HealthCheckInput>,
-    ) -> Enc<Shared, bool> {
-        let input = input_ctxt.to_arcis();
-        
-        // Calculate LTV ratio: (debt * 100) / collateral
-        // Handle edge cases:
-        // - No debt = healthy (LTV = 0)
-        // - No collateral but has debt = liquidatable (LTV = infinity)
-        let is_liquidatable = if input.debt == 0 {
-            false
-        } else if input.collateral == 0 {
-            true
+    /// Encrypted net position stored on a margin account
+    /// Layout must match `Position` in the settle_trade circuit
+    pub struct Position {
+        size: u64,
+        side: u8,
+        entry_notional: u64,
+    }
+
+    /// Margin accounts checked per sweep (must match the program)
+    const HEALTH_SWEEP_ACCOUNTS: usize = 8;
+
+    /// Health of one margin account: (liquidatable, margin ratio in bps)
+    ///
+    /// equity      = collateral - debt +/- size * mark_price (long / short)
+    /// requirement = size * mark_price * maintenance_margin_bps / 10_000
+    /// The account is liquidatable when equity < requirement.
+    ///
+    /// The margin ratio is equity over position value, in bps; 0 when equity
+    /// is negative, u64::MAX with no position.
+    fn account_health(
+        balances: MarginBalances,
+        position: Position,
+        mark_price: u64,
+        maintenance_margin_bps: u64,
+    ) -> (bool, u64) {
+        // Using u128 to prevent overflow
+        let position_value = position.size as u128 * mark_price as u128 / PRICE_SCALE;
+        let (assets, liabilities) = if position.side == 1 {
+            (
+                balances.collateral as u128 + position_value,
+                balances.debt as u128,
+            )
         } else {
-            // Using u128 to prevent overflow
-            let debt_scaled = input.debt as u128 * 100u128;
-            let ltv = (debt_scaled / input.collateral as u128) as u64;
-            ltv > LIQUIDATION_THRESHOLD
+            (
+                balances.collateral as u128,
+                balances.debt as u128 + position_value,
+            )
         };
-        
-        input_ctxt.owner.from_arcis(is_liquidatable)
+        let requirement = position_value * maintenance_margin_bps as u128 / BPS_DENOMINATOR;
+
+        let is_liquidatable = assets < liabilities + requirement;
+
+        let equity = if assets > liabilities {
+            assets - liabilities
+        } else {
+            0
+        };
+        // Both branches are evaluated in MPC, so the divisor is kept non-zero
+        let divisor = if position_value == 0 {
+            1
+        } else {
+            position_value
+        };
+        let ratio = equity * BPS_DENOMINATOR / divisor;
+        let margin_ratio = if position_value == 0 || ratio > u64::MAX as u128 {
+            u64::MAX
+        } else {
+            ratio as u64
+        };
+
+        (is_liquidatable, margin_ratio)
+    }
+
+    /// Check the margin account against the market's maintenance margin
+    /// Reads the stored balances and net position; the position is valued
+    /// at the oracle mark price (1e6 fixed-point), which is public.
+    ///
+    /// Reveals only the liquidatable bit. The margin ratio is encrypted for
+    /// the owner's viewing key; without a registered key (`has_viewing_key`
+    /// unset) the shared secret is public, so zero is encrypted instead.
+    /// The computation offset, slot and account
+    /// state version the check was queued at are passed through so the
+    /// callback can bind the result to them.
+    #[instruction]
+    pub fn check_health(
+        balances_ctxt: Enc<Mxe, MarginBalances>,
+        position_ctxt: Enc<Mxe, Position>,
+        owner: Shared,
+        has_viewing_key: bool,
+        mark_price: u64,
+        maintenance_margin_bps: u64,
+        computation_offset: u64,
+        check_slot: u64,
+        state_version: u64,
+    ) -> (bool, Enc<Shared, u64>, u64, u64, u64) {
+        let (is_liquidatable, margin_ratio) = account_health(
+            balances_ctxt.to_arcis(),
+            position_ctxt.to_arcis(),
+            mark_price,
+            maintenance_margin_bps,
+        );
+        let margin_ratio = if has_viewing_key { margin_ratio } else { 0 };
+
+        (
+            is_liquidatable.reveal(),
+            owner.from_arcis(margin_ratio),
+            computation_offset,
+            check_slot,
+            state_version,
+        )
+    }
+
+    /// Check up to HEALTH_SWEEP_ACCOUNTS margin accounts in one computation
+    /// Same rule as `check_health`. `active_mask` marks which slots are real
+    /// (bit i = account i). Only the bitmap of liquidatable accounts is
+    /// revealed (bit i set = account i is liquidatable); the per-account
+    /// state versions are passed through like in `check_health`.
+    #[instruction]
+    pub fn check_health_batch(
+        balances: [Enc<Mxe, MarginBalances>; HEALTH_SWEEP_ACCOUNTS],
+        positions: [Enc<Mxe, Position>; HEALTH_SWEEP_ACCOUNTS],
+        active_mask: u8,
+        mark_price: u64,
+        maintenance_margin_bps: u64,
+        computation_offset: u64,
+        check_slot: u64,
+        state_versions: [u64; HEALTH_SWEEP_ACCOUNTS],
+    ) -> (u8, u64, u64, [u64; HEALTH_SWEEP_ACCOUNTS]) {
+        let mut bitmap: u8 = 0;
+
+        for i in 0..HEALTH_SWEEP_ACCOUNTS {
+            let (is_liquidatable, _) = account_health(
+                balances[i].to_arcis(),
+                positions[i].to_arcis(),
+                mark_price,
+                maintenance_margin_bps,
+            );
+            let active = (active_mask >> i) & 1 == 1;
+            if active && is_liquidatable {
+                bitmap |= 1 << i;
+            }
+        }
+
+        (
+            bitmap.reveal(),
+            computation_offset,
+            check_slot,
+            state_versions,
+        )
     }
 }
@@ -1,7 +1,21 @@
+pub mod adl;
+pub mod book_snapshot;
 pub mod check_health;
+pub mod liquidation;
+pub mod match_orders;
+pub mod rfq;
 pub mod submit_order;
+pub mod trigger_orders;
+pub mod twap;
 pub mod update_balance;
 
+pub use adl::*;
+pub use book_snapshot::*;
 pub use check_health::*;
+pub use liquidation::*;
+pub use match_orders::*;
+pub use rfq::*;
 pub use submit_order::*;
+pub use trigger_orders::*;
+pub use twap::*;
 pub use update_balance::*;
@@ -0,0 +1,303 @@
+use arcis_imports::*;
+
+/// Encrypted instructions for liquidating unhealthy margin accounts
+/// The size closed and the collateral seized are computed privately from
+/// the stored balances and position; only the seized amount and, for a
+/// bankrupt account, how its bad debt is covered are revealed.
+#[encrypted]
+pub mod circuits {
+    use arcis_imports::*;
+
+    /// Fixed-point scale shared by prices and sizes (1e6)
+    const PRICE_SCALE: u128 = 1_000_000;
+
+    /// Margin ratios and penalties are expressed in basis points
+    const BPS_DENOMINATOR: u128 = 10_000;
+
+    /// Encrypted balances stored on a margin account
+    /// Ciphertext order matches `encrypted_collateral`, `encrypted_debt`
+    pub struct MarginBalances {
+        collateral: u64,
+        debt: u64,
+    }
+
+    /// Encrypted net position stored on a margin account
+    /// Layout must match `Position` in the settle_trade circuit
+    pub struct Position {
+        size: u64,
+        side: u8,
+        entry_notional: u64,
+    }
+
+    /// Encrypted open interest per side
+    /// Layout must match `OpenInterest` in the settle_trade circuit
+    pub struct OpenInterest {
+        long: u64,
+        short: u64,
+    }
+
+    /// Public risk parameters of the market
+    pub struct RiskParams {
+        maintenance_margin_bps: u64,
+        liquidation_penalty_bps: u64,
+    }
+
+    /// Close part of a position at the mark price and seize a penalty
+    ///
+    /// Closing at the mark price leaves equity unchanged and lowers the
+    /// requirement, while the penalty (liquidation_penalty_bps of the closed
+    /// value) lowers equity. The closed value is the smallest v with
+    ///     equity - v * penalty_bps >= (value - v) * maintenance_margin_bps
+    /// i.e. v >= (value * mm_bps - equity * 10_000) / (mm_bps - penalty_bps),
+    /// rounded up to whole size units. If that exceeds the position, or the
+    /// account has no equity left, the whole position is closed.
+    ///
+    /// The penalty is taken from collateral and capped at what is left of it;
+    /// a shortfall from closing a short is carried as debt. The closed size
+    /// is removed from the open interest.
+    ///
+    /// If the whole position is closed and debt still exceeds collateral, the
+    /// account is bankrupt: nothing is seized, both balances are written off
+    /// and the bad debt is covered from the insurance fund first (up to
+    /// `insurance_available`, the fund's uncommitted public balance). The remainder is
+    /// socialized across the opposite side as a loss per unit of size
+    /// (1e6 fixed-point), rounded up, to be added to that side's loss index.
+    ///
+    /// If the fund cannot cover the bad debt and no auto-deleveraging round
+    /// has run against the account's current state (`adl_completed`), the
+    /// account is left unchanged and only `needs_adl` is set, so the
+    /// opposite side is deleveraged before any loss is socialized.
+    ///
+    /// An account that is not liquidatable at the mark price is left
+    /// unchanged with nothing seized. Reveals the seized amount, the insurance
+    /// draw, the loss index increment, whether it applies to longs and
+    /// whether the account needs auto-deleveraging; the
+    /// account's state version is passed through so the callback can drop
+    /// results for an outdated state.
+    #[instruction]
+    pub fn liquidate_position(
+        balances_ctxt: Enc<Mxe, MarginBalances>,
+        position_ctxt: Enc<Mxe, Position>,
+        open_interest_ctxt: Enc<Mxe, OpenInterest>,
+        mark_price: u64,
+        params: RiskParams,
+        insurance_available: u64,
+        adl_completed: bool,
+        state_version: u64,
+    ) -> (
+        Enc<Mxe, MarginBalances>,
+        Enc<Mxe, Position>,
+        Enc<Mxe, OpenInterest>,
+        u64,
+        u64,
+        u64,
+        bool,
+        bool,
+        u64,
+    ) {
+        let mut balances = balances_ctxt.to_arcis();
+        let mut position = position_ctxt.to_arcis();
+        let mut open_interest = open_interest_ctxt.to_arcis();
+
+        // Using u128 to prevent overflow
+        let held = position.size as u128;
+        let mark = mark_price as u128;
+        let is_long = position.side == 1;
+        let position_value = held * mark / PRICE_SCALE;
+        let (assets, liabilities) = if is_long {
+            (
+                balances.collateral as u128 + position_value,
+                balances.debt as u128,
+            )
+        } else {
+            (
+                balances.collateral as u128,
+                balances.debt as u128 + position_value,
+            )
+        };
+        let mm_bps = params.maintenance_margin_bps as u128;
+        let penalty_bps = params.liquidation_penalty_bps as u128;
+        let requirement = position_value * mm_bps / BPS_DENOMINATOR;
+        let is_liquidatable = held > 0 && assets < liabilities + requirement;
+
+        let equity = if assets > liabilities {
+            assets - liabilities
+        } else {
+            0
+        };
+
+        // Both branches are evaluated in MPC, so divisors are kept non-zero
+        let margin_gap = if mm_bps > penalty_bps {
+            mm_bps - penalty_bps
+        } else {
+            1
+        };
+        let mark_divisor = if mark == 0 { 1 } else { mark };
+        let target = position_value * mm_bps;
+        let cushion = equity * BPS_DENOMINATOR;
+        let shortfall = if target > cushion {
+            target - cushion
+        } else {
+            0
+        };
+        let close_value = (shortfall + margin_gap - 1) / margin_gap;
+        let close_size = (close_value * PRICE_SCALE + mark_divisor - 1) / mark_divisor;
+        let close_size = if equity == 0 || close_size > held {
+            held
+        } else {
+            close_size
+        };
+        let closed_value = close_size * mark / PRICE_SCALE;
+        let penalty = closed_value * penalty_bps / BPS_DENOMINATOR;
+
+        // Closing a long releases its value into collateral, closing a
+        // short pays it out of collateral (the shortfall becomes debt)
+        let (collateral, debt) = if is_long {
+            (
+                balances.collateral as u128 + closed_value,
+                balances.debt as u128,
+            )
+        } else if closed_value <= balances.collateral as u128 {
+            (
+                balances.collateral as u128 - closed_value,
+                balances.debt as u128,
+            )
+        } else {
+            (
+                0,
+                balances.debt as u128 + closed_value - balances.collateral as u128,
+            )
+        };
+        let held_divisor = if held == 0 { 1 } else { held };
+        let remaining = held - close_size;
+        let entry_notional = position.entry_notional as u128 * remaining / held_divisor;
+
+        // Bankrupt: nothing left to seize, the shortfall is written off
+        let is_bankrupt = remaining == 0 && debt > collateral;
+        let bad_debt = if is_bankrupt { debt - collateral } else { 0 };
+        let seized = if is_bankrupt {
+            0
+        } else if penalty > collateral {
+            collateral
+        } else {
+            penalty
+        };
+
+        let available = insurance_available as u128;
+        let insurance_draw = if bad_debt > available {
+            available
+        } else {
+            bad_debt
+        };
+        let socialized = bad_debt - insurance_draw;
+        let opposite_open_interest = if is_long {
+            open_interest.short as u128
+        } else {
+            open_interest.long as u128
+        };
+        let opposite_divisor = if opposite_open_interest == 0 {
+            1
+        } else {
+            opposite_open_interest
+        };
+        let loss_per_unit = if opposite_open_interest == 0 {
+            0
+        } else {
+            (socialized * PRICE_SCALE + opposite_divisor - 1) / opposite_divisor
+        };
+
+        let own_open_interest = if is_long {
+            open_interest.long as u128
+        } else {
+            open_interest.short as u128
+        };
+        let (long_open_interest, short_open_interest) = if is_long {
+            (own_open_interest - close_size, open_interest.short as u128)
+        } else {
+            (open_interest.long as u128, own_open_interest - close_size)
+        };
+
+        let ok = collateral <= u64::MAX as u128
+            && debt <= u64::MAX as u128
+            && loss_per_unit <= u64::MAX as u128
+            && close_size <= own_open_interest;
+        let needs_adl = is_liquidatable && ok && bad_debt > available && !adl_completed;
+        let apply = is_liquidatable && ok && !needs_adl;
+        if apply {
+            balances.collateral = if is_bankrupt {
+                0
+            } else {
+                (collateral - seized) as u64
+            };
+            balances.debt = if is_bankrupt { 0 } else { debt as u64 };
+            position.size = remaining as u64;
+            position.entry_notional = entry_notional as u64;
+            open_interest.long = long_open_interest as u64;
+            open_interest.short = short_open_interest as u64;
+        }
+        let seized = if apply { seized as u64 } else { 0 };
+        let insurance_draw = if apply { insurance_draw as u64 } else { 0 };
+        let loss_per_unit = if apply { loss_per_unit as u64 } else { 0 };
+        // Longs absorb the loss of a bankrupt short and vice versa
+        let loss_on_longs = apply && is_bankrupt && !is_long;
+
+        (
+            balances_ctxt.owner.from_arcis(balances),
+            position_ctxt.owner.from_arcis(position),
+            open_interest_ctxt.owner.from_arcis(open_interest),
+            seized.reveal(),
+            insurance_draw.reveal(),
+            loss_per_unit.reveal(),
+            loss_on_longs.reveal(),
+            needs_adl.reveal(),
+            state_version,
+        )
+    }
+
+    /// Charge an account its share of socialized losses
+    ///
+    /// `long_loss_delta` / `short_loss_delta` are how far the market's loss
+    /// indexes moved since the account last had them applied (loss per unit
+    /// of size, 1e6 fixed-point). The delta of the account's own side is
+    /// charged to collateral; what collateral cannot cover is carried as
+    /// debt. The side stays private. The index values and the account's state
+    /// version are passed through so the callback can record them.
+    #[instruction]
+    pub fn apply_loss_index(
+        balances_ctxt: Enc<Mxe, MarginBalances>,
+        position_ctxt: Enc<Mxe, Position>,
+        long_loss_delta: u64,
+        short_loss_delta: u64,
+        long_loss_index: u64,
+        short_loss_index: u64,
+        state_version: u64,
+    ) -> (Enc<Mxe, MarginBalances>, u64, u64, u64) {
+        let mut balances = balances_ctxt.to_arcis();
+        let position = position_ctxt.to_arcis();
+
+        let delta = if position.side == 1 {
+            long_loss_delta as u128
+        } else {
+            short_loss_delta as u128
+        };
+        let loss = position.size as u128 * delta / PRICE_SCALE;
+
+        let collateral = balances.collateral as u128;
+        let (new_collateral, new_debt) = if loss <= collateral {
+            (collateral - loss, balances.debt as u128)
+        } else {
+            (0, balances.debt as u128 + loss - collateral)
+        };
+        if new_debt <= u64::MAX as u128 {
+            balances.collateral = new_collateral as u64;
+            balances.debt = new_debt as u64;
+        }
+
+        (
+            balances_ctxt.owner.from_arcis(balances),
+            long_loss_index,
+            short_loss_index,
+            state_version,
+        )
+    }
+}
@@ -0,0 +1,201 @@
+use arcis_imports::*;
+
+/// Encrypted instruction for matching a buy order against a sell order
+/// Both orders are stored on-chain as Enc<Mxe, OrderData>; the resulting
+/// fill (price and size) stays encrypted and is consumed by settlement
+#[encrypted]
+pub mod circuits {
+    use arcis_imports::*;
+
+    /// Self-trade prevention modes, taken from the taker (newer) order
+    const STP_CANCEL_NEWEST: u8 = 0;
+    const STP_CANCEL_OLDEST: u8 = 1;
+    const STP_CANCEL_BOTH: u8 = 2;
+    const STP_DECREMENT: u8 = 3;
+
+    /// Order fields stored on-chain, encrypted for the MXE
+    #[derive(Clone, Copy)]
+    pub struct OrderData {
+        /// Remaining size in smallest units
+        size: u64,
+        /// Limit price in smallest units
+        price: u64,
+        /// Side: 1 = long/buy, 0 = short/sell
+        side: u8,
+        /// Iceberg peak: size shown per slice (0 = display the full size)
+        display_size: u64,
+        /// Currently visible slice; matching never fills beyond it
+        visible_size: u64,
+        /// Self-trade prevention mode (see STP_* constants)
+        stp_mode: u8,
+        /// Identifier of the owning margin account
+        owner_id: u128,
+    }
+
+    /// Encrypted fill produced by a match
+    pub struct Fill {
+        /// Execution price (the maker's limit price)
+        price: u64,
+        /// Executed size
+        size: u64,
+    }
+
+    /// Visible slice after a fill: once the current slice is used up,
+    /// reveal the next one (bounded by what is left of the order)
+    fn refresh_visible(order: OrderData) -> u64 {
+        if order.visible_size > 0 {
+            order.visible_size
+        } else if order.display_size == 0 || order.display_size > order.size {
+            order.size
+        } else {
+            order.display_size
+        }
+    }
+
+    /// Match a buy order against a sell order
+    /// Fills are bounded by each order's visible slice, so iceberg orders
+    /// trade one slice at a time and their total size never leaves MPC.
+    /// `buy_is_maker` is public: the older order sets the execution price
+    ///
+    /// Orders from the same owner never fill against each other: the taker's
+    /// self-trade prevention mode cancels the newest, the oldest or both
+    /// orders, or decrements both by the smaller remaining size
+    ///
+    /// Returns both orders with their remaining size, the encrypted fill,
+    /// and revealed flags: whether the orders crossed, whether each order
+    /// has no size left, and whether self-trade prevention kicked in
+    #[instruction]
+    pub fn match_orders(
+        buy_ctxt: Enc<Mxe, OrderData>,
+        sell_ctxt: Enc<Mxe, OrderData>,
+        buy_is_maker: bool,
+    ) -> (
+        Enc<Mxe, OrderData>,
+        Enc<Mxe, OrderData>,
+        Enc<Mxe, Fill>,
+        bool,
+        bool,
+        bool,
+        bool,
+    ) {
+        let mut buy = buy_ctxt.to_arcis();
+        let mut sell = sell_ctxt.to_arcis();
+
+        let would_cross = buy.side == 1
+            && sell.side == 0
+            && buy.visible_size > 0
+            && sell.visible_size > 0
+            && buy.price >= sell.price;
+        let self_trade = would_cross && buy.owner_id == sell.owner_id;
+        let crossed = would_cross && !self_trade;
+
+        let fill_size = if !crossed {
+            0
+        } else if buy.visible_size < sell.visible_size {
+            buy.visible_size
+        } else {
+            sell.visible_size
+        };
+        let fill_price = if buy_is_maker { buy.price } else { sell.price };
+
+        buy.size -= fill_size;
+        buy.visible_size -= fill_size;
+        sell.size -= fill_size;
+        sell.visible_size -= fill_size;
+
+        // Self-trade prevention, driven by the taker's mode
+        let taker_mode = if buy_is_maker {
+            sell.stp_mode
+        } else {
+            buy.stp_mode
+        };
+        let cancel_maker =
+            self_trade && (taker_mode == STP_CANCEL_OLDEST || taker_mode == STP_CANCEL_BOTH);
+        let cancel_taker =
+            self_trade && (taker_mode == STP_CANCEL_NEWEST || taker_mode == STP_CANCEL_BOTH);
+        let cancel_buy = if buy_is_maker {
+            cancel_maker
+        } else {
+            cancel_taker
+        };
+        let cancel_sell = if buy_is_maker {
+            cancel_taker
+        } else {
+            cancel_maker
+        };
+        let decrement = if self_trade && taker_mode == STP_DECREMENT {
+            if buy.size < sell.size {
+                buy.size
+            } else {
+                sell.size
+            }
+        } else {
+            0
+        };
+
+        buy.size = if cancel_buy { 0 } else { buy.size - decrement };
+        sell.size = if cancel_sell {
+            0
+        } else {
+            sell.size - decrement
+        };
+        if buy.visible_size > buy.size {
+            buy.visible_size = buy.size;
+        }
+        if sell.visible_size > sell.size {
+            sell.visible_size = sell.size;
+        }
+
+        buy.visible_size = refresh_visible(buy);
+        sell.visible_size = refresh_visible(sell);
+
+        let fill = Fill {
+            price: fill_price,
+            size: fill_size,
+        };
+
+        (
+            buy_ctxt.owner.from_arcis(buy),
+            sell_ctxt.owner.from_arcis(sell),
+            Mxe::get().from_arcis(fill),
+            crossed.reveal(),
+            (buy.size == 0).reveal(),
+            (sell.size == 0).reveal(),
+            self_trade.reveal(),
+        )
+    }
+
+    /// Give a fill's size back to an order: to its remaining size, and to
+    /// its visible slice up to the iceberg peak
+    fn return_size(order: OrderData, size: u64) -> OrderData {
+        let mut order = order;
+        order.size += size;
+        let visible = order.visible_size + size;
+        let peak = if order.display_size == 0 {
+            order.size
+        } else {
+            order.display_size
+        };
+        order.visible_size = if visible > peak { peak } else { visible };
+        order
+    }
+
+    /// Return the size of a fill whose settlement failed to both orders
+    /// `match_orders` took the size out of both orders when it produced the
+    /// fill; nothing is revealed.
+    #[instruction]
+    pub fn return_fill(
+        buy_ctxt: Enc<Mxe, OrderData>,
+        sell_ctxt: Enc<Mxe, OrderData>,
+        fill_ctxt: Enc<Mxe, Fill>,
+    ) -> (Enc<Mxe, OrderData>, Enc<Mxe, OrderData>) {
+        let fill = fill_ctxt.to_arcis();
+        let buy = return_size(buy_ctxt.to_arcis(), fill.size);
+        let sell = return_size(sell_ctxt.to_arcis(), fill.size);
+
+        (
+            buy_ctxt.owner.from_arcis(buy),
+            sell_ctxt.owner.from_arcis(sell),
+        )
+    }
+}
@@ -0,0 +1,300 @@
+use arcis_imports::*;
+
+/// Encrypted instructions for private RFQ / block trades
+/// The requester's size, side and limit price and the responder's quote
+/// never leave MPC; the block settles straight into both margin accounts
+#[encrypted]
+pub mod circuits {
+    use arcis_imports::*;
+
+    /// Fixed-point scale shared by prices and sizes (1e6)
+    const PRICE_SCALE: u128 = 1_000_000;
+
+    /// Margin ratios are expressed in basis points
+    const BPS_DENOMINATOR: u128 = 10_000;
+
+    /// Quote request encrypted client-side by the requester
+    #[derive(Clone, Copy)]
+    pub struct RfqInput {
+        /// Block size in smallest units
+        size: u64,
+        /// Side: 1 = requester buys, 0 = requester sells
+        side: u8,
+        /// Worst price the requester accepts
+        limit_price: u64,
+    }
+
+    /// Quote encrypted client-side by the responder
+    pub struct QuoteInput {
+        /// Price the responder trades the full block at
+        price: u64,
+    }
+
+    /// Encrypted balances stored on a margin account
+    /// Ciphertext order matches `encrypted_collateral`, `encrypted_debt`
+    pub struct MarginBalances {
+        collateral: u64,
+        debt: u64,
+    }
+
+    /// Encrypted net position stored on a margin account
+    /// Layout must match `Position` in the settle_trade circuit
+    #[derive(Clone, Copy)]
+    pub struct Position {
+        size: u64,
+        side: u8,
+        entry_notional: u64,
+    }
+
+    /// Encrypted open interest per side
+    /// Layout must match `OpenInterest` in the settle_trade circuit
+    #[derive(Clone, Copy)]
+    pub struct OpenInterest {
+        long: u64,
+        short: u64,
+    }
+
+    /// Apply one side of a block to a net position; false on overflow
+    /// Same rules as `apply_fill` in the settle_trade circuit
+    fn apply_fill(position: Position, is_buy: bool, size: u64, notional: u128) -> (Position, bool) {
+        let side: u8 = if is_buy { 1 } else { 0 };
+        let held = position.size as u128;
+        let traded = size as u128;
+        // Both branches are evaluated in MPC, so divisors are kept non-zero
+        let held_divisor = if held == 0 { 1 } else { held };
+        let traded_divisor = if traded == 0 { 1 } else { traded };
+
+        let (new_side, new_size, new_entry) = if held == 0 || position.side == side {
+            (
+                side,
+                held + traded,
+                position.entry_notional as u128 + notional,
+            )
+        } else if traded <= held {
+            (
+                position.side,
+                held - traded,
+                position.entry_notional as u128 * (held - traded) / held_divisor,
+            )
+        } else {
+            (
+                side,
+                traded - held,
+                notional * (traded - held) / traded_divisor,
+            )
+        };
+
+        let ok = new_size <= u64::MAX as u128 && new_entry <= u64::MAX as u128;
+        let updated = if ok {
+            Position {
+                size: new_size as u64,
+                side: new_side,
+                entry_notional: new_entry as u64,
+            }
+        } else {
+            position
+        };
+        (updated, ok)
+    }
+
+    /// Whether an account meets the maintenance margin at the mark price
+    /// Same rule as `account_health` in the check_health circuit
+    fn meets_margin(
+        collateral: u128,
+        debt: u128,
+        position: Position,
+        mark_price: u64,
+        maintenance_margin_bps: u64,
+    ) -> bool {
+        let position_value = position.size as u128 * mark_price as u128 / PRICE_SCALE;
+        let (assets, liabilities) = if position.side == 1 {
+            (collateral + position_value, debt)
+        } else {
+            (collateral, debt + position_value)
+        };
+        let requirement = position_value * maintenance_margin_bps as u128 / BPS_DENOMINATOR;
+        assets >= liabilities + requirement
+    }
+
+    /// Move one account's contribution to open interest; false on overflow
+    /// Same rules as `shift_open_interest` in the settle_trade circuit
+    fn shift_open_interest(
+        open_interest: OpenInterest,
+        before: Position,
+        after: Position,
+    ) -> (OpenInterest, bool) {
+        let (before_long, before_short) = if before.side == 1 {
+            (before.size as u128, 0)
+        } else {
+            (0, before.size as u128)
+        };
+        let (after_long, after_short) = if after.side == 1 {
+            (after.size as u128, 0)
+        } else {
+            (0, after.size as u128)
+        };
+        let long = open_interest.long as u128 + after_long;
+        let short = open_interest.short as u128 + after_short;
+
+        let ok = before_long <= long
+            && before_short <= short
+            && long - before_long <= u64::MAX as u128
+            && short - before_short <= u64::MAX as u128;
+        let updated = if ok {
+            OpenInterest {
+                long: (long - before_long) as u64,
+                short: (short - before_short) as u64,
+            }
+        } else {
+            open_interest
+        };
+        (updated, ok)
+    }
+
+    /// Post a quote request
+    /// Re-encrypts the request for the MXE so it can be settled later
+    /// without the requester. Only the validity bit is revealed.
+    #[instruction]
+    pub fn request_quote(request_ctxt: Enc<Shared, RfqInput>) -> (Enc<Mxe, RfqInput>, bool) {
+        let request = request_ctxt.to_arcis();
+
+        let valid = request.size > 0 && request.side <= 1 && request.limit_price > 0;
+
+        (Mxe::get().from_arcis(request), valid.reveal())
+    }
+
+    /// Settle a block trade against a quote request
+    /// The quote must be within the requester's limit, the buying side must
+    /// cover the notional, and both counterparties must meet the maintenance
+    /// margin on their post-trade balances and positions at the mark price.
+    /// Balances, net positions and the open interest are only moved if all
+    /// hold; only the success bit is revealed. Both
+    /// accounts' state versions are passed through so the callback can drop
+    /// results for an outdated state.
+    #[instruction]
+    pub fn settle_block_trade(
+        request_ctxt: Enc<Mxe, RfqInput>,
+        quote_ctxt: Enc<Shared, QuoteInput>,
+        requester_ctxt: Enc<Mxe, MarginBalances>,
+        responder_ctxt: Enc<Mxe, MarginBalances>,
+        requester_position_ctxt: Enc<Mxe, Position>,
+        responder_position_ctxt: Enc<Mxe, Position>,
+        open_interest_ctxt: Enc<Mxe, OpenInterest>,
+        mark_price: u64,
+        maintenance_margin_bps: u64,
+        requester_state_version: u64,
+        responder_state_version: u64,
+    ) -> (
+        Enc<Mxe, MarginBalances>,
+        Enc<Mxe, MarginBalances>,
+        Enc<Mxe, Position>,
+        Enc<Mxe, Position>,
+        Enc<Mxe, OpenInterest>,
+        bool,
+        u64,
+        u64,
+    ) {
+        let request = request_ctxt.to_arcis();
+        let quote = quote_ctxt.to_arcis();
+        let mut requester = requester_ctxt.to_arcis();
+        let mut responder = responder_ctxt.to_arcis();
+        let mut requester_position = requester_position_ctxt.to_arcis();
+        let mut responder_position = responder_position_ctxt.to_arcis();
+        let mut open_interest = open_interest_ctxt.to_arcis();
+
+        let requester_buys = request.side == 1;
+        let within_limit = if requester_buys {
+            quote.price <= request.limit_price
+        } else {
+            quote.price >= request.limit_price
+        };
+
+        // Notional: (price * size) / 1e6, computed in u128 to prevent overflow
+        let notional = quote.price as u128 * request.size as u128 / PRICE_SCALE;
+
+        let (buyer_collateral, seller_collateral) = if requester_buys {
+            (requester.collateral, responder.collateral)
+        } else {
+            (responder.collateral, requester.collateral)
+        };
+
+        let (new_requester_position, requester_position_ok) =
+            apply_fill(requester_position, requester_buys, request.size, notional);
+        let (new_responder_position, responder_position_ok) =
+            apply_fill(responder_position, !requester_buys, request.size, notional);
+        let (shifted, requester_open_interest_ok) =
+            shift_open_interest(open_interest, requester_position, new_requester_position);
+        let (new_open_interest, responder_open_interest_ok) =
+            shift_open_interest(shifted, responder_position, new_responder_position);
+
+        // Post-trade collateral: the buyer pays the notional, the seller receives it
+        let (requester_collateral, responder_collateral) = if requester_buys {
+            (
+                if notional <= requester.collateral as u128 {
+                    requester.collateral as u128 - notional
+                } else {
+                    0
+                },
+                responder.collateral as u128 + notional,
+            )
+        } else {
+            (
+                requester.collateral as u128 + notional,
+                if notional <= responder.collateral as u128 {
+                    responder.collateral as u128 - notional
+                } else {
+                    0
+                },
+            )
+        };
+        let requester_margin_ok = meets_margin(
+            requester_collateral,
+            requester.debt as u128,
+            new_requester_position,
+            mark_price,
+            maintenance_margin_bps,
+        );
+        let responder_margin_ok = meets_margin(
+            responder_collateral,
+            responder.debt as u128,
+            new_responder_position,
+            mark_price,
+            maintenance_margin_bps,
+        );
+
+        let success = quote.price > 0
+            && within_limit
+            && notional <= buyer_collateral as u128
+            && seller_collateral as u128 + notional <= u64::MAX as u128
+            && requester_position_ok
+            && responder_position_ok
+            && requester_open_interest_ok
+            && responder_open_interest_ok
+            && requester_margin_ok
+            && responder_margin_ok;
+
+        if success {
+            requester_position = new_requester_position;
+            responder_position = new_responder_position;
+            open_interest = new_open_interest;
+            if requester_buys {
+                requester.collateral -= notional as u64;
+                responder.collateral += notional as u64;
+            } else {
+                responder.collateral -= notional as u64;
+                requester.collateral += notional as u64;
+            }
+        }
+
+        (
+            requester_ctxt.owner.from_arcis(requester),
+            responder_ctxt.owner.from_arcis(responder),
+            requester_position_ctxt.owner.from_arcis(requester_position),
+            responder_position_ctxt.owner.from_arcis(responder_position),
+            open_interest_ctxt.owner.from_arcis(open_interest),
+            success.reveal(),
+            requester_state_version,
+            responder_state_version,
+        )
+    }
+}
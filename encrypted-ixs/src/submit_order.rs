@@ -7,7 +7,26 @@ use arcis_imports::*;
 pub mod circuits {
     use arcis_imports::*;
 
+    /// Status codes revealed by `submit_order`
+    /// Must stay in sync with `OrderRejectReason` in the program
+    const ORDER_OK: u8 = 0;
+    const ORDER_INVALID_SIZE: u8 = 1;
+    const ORDER_INVALID_SIDE: u8 = 2;
+    const ORDER_OFF_TICK: u8 = 3;
+    const ORDER_OFF_LOT: u8 = 4;
+    const ORDER_OUTSIDE_BAND: u8 = 5;
+    const ORDER_INVALID_DISPLAY_SIZE: u8 = 6;
+    const ORDER_STALE_SEQUENCE: u8 = 7;
+    const ORDER_INVALID_STP_MODE: u8 = 8;
+
+    /// Highest valid self-trade prevention mode (see match_orders)
+    const STP_MODE_MAX: u8 = 3;
+
+    /// Basis points denominator for the price band
+    const BPS_DENOMINATOR: u128 = 10_000;
+
     /// Order data structure
+    #[derive(Clone, Copy)]
     pub struct OrderInput {
         /// Order size in smallest units (e.g., 1.5 ZEC = 150000000)
         size: u64,
@@ -15,26 +34,129 @@ pub mod circuits {
         price: u64,
         /// Side: 1 = long/buy, 0 = short/sell
         side: u8,
+        /// Iceberg peak: size shown per slice (0 = display the full size)
+        display_size: u64,
+        /// Per-margin-account sequence number, must be the next expected one
+        sequence: u64,
+        /// Self-trade prevention mode:
+        /// 0 = cancel newest, 1 = cancel oldest, 2 = cancel both, 3 = decrement
+        stp_mode: u8,
+    }
+
+    /// Order fields stored on-chain, encrypted for the MXE
+    /// Layout must match `OrderData` in the match_orders circuit
+    pub struct OrderData {
+        size: u64,
+        price: u64,
+        side: u8,
+        display_size: u64,
+        /// Currently visible slice of the order
+        visible_size: u64,
+        stp_mode: u8,
+        /// Identifier of the owning margin account, compared for self-trade prevention
+        owner_id: u128,
+    }
+
+    /// Public market constraints the order is validated against
+    pub struct MarketParams {
+        /// Current mark price (same units as order price)
+        mark_price: u64,
+        /// Order price must be a multiple of this
+        tick_size: u64,
+        /// Order size must be a multiple of this
+        lot_size: u64,
+        /// Maximum distance from mark price, in basis points
+        price_band_bps: u64,
     }
 
     /// Submit encrypted order
     /// Input: Enc<Shared, OrderInput> - User encrypts with shared secret
-    /// Output: Enc<Shared, OrderInput> - Returns encrypted confirmation to user
+    ///
+    /// For market orders (`is_market`), `price` is the encrypted worst
+    /// acceptable price: it is not bound to the tick size or the price band,
+    /// and the order cannot be an iceberg
+    /// Output:
+    /// - Enc<Shared, OrderInput> - encrypted confirmation returned to the user
+    /// - Enc<Mxe, OrderData> - copy stored on the order account for matching,
+    ///   with the first visible slice set for iceberg orders
+    /// - revealed status code (0 = accepted, otherwise the first failed check)
     ///
     /// This ensures:
     /// 1. User can verify their order was submitted correctly
     /// 2. Order details are encrypted throughout the process
     /// 3. Orders hidden from other users and front-runners
+    /// 4. Malformed orders are rejected without revealing their contents
+    /// 5. Replayed ciphertexts are rejected: the encrypted sequence number
+    ///    must equal the public `expected_sequence` tracked on-chain
+    ///
+    /// `owner_id` is derived on-chain from the margin account and sealed into
+    /// the stored order, so matching can detect self-trades
     #[instruction]
-    pub fn submit_order(order_ctxt: Enc<Shared, OrderInput>) -> Enc<Shared, OrderInput> {
+    pub fn submit_order(
+        order_ctxt: Enc<Shared, OrderInput>,
+        market: MarketParams,
+        expected_sequence: u64,
+        owner_id: u128,
+        is_market: bool,
+    ) -> (Enc<Shared, OrderInput>, Enc<Mxe, OrderData>, u8) {
         // Decrypt in MPC (nodes don't see plaintext individually)
         let order = order_ctxt.to_arcis();
 
-        // Validate order (basic checks in MPC)
-        // Note: More validation should be done on-chain before queuing
+        // Distance from mark price, compared in u128 to avoid overflow:
+        // |price - mark| * 10_000 <= mark * band_bps
+        let distance = if order.price > market.mark_price {
+            order.price - market.mark_price
+        } else {
+            market.mark_price - order.price
+        };
+        let within_band = distance as u128 * BPS_DENOMINATOR
+            <= market.mark_price as u128 * market.price_band_bps as u128;
+
+        let status = if order.sequence != expected_sequence {
+            ORDER_STALE_SEQUENCE
+        } else if order.size == 0 {
+            ORDER_INVALID_SIZE
+        } else if order.side > 1 {
+            ORDER_INVALID_SIDE
+        } else if !is_market && order.price % market.tick_size != 0 {
+            ORDER_OFF_TICK
+        } else if order.size % market.lot_size != 0 {
+            ORDER_OFF_LOT
+        } else if !is_market && !within_band {
+            ORDER_OUTSIDE_BAND
+        } else if order.display_size > order.size
+            || order.display_size % market.lot_size != 0
+            || (is_market && order.display_size != 0)
+        {
+            ORDER_INVALID_DISPLAY_SIZE
+        } else if order.stp_mode > STP_MODE_MAX {
+            ORDER_INVALID_STP_MODE
+        } else {
+            ORDER_OK
+        };
+
+        // Iceberg orders start with one slice visible, others fully visible
+        let stored = OrderData {
+            size: order.size,
+            price: order.price,
+            side: order.side,
+            display_size: order.display_size,
+            visible_size: if order.display_size == 0 {
+                order.size
+            } else {
+                order.display_size
+            },
+            stp_mode: order.stp_mode,
+            owner_id,
+        };
 
-        // Return encrypted order back to user as confirmation
-        // The order will be stored encrypted on-chain
-        order_ctxt.owner.from_arcis(order)
+        // Return encrypted order back to user as confirmation,
+        // and re-encrypt it for the MXE so matching can run without the user
+        // Only the status code is public
+        (
+            order_ctxt.owner.from_arcis(order),
+            Mxe::get().from_arcis(stored),
+            status.reveal(),
+        )
     }
 }
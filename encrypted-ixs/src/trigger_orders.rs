@@ -0,0 +1,117 @@
+use arcis_imports::*;
+
+/// Encrypted instructions for stop-loss / take-profit trigger orders
+/// Trigger price and direction never leave MPC; only the set of fired orders is revealed
+#[encrypted]
+pub mod circuits {
+    use arcis_imports::*;
+
+    /// Number of trigger orders evaluated per crank
+    const TRIGGER_BATCH_SIZE: usize = 8;
+
+    /// Trigger direction: fire when mark price falls to or below the trigger price
+    const TRIGGER_BELOW: u8 = 0;
+
+    /// Self-trade prevention mode used by trigger orders: cancel newest
+    const STP_CANCEL_NEWEST: u8 = 0;
+
+    /// Trigger order as submitted by the user
+    pub struct TriggerOrderInput {
+        /// Order size in smallest units
+        size: u64,
+        /// Limit price of the order once triggered
+        price: u64,
+        /// Side: 1 = long/buy, 0 = short/sell
+        side: u8,
+        /// Mark price at which the order becomes live
+        trigger_price: u64,
+        /// 0 = fire when mark <= trigger_price, 1 = fire when mark >= trigger_price
+        direction: u8,
+        /// Per-margin-account sequence number, must be the next expected one
+        sequence: u64,
+    }
+
+    /// Order fields stored on-chain, encrypted for the MXE
+    /// Layout must match `OrderData` in the match_orders circuit
+    pub struct OrderData {
+        size: u64,
+        price: u64,
+        side: u8,
+        /// Trigger orders are never icebergs: always fully displayed
+        display_size: u64,
+        visible_size: u64,
+        stp_mode: u8,
+        owner_id: u128,
+    }
+
+    /// Trigger fields stored on-chain, encrypted for the MXE
+    pub struct TriggerData {
+        trigger_price: u64,
+        direction: u8,
+    }
+
+    /// Place a trigger order
+    /// Input: Enc<Shared, TriggerOrderInput> - User encrypts with shared secret
+    /// Output: order and trigger re-encrypted for the MXE, so that later
+    /// computations (trigger evaluation, matching) can use them without the user,
+    /// and a revealed flag telling whether the sequence number was the expected one
+    #[instruction]
+    pub fn place_trigger_order(
+        input_ctxt: Enc<Shared, TriggerOrderInput>,
+        expected_sequence: u64,
+        owner_id: u128,
+    ) -> (Enc<Mxe, OrderData>, Enc<Mxe, TriggerData>, bool) {
+        let input = input_ctxt.to_arcis();
+
+        let order = OrderData {
+            size: input.size,
+            price: input.price,
+            side: input.side,
+            display_size: 0,
+            visible_size: input.size,
+            stp_mode: STP_CANCEL_NEWEST,
+            owner_id,
+        };
+        let trigger = TriggerData {
+            trigger_price: input.trigger_price,
+            direction: input.direction,
+        };
+
+        let sequence_ok = input.sequence == expected_sequence;
+
+        (
+            Mxe::get().from_arcis(order),
+            Mxe::get().from_arcis(trigger),
+            sequence_ok.reveal(),
+        )
+    }
+
+    /// Evaluate a batch of trigger orders against the public mark price
+    /// `active_mask` marks which slots hold a real order (bit i = slot i)
+    /// Returns a revealed bitmask of the orders whose trigger fired
+    #[instruction]
+    pub fn evaluate_triggers(
+        triggers: [Enc<Mxe, TriggerData>; TRIGGER_BATCH_SIZE],
+        active_mask: u8,
+        mark_price: u64,
+    ) -> u8 {
+        let mut fired: u8 = 0;
+
+        for i in 0..TRIGGER_BATCH_SIZE {
+            let bit = 1u8 << i;
+            if active_mask & bit != 0 {
+                let trigger = triggers[i].to_arcis();
+                let crossed = if trigger.direction == TRIGGER_BELOW {
+                    mark_price <= trigger.trigger_price
+                } else {
+                    mark_price >= trigger.trigger_price
+                };
+                if crossed {
+                    fired += bit;
+                }
+            }
+        }
+
+        fired.reveal()
+    }
+}
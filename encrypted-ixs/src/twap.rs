@@ -0,0 +1,154 @@
+use arcis_imports::*;
+
+/// Encrypted instructions for TWAP execution orders
+/// The parent's total size, slice count and interval never leave MPC;
+/// each crank only reveals whether a slice was released into the book
+#[encrypted]
+pub mod circuits {
+    use arcis_imports::*;
+
+    /// Highest valid self-trade prevention mode (see match_orders)
+    const STP_MODE_MAX: u8 = 3;
+
+    /// TWAP order as submitted by the user
+    pub struct TwapInput {
+        /// Total size to execute, in smallest units
+        total_size: u64,
+        /// Limit price applied to every slice
+        price: u64,
+        /// Side: 1 = long/buy, 0 = short/sell
+        side: u8,
+        /// Number of slices the total is split into
+        slices: u64,
+        /// Slots between two slices
+        interval_slots: u64,
+        /// Per-margin-account sequence number, must be the next expected one
+        sequence: u64,
+        /// Self-trade prevention mode applied to the child order
+        stp_mode: u8,
+    }
+
+    /// Parent state stored on the TWAP account, encrypted for the MXE
+    pub struct TwapData {
+        /// Size not yet released into the book
+        remaining_size: u64,
+        slices_left: u64,
+        interval_slots: u64,
+        /// First slot at which the next slice may be released
+        next_release_slot: u64,
+    }
+
+    /// Order fields stored on-chain, encrypted for the MXE
+    /// Layout must match `OrderData` in the match_orders circuit
+    pub struct OrderData {
+        size: u64,
+        price: u64,
+        side: u8,
+        /// TWAP children are never icebergs: always fully displayed
+        display_size: u64,
+        visible_size: u64,
+        stp_mode: u8,
+        owner_id: u128,
+    }
+
+    /// Public market constraints the TWAP is validated against
+    pub struct TwapMarketParams {
+        tick_size: u64,
+        lot_size: u64,
+    }
+
+    /// Place a TWAP order
+    /// Output: parent state and an empty child order, both re-encrypted for
+    /// the MXE, and a revealed validity flag. The child only enters the book
+    /// once the first slice is released.
+    #[instruction]
+    pub fn place_twap_order(
+        input_ctxt: Enc<Shared, TwapInput>,
+        market: TwapMarketParams,
+        expected_sequence: u64,
+        owner_id: u128,
+        current_slot: u64,
+    ) -> (Enc<Mxe, TwapData>, Enc<Mxe, OrderData>, bool) {
+        let input = input_ctxt.to_arcis();
+
+        let valid = input.sequence == expected_sequence
+            && input.total_size > 0
+            && input.total_size % market.lot_size == 0
+            && input.side <= 1
+            && input.price % market.tick_size == 0
+            && input.slices > 0
+            && input.slices <= input.total_size / market.lot_size
+            && input.interval_slots > 0
+            && input.stp_mode <= STP_MODE_MAX;
+
+        let parent = TwapData {
+            remaining_size: input.total_size,
+            slices_left: input.slices,
+            interval_slots: input.interval_slots,
+            next_release_slot: current_slot,
+        };
+        let child = OrderData {
+            size: 0,
+            price: input.price,
+            side: input.side,
+            display_size: 0,
+            visible_size: 0,
+            stp_mode: input.stp_mode,
+            owner_id,
+        };
+
+        (
+            Mxe::get().from_arcis(parent),
+            Mxe::get().from_arcis(child),
+            valid.reveal(),
+        )
+    }
+
+    /// Release the next TWAP slice if its interval has elapsed
+    /// The slice (remaining / slices left, rounded down to the lot size; the
+    /// last slice takes everything left) is added to the child order, so any
+    /// unfilled part of earlier slices stays in the book.
+    ///
+    /// Returns the updated parent and child, and revealed flags: whether a
+    /// slice was released, and whether the parent is now fully released
+    #[instruction]
+    pub fn release_twap_slice(
+        parent_ctxt: Enc<Mxe, TwapData>,
+        child_ctxt: Enc<Mxe, OrderData>,
+        current_slot: u64,
+        lot_size: u64,
+    ) -> (Enc<Mxe, TwapData>, Enc<Mxe, OrderData>, bool, bool) {
+        let mut parent = parent_ctxt.to_arcis();
+        let mut child = child_ctxt.to_arcis();
+
+        let due = parent.slices_left > 0 && current_slot >= parent.next_release_slot;
+
+        let divisor = if parent.slices_left > 0 {
+            parent.slices_left
+        } else {
+            1
+        };
+        let slice = if parent.slices_left == 1 {
+            parent.remaining_size
+        } else {
+            parent.remaining_size / divisor / lot_size * lot_size
+        };
+
+        if due {
+            child.size += slice;
+            child.visible_size = child.size;
+            parent.remaining_size -= slice;
+            parent.slices_left -= 1;
+            parent.next_release_slot = current_slot + parent.interval_slots;
+        }
+
+        let completed = parent.slices_left == 0;
+
+        (
+            parent_ctxt.owner.from_arcis(parent),
+            child_ctxt.owner.from_arcis(child),
+            due.reveal(),
+            completed.reveal(),
+        )
+    }
+}
@@ -6,113 +6,553 @@ use arcis_imports::*;
 pub mod circuits {
     use arcis_imports::*;
 
-    /// Deposit collateral input
-    pub struct DepositInput {
-        current_collateral: u64,
-        deposit_amount: u64,
+    /// Encrypted balances stored on a margin account
+    /// Ciphertext order matches `encrypted_collateral`, `encrypted_debt`
+    #[derive(Clone, Copy)]
+    pub struct MarginBalances {
+        collateral: u64,
+        debt: u64,
     }
 
-    /// Deposit result
-    pub struct DepositResult {
-        new_collateral: u64,
-        success: u8,
-    }
-
-    /// Deposit collateral - add to encrypted balance
+    /// Credit a deposit to the encrypted collateral
+    /// `amount` is the token amount the program received into the vault.
+    /// Nothing changes on overflow. Only the success bit is revealed; the
+    /// amount and the account's state version are passed through so the
+    /// callback can return an uncredited deposit and drop results for an
+    /// outdated state.
     #[instruction]
-    pub fn deposit_collateral(input_ctxt: Enc<Mxe, DepositInput>) -> Enc<Mxe, DepositResult> {
-        let input = input_ctxt.to_arcis();
+    pub fn deposit_collateral(
+        balances_ctxt: Enc<Mxe, MarginBalances>,
+        amount: u64,
+        state_version: u64,
+    ) -> (Enc<Mxe, MarginBalances>, bool, u64, u64) {
+        let mut balances = balances_ctxt.to_arcis();
 
         // Check for overflow
-        let (new_collateral, success) =
-            if input.current_collateral > u64::MAX - input.deposit_amount {
-                (input.current_collateral, 0)
-            } else {
-                (input.current_collateral + input.deposit_amount, 1)
-            };
+        let success = balances.collateral <= u64::MAX - amount;
+        if success {
+            balances.collateral += amount;
+        }
+
+        (
+            balances_ctxt.owner.from_arcis(balances),
+            success.reveal(),
+            amount,
+            state_version,
+        )
+    }
+
+    /// Debit a withdrawal from the encrypted collateral
+    /// Nothing changes if the collateral does not cover `amount`, or if the
+    /// account would not meet the maintenance margin at the mark price
+    /// afterwards, given its debt and open position. Only the success bit is
+    /// revealed, which approves the token transfer; the amount and the
+    /// account's state version are passed through so the callback can drop
+    /// results for an outdated state.
+    #[instruction]
+    pub fn withdraw_collateral(
+        balances_ctxt: Enc<Mxe, MarginBalances>,
+        position_ctxt: Enc<Mxe, Position>,
+        amount: u64,
+        mark_price: u64,
+        maintenance_margin_bps: u64,
+        state_version: u64,
+    ) -> (Enc<Mxe, MarginBalances>, bool, u64, u64) {
+        let mut balances = balances_ctxt.to_arcis();
+        let position = position_ctxt.to_arcis();
 
-        let result = DepositResult {
-            new_collateral,
-            success,
+        // Check for underflow, then the margin left behind
+        let covered = amount <= balances.collateral;
+        let remaining = if covered {
+            (balances.collateral - amount) as u128
+        } else {
+            0
         };
+        let success = covered
+            && meets_margin(
+                remaining,
+                balances.debt as u128,
+                position,
+                mark_price,
+                maintenance_margin_bps,
+            );
+        if success {
+            balances.collateral -= amount;
+        }
 
-        input_ctxt.owner.from_arcis(result)
+        (
+            balances_ctxt.owner.from_arcis(balances),
+            success.reveal(),
+            amount,
+            state_version,
+        )
     }
 
-    /// Withdraw collateral input
-    pub struct WithdrawInput {
-        current_collateral: u64,
-        withdraw_amount: u64,
+    /// Encrypted net position stored on a margin account
+    /// Ciphertext order matches `encrypted_position`
+    #[derive(Clone, Copy)]
+    pub struct Position {
+        /// Net size in smallest units
+        size: u64,
+        /// Side: 1 = long, 0 = short
+        side: u8,
+        /// Notional paid to open the current size (cost basis)
+        entry_notional: u64,
     }
 
-    /// Withdraw result
-    pub struct WithdrawResult {
-        new_collateral: u64,
-        success: u8,
+    /// Encrypted fill produced by `match_orders`
+    pub struct Fill {
+        price: u64,
+        size: u64,
     }
 
-    /// Withdraw collateral - subtract from encrypted balance
-    #[instruction]
-    pub fn withdraw_collateral(input_ctxt: Enc<Mxe, WithdrawInput>) -> Enc<Mxe, WithdrawResult> {
-        let input = input_ctxt.to_arcis();
+    /// Fixed-point scale shared by prices and sizes (1e6)
+    const PRICE_SCALE: u128 = 1_000_000;
+
+    /// Fee rates are expressed in basis points of the notional
+    const BPS_DENOMINATOR: u128 = 10_000;
+
+    /// Cumulative protocol fees, encrypted for the MXE
+    pub struct ProtocolFees {
+        collected: u64,
+    }
+
+    /// Encrypted open interest per side, in size units
+    /// Kept up to date by every settlement so socialized losses can be
+    /// spread per unit of size
+    #[derive(Clone, Copy)]
+    pub struct OpenInterest {
+        long: u64,
+        short: u64,
+    }
+
+    /// Public per-market fee rates
+    /// `maker_bps` is a rebate paid out of the taker fee when `maker_rebate` is set
+    pub struct FeeRates {
+        taker_bps: u64,
+        maker_bps: u64,
+        maker_rebate: bool,
+    }
 
-        // Check for underflow
-        let (new_collateral, success) = if input.withdraw_amount > input.current_collateral {
-            (input.current_collateral, 0)
+    /// Collateral movements of one fill: (buyer debit, buyer credit,
+    /// seller debit, seller credit, protocol fee)
+    fn fill_deltas(
+        notional: u128,
+        rates: &FeeRates,
+        buyer_is_maker: bool,
+    ) -> (u128, u128, u128, u128, u128) {
+        let taker_fee = notional * rates.taker_bps as u128 / BPS_DENOMINATOR;
+        let maker_amount = notional * rates.maker_bps as u128 / BPS_DENOMINATOR;
+        let (maker_fee, maker_rebate) = if rates.maker_rebate {
+            (0, maker_amount)
         } else {
-            (input.current_collateral - input.withdraw_amount, 1)
+            (maker_amount, 0)
         };
 
-        let result = WithdrawResult {
-            new_collateral,
-            success,
+        let (buyer_fee, buyer_rebate, seller_fee, seller_rebate) = if buyer_is_maker {
+            (maker_fee, maker_rebate, taker_fee, 0)
+        } else {
+            (taker_fee, 0, maker_fee, maker_rebate)
         };
 
-        input_ctxt.owner.from_arcis(result)
+        (
+            notional + buyer_fee,
+            buyer_rebate,
+            seller_fee,
+            notional + seller_rebate,
+            taker_fee + maker_fee - maker_rebate,
+        )
+    }
+
+    /// Apply a debit and a credit to a balance; false if it would go
+    /// negative or overflow
+    fn apply_delta(balance: u64, debit: u128, credit: u128) -> (u64, bool) {
+        let available = balance as u128 + credit;
+        let ok = debit <= available && available - debit <= u64::MAX as u128;
+        let updated = if ok {
+            (available - debit) as u64
+        } else {
+            balance
+        };
+        (updated, ok)
     }
 
-    /// Trade settlement input (encrypted balances only)
-    pub struct TradeBalances {
-        buyer_collateral: u64,
-        seller_collateral: u64,
+    /// Apply one side of a fill to a net position; false on overflow.
+    /// Adding to the position grows the cost basis, reducing it releases the
+    /// cost basis pro rata, and trading through zero flips the side.
+    fn apply_fill(position: Position, is_buy: bool, size: u64, notional: u128) -> (Position, bool) {
+        let side: u8 = if is_buy { 1 } else { 0 };
+        let held = position.size as u128;
+        let traded = size as u128;
+        // Both branches are evaluated in MPC, so divisors are kept non-zero
+        let held_divisor = if held == 0 { 1 } else { held };
+        let traded_divisor = if traded == 0 { 1 } else { traded };
+
+        let (new_side, new_size, new_entry) = if held == 0 || position.side == side {
+            (
+                side,
+                held + traded,
+                position.entry_notional as u128 + notional,
+            )
+        } else if traded <= held {
+            (
+                position.side,
+                held - traded,
+                position.entry_notional as u128 * (held - traded) / held_divisor,
+            )
+        } else {
+            (
+                side,
+                traded - held,
+                notional * (traded - held) / traded_divisor,
+            )
+        };
+
+        let ok = new_size <= u64::MAX as u128 && new_entry <= u64::MAX as u128;
+        let updated = if ok {
+            Position {
+                size: new_size as u64,
+                side: new_side,
+                entry_notional: new_entry as u64,
+            }
+        } else {
+            position
+        };
+        (updated, ok)
     }
 
-    /// Trade result
-    pub struct TradeResult {
-        new_buyer_collateral: u64,
-        new_seller_collateral: u64,
-        success: u8,
+    /// Whether an account meets the maintenance margin at the mark price
+    /// Same rule as `account_health` in the check_health circuit
+    fn meets_margin(
+        collateral: u128,
+        debt: u128,
+        position: Position,
+        mark_price: u64,
+        maintenance_margin_bps: u64,
+    ) -> bool {
+        let position_value = position.size as u128 * mark_price as u128 / PRICE_SCALE;
+        let (assets, liabilities) = if position.side == 1 {
+            (collateral + position_value, debt)
+        } else {
+            (collateral, debt + position_value)
+        };
+        let requirement = position_value * maintenance_margin_bps as u128 / BPS_DENOMINATOR;
+        assets >= liabilities + requirement
+    }
+
+    /// Move one account's contribution to open interest from its position
+    /// `before` to its position `after`; false on overflow
+    fn shift_open_interest(
+        open_interest: OpenInterest,
+        before: Position,
+        after: Position,
+    ) -> (OpenInterest, bool) {
+        let (before_long, before_short) = if before.side == 1 {
+            (before.size as u128, 0)
+        } else {
+            (0, before.size as u128)
+        };
+        let (after_long, after_short) = if after.side == 1 {
+            (after.size as u128, 0)
+        } else {
+            (0, after.size as u128)
+        };
+        let long = open_interest.long as u128 + after_long;
+        let short = open_interest.short as u128 + after_short;
+
+        let ok = before_long <= long
+            && before_short <= short
+            && long - before_long <= u64::MAX as u128
+            && short - before_short <= u64::MAX as u128;
+        let updated = if ok {
+            OpenInterest {
+                long: (long - before_long) as u64,
+                short: (short - before_short) as u64,
+            }
+        } else {
+            open_interest
+        };
+        (updated, ok)
     }
 
     /// Settle trade - update both buyer and seller balances privately
-    /// trade_value is passed as plaintext since it's public information
+    /// The fill price and size come encrypted from the matching step,
+    /// so the notional never appears on-chain. Maker and taker fees are
+    /// charged against collateral and accrue to the encrypted protocol fee
+    /// balance. Both net positions are updated with the fill, and the
+    /// open interest with them. The trade fails if either account would not
+    /// meet the maintenance margin at the mark price afterwards. Only the
+    /// success bit is revealed; both
+    /// accounts' state versions are passed through so the callback can drop
+    /// results for an outdated state.
     #[instruction]
     pub fn settle_trade(
-        balances_ctxt: Enc<Mxe, TradeBalances>,
-        trade_value: u64,
-    ) -> Enc<Mxe, TradeResult> {
-        let balances = balances_ctxt.to_arcis();
-
-        // Check if buyer has enough and no overflow on seller side
-        let (new_buyer_collateral, new_seller_collateral, success) =
-            if trade_value > balances.buyer_collateral {
-                (balances.buyer_collateral, balances.seller_collateral, 0)
-            } else if balances.seller_collateral > u64::MAX - trade_value {
-                (balances.buyer_collateral, balances.seller_collateral, 0)
+        buyer_ctxt: Enc<Mxe, MarginBalances>,
+        seller_ctxt: Enc<Mxe, MarginBalances>,
+        buyer_position_ctxt: Enc<Mxe, Position>,
+        seller_position_ctxt: Enc<Mxe, Position>,
+        fill_ctxt: Enc<Mxe, Fill>,
+        fees_ctxt: Enc<Mxe, ProtocolFees>,
+        open_interest_ctxt: Enc<Mxe, OpenInterest>,
+        rates: FeeRates,
+        buyer_is_maker: bool,
+        mark_price: u64,
+        maintenance_margin_bps: u64,
+        buyer_state_version: u64,
+        seller_state_version: u64,
+    ) -> (
+        Enc<Mxe, MarginBalances>,
+        Enc<Mxe, MarginBalances>,
+        Enc<Mxe, Position>,
+        Enc<Mxe, Position>,
+        Enc<Mxe, ProtocolFees>,
+        Enc<Mxe, OpenInterest>,
+        bool,
+        u64,
+        u64,
+    ) {
+        let mut buyer = buyer_ctxt.to_arcis();
+        let mut seller = seller_ctxt.to_arcis();
+        let mut buyer_position = buyer_position_ctxt.to_arcis();
+        let mut seller_position = seller_position_ctxt.to_arcis();
+        let fill = fill_ctxt.to_arcis();
+        let mut fees = fees_ctxt.to_arcis();
+        let mut open_interest = open_interest_ctxt.to_arcis();
+
+        // Notional: (price * size) / 1e6, computed in u128 to prevent overflow
+        let notional = fill.price as u128 * fill.size as u128 / PRICE_SCALE;
+        let (buyer_debit, buyer_credit, seller_debit, seller_credit, protocol_fee) =
+            fill_deltas(notional, &rates, buyer_is_maker);
+
+        let (new_buyer, buyer_ok) = apply_delta(buyer.collateral, buyer_debit, buyer_credit);
+        let (new_seller, seller_ok) = apply_delta(seller.collateral, seller_debit, seller_credit);
+        let (new_fees, fees_ok) = apply_delta(fees.collected, 0, protocol_fee);
+        let (new_buyer_position, buyer_position_ok) =
+            apply_fill(buyer_position, true, fill.size, notional);
+        let (new_seller_position, seller_position_ok) =
+            apply_fill(seller_position, false, fill.size, notional);
+        let (shifted, buyer_open_interest_ok) =
+            shift_open_interest(open_interest, buyer_position, new_buyer_position);
+        let (new_open_interest, seller_open_interest_ok) =
+            shift_open_interest(shifted, seller_position, new_seller_position);
+        let buyer_margin_ok = meets_margin(
+            new_buyer as u128,
+            buyer.debt as u128,
+            new_buyer_position,
+            mark_price,
+            maintenance_margin_bps,
+        );
+        let seller_margin_ok = meets_margin(
+            new_seller as u128,
+            seller.debt as u128,
+            new_seller_position,
+            mark_price,
+            maintenance_margin_bps,
+        );
+
+        let success = buyer_ok
+            && seller_ok
+            && fees_ok
+            && buyer_position_ok
+            && seller_position_ok
+            && buyer_open_interest_ok
+            && seller_open_interest_ok
+            && buyer_margin_ok
+            && seller_margin_ok;
+
+        if success {
+            buyer.collateral = new_buyer;
+            seller.collateral = new_seller;
+            fees.collected = new_fees;
+            buyer_position = new_buyer_position;
+            seller_position = new_seller_position;
+            open_interest = new_open_interest;
+        }
+
+        (
+            buyer_ctxt.owner.from_arcis(buyer),
+            seller_ctxt.owner.from_arcis(seller),
+            buyer_position_ctxt.owner.from_arcis(buyer_position),
+            seller_position_ctxt.owner.from_arcis(seller_position),
+            fees_ctxt.owner.from_arcis(fees),
+            open_interest_ctxt.owner.from_arcis(open_interest),
+            success.reveal(),
+            buyer_state_version,
+            seller_state_version,
+        )
+    }
+
+    /// Reveal the cumulative protocol fees collected so far
+    /// The encrypted balance itself is left untouched.
+    #[instruction]
+    pub fn reveal_protocol_fees(fees_ctxt: Enc<Mxe, ProtocolFees>) -> u64 {
+        let fees = fees_ctxt.to_arcis();
+        fees.collected.reveal()
+    }
+
+    /// Fills settled per batch
+    const SETTLE_BATCH_FILLS: usize = 4;
+
+    /// Distinct margin accounts per batch (two per fill at most)
+    const SETTLE_BATCH_ACCOUNTS: usize = 8;
+
+    /// Settle a batch of fills in one computation
+    /// `buyer_idx` / `seller_idx` map each fill to its margin account slot,
+    /// `fill_mask` marks which fill slots are real (bit i = fill i).
+    ///
+    /// `maker_mask` marks fills whose buyer was the maker (bit i = fill i).
+    ///
+    /// Notionals and fees are netted per account first, so a fill may be
+    /// funded by another fill in the same batch. Net positions are updated
+    /// fill by fill, and the open interest with them. The batch is all-or-nothing: if any account would end
+    /// up negative or overflow, or would not meet the maintenance margin at
+    /// the mark price afterwards, no balance or position changes.
+    /// Only the success bit is revealed; the accounts' state versions and the
+    /// number of account slots in use are passed through so the callback can
+    /// drop results for an outdated state and split its accounts.
+    #[instruction]
+    pub fn settle_batch(
+        accounts: [Enc<Mxe, MarginBalances>; SETTLE_BATCH_ACCOUNTS],
+        positions: [Enc<Mxe, Position>; SETTLE_BATCH_ACCOUNTS],
+        fills: [Enc<Mxe, Fill>; SETTLE_BATCH_FILLS],
+        buyer_idx: [u8; SETTLE_BATCH_FILLS],
+        seller_idx: [u8; SETTLE_BATCH_FILLS],
+        fill_mask: u8,
+        maker_mask: u8,
+        fees_ctxt: Enc<Mxe, ProtocolFees>,
+        open_interest_ctxt: Enc<Mxe, OpenInterest>,
+        rates: FeeRates,
+        mark_price: u64,
+        maintenance_margin_bps: u64,
+        state_versions: [u64; SETTLE_BATCH_ACCOUNTS],
+        account_count: u8,
+    ) -> (
+        [Enc<Mxe, MarginBalances>; SETTLE_BATCH_ACCOUNTS],
+        [Enc<Mxe, Position>; SETTLE_BATCH_ACCOUNTS],
+        Enc<Mxe, ProtocolFees>,
+        Enc<Mxe, OpenInterest>,
+        bool,
+        [u64; SETTLE_BATCH_ACCOUNTS],
+        u8,
+    ) {
+        let mut credits = [0u128; SETTLE_BATCH_ACCOUNTS];
+        let mut debits = [0u128; SETTLE_BATCH_ACCOUNTS];
+        let mut protocol_fees: u128 = 0;
+        let mut positions_ok = true;
+
+        let mut held = [Position {
+            size: 0,
+            side: 0,
+            entry_notional: 0,
+        }; SETTLE_BATCH_ACCOUNTS];
+        for j in 0..SETTLE_BATCH_ACCOUNTS {
+            held[j] = positions[j].to_arcis();
+        }
+        let mut updated = held;
+
+        for i in 0..SETTLE_BATCH_FILLS {
+            let fill = fills[i].to_arcis();
+            let active = (fill_mask >> i) & 1 == 1;
+            let notional = if active {
+                fill.price as u128 * fill.size as u128 / PRICE_SCALE
             } else {
-                (
-                    balances.buyer_collateral - trade_value,
-                    balances.seller_collateral + trade_value,
-                    1,
-                )
+                0
             };
+            let buyer_is_maker = (maker_mask >> i) & 1 == 1;
+            let (buyer_debit, buyer_credit, seller_debit, seller_credit, protocol_fee) =
+                fill_deltas(notional, &rates, buyer_is_maker);
+            protocol_fees += protocol_fee;
 
-        let result = TradeResult {
-            new_buyer_collateral,
-            new_seller_collateral,
-            success,
-        };
+            for j in 0..SETTLE_BATCH_ACCOUNTS {
+                if buyer_idx[i] as usize == j {
+                    debits[j] += buyer_debit;
+                    credits[j] += buyer_credit;
+                }
+                if seller_idx[i] as usize == j {
+                    debits[j] += seller_debit;
+                    credits[j] += seller_credit;
+                }
+                if active && buyer_idx[i] as usize == j {
+                    let (position, ok) = apply_fill(updated[j], true, fill.size, notional);
+                    updated[j] = position;
+                    positions_ok = positions_ok && ok;
+                }
+                if active && seller_idx[i] as usize == j {
+                    let (position, ok) = apply_fill(updated[j], false, fill.size, notional);
+                    updated[j] = position;
+                    positions_ok = positions_ok && ok;
+                }
+            }
+        }
+
+        let mut balances = [MarginBalances {
+            collateral: 0,
+            debt: 0,
+        }; SETTLE_BATCH_ACCOUNTS];
+        let mut success = true;
+        for j in 0..SETTLE_BATCH_ACCOUNTS {
+            balances[j] = accounts[j].to_arcis();
+            let available = balances[j].collateral as u128 + credits[j];
+            if debits[j] > available || available - debits[j] > u64::MAX as u128 {
+                success = false;
+            }
+            // Only slots in use hold an account
+            let collateral = if debits[j] > available {
+                0
+            } else {
+                available - debits[j]
+            };
+            if (j as u8) < account_count
+                && !meets_margin(
+                    collateral,
+                    balances[j].debt as u128,
+                    updated[j],
+                    mark_price,
+                    maintenance_margin_bps,
+                )
+            {
+                success = false;
+            }
+        }
+
+        // Unused and untouched slots hold the same position before and after
+        let mut open_interest = open_interest_ctxt.to_arcis();
+        let mut new_open_interest = open_interest;
+        let mut open_interest_ok = true;
+        for j in 0..SETTLE_BATCH_ACCOUNTS {
+            let (shifted, ok) = shift_open_interest(new_open_interest, held[j], updated[j]);
+            new_open_interest = shifted;
+            open_interest_ok = open_interest_ok && ok;
+        }
+
+        let mut fees = fees_ctxt.to_arcis();
+        let (new_fees, fees_ok) = apply_delta(fees.collected, 0, protocol_fees);
+        let success = success && fees_ok && positions_ok && open_interest_ok;
+
+        let mut outputs = accounts;
+        let mut position_outputs = positions;
+        for j in 0..SETTLE_BATCH_ACCOUNTS {
+            if success {
+                balances[j].collateral =
+                    (balances[j].collateral as u128 + credits[j] - debits[j]) as u64;
+            }
+            outputs[j] = outputs[j].owner.from_arcis(balances[j]);
+            let position = if success { updated[j] } else { held[j] };
+            position_outputs[j] = position_outputs[j].owner.from_arcis(position);
+        }
+        if success {
+            fees.collected = new_fees;
+            open_interest = new_open_interest;
+        }
 
-        balances_ctxt.owner.from_arcis(result)
+        (
+            outputs,
+            position_outputs,
+            fees_ctxt.owner.from_arcis(fees),
+            open_interest_ctxt.owner.from_arcis(open_interest),
+            success.reveal(),
+            state_versions,
+            account_count,
+        )
     }
 }
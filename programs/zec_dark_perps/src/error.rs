@@ -36,4 +36,186 @@ pub enum ErrorCode {
     // Arcium errors
     #[msg("Arcium cluster not set")]
     ClusterNotSet,
+
+    // Market errors
+    #[msg("Signer is not the market oracle")]
+    UnauthorizedOracle,
+
+    #[msg("Signer is not the market authority")]
+    UnauthorizedMarketAuthority,
+
+    #[msg("Invalid market parameters")]
+    InvalidMarketParams,
+
+    #[msg("Mark price is missing or stale")]
+    StaleMarkPrice,
+
+    #[msg("Signer is not the program upgrade authority")]
+    UnauthorizedUpgradeAuthority,
+
+    // Order errors
+    #[msg("Order is not in the expected status")]
+    InvalidOrderStatus,
+
+    #[msg("Account is not a valid order account")]
+    InvalidOrderAccount,
+
+    #[msg("Invalid number of accounts in batch")]
+    InvalidBatchSize,
+
+    #[msg("Order does not belong to the given margin account")]
+    OrderAccountMismatch,
+
+    #[msg("Fill is not in the expected status")]
+    InvalidFillStatus,
+
+    #[msg("Buyer and seller are the same margin account")]
+    SelfTrade,
+
+    #[msg("Account in batch is not a valid fill or margin account")]
+    InvalidBatchAccount,
+
+    #[msg("Account appears more than once in batch")]
+    DuplicateBatchAccount,
+
+    #[msg("Two market orders cannot be matched against each other")]
+    MarketOrderPair,
+
+    #[msg("Market order matching window has passed")]
+    MarketOrderExpired,
+
+    #[msg("Market order matching window has not passed yet")]
+    MarketOrderNotExpired,
+
+    #[msg("Order is held by a computation still in flight")]
+    OrderInFlight,
+
+    #[msg("Signer does not own the order")]
+    UnauthorizedOrderOwner,
+
+    // Commit-reveal errors
+    #[msg("Revealed order does not match its commitment")]
+    InvalidCommitment,
+
+    #[msg("Order cannot be revealed in the commitment slot")]
+    RevealTooEarly,
+
+    #[msg("Order commitment reveal window has passed")]
+    CommitmentExpired,
+
+    #[msg("Only the owner can withdraw a commitment before its reveal window closes")]
+    CommitmentStillRevealable,
+
+    // Matcher registry errors
+    #[msg("Signer is not the config authority")]
+    UnauthorizedConfigAuthority,
+
+    #[msg("Signer is not an authorized matching engine")]
+    UnauthorizedMatcher,
+
+    #[msg("Matcher is already registered")]
+    MatcherAlreadyRegistered,
+
+    #[msg("Matcher registry is full")]
+    MatcherRegistryFull,
+
+    #[msg("Callback server URL is too long")]
+    CallbackUrlTooLong,
+
+    #[msg("Computation output needs a callback server, but none is configured")]
+    CallbackServerNotSet,
+
+    // RFQ errors
+    #[msg("Invalid quote request parameters")]
+    InvalidRfqParams,
+
+    #[msg("Quote request is not in the expected status")]
+    InvalidRfqStatus,
+
+    #[msg("Quote request has expired")]
+    RfqExpired,
+
+    #[msg("Signer is not the requester of this quote request")]
+    UnauthorizedRequester,
+
+    #[msg("Signer is not the responder named in this quote request")]
+    UnauthorizedResponder,
+
+    #[msg("Margin account does not belong to this quote request")]
+    RfqAccountMismatch,
+
+    // TWAP errors
+    #[msg("TWAP order is not in the expected status")]
+    InvalidTwapStatus,
+
+    #[msg("Signer is not the owner of this TWAP order")]
+    UnauthorizedTwapOwner,
+
+    #[msg("Order is not the child of this TWAP order")]
+    TwapChildMismatch,
+
+    // OCO group errors
+    #[msg("Order already belongs to an OCO group")]
+    OrderAlreadyGrouped,
+
+    #[msg("OCO group is not in the expected status")]
+    InvalidOrderGroupStatus,
+
+    #[msg("Account does not match the order's OCO group")]
+    OrderGroupMismatch,
+
+    #[msg("The other leg of this OCO group has already executed")]
+    OcoLegResolved,
+
+    #[msg("Signer is not the owner of this OCO group")]
+    UnauthorizedGroupOwner,
+
+    // Book snapshot errors
+    #[msg("Book snapshot interval has not elapsed")]
+    SnapshotNotDue,
+
+    #[msg("Book snapshot is not in the expected state")]
+    InvalidSnapshotState,
+
+    #[msg("Snapshot orders must be passed in increasing address order")]
+    UnsortedSnapshotOrders,
+
+    // Fee errors
+    #[msg("Protocol fee balance is locked by another settlement")]
+    FeeBalanceBusy,
+
+    // Liquidation errors
+    #[msg("Health check is too old or predates the account's latest state")]
+    StaleHealthCheck,
+
+    #[msg("Socialized losses must be applied to the margin account first")]
+    PendingSocializedLoss,
+
+    // Auto-deleveraging errors
+    #[msg("Auto-deleveraging must run before the account can be liquidated again")]
+    AdlPending,
+
+    #[msg("Margin account is not awaiting auto-deleveraging")]
+    AdlNotPending,
+
+    #[msg("ADL ranking is missing or predates the accounts' latest state")]
+    StaleAdlRanking,
+
+    // Collateral errors
+    #[msg("Amount exceeds the tokens deposited but not yet credited")]
+    DepositNotReceived,
+
+    #[msg("Signer is not the owner of this margin account")]
+    UnauthorizedMarginOwner,
+
+    #[msg("Viewing key must be a non-zero x25519 public key")]
+    InvalidViewingKey,
+
+    // Settlement recovery errors
+    #[msg("Fill settlement still holds the protocol fee lock")]
+    SettlementInFlight,
+
+    // TWAP crank errors
+    #[msg("TWAP order was cranked too recently")]
+    TwapCrankTooSoon,
 }
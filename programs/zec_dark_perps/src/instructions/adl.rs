@@ -0,0 +1,787 @@
+use anchor_lang::prelude::*;
+use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
+
+use crate::error::ErrorCode;
+use crate::instructions::margin_arcium::{
+    push_open_interest_args, push_padding_position_args, push_position_args,
+};
+use crate::instructions::shared::{load_account, store_account, validate_callback_ixs};
+use crate::state::adl_ranking::AdlRanking;
+use crate::state::margin_account::MarginAccount;
+use crate::state::market::Market;
+use crate::state::program_config::ProgramConfig;
+use crate::state::protocol_fees::ProtocolFees;
+use crate::state::SignerAccount;
+use crate::ID;
+use crate::ID as ID_CONST;
+
+// Computation definition offsets for auto-deleveraging encrypted instructions
+const COMP_DEF_OFFSET_RANK_ADL: u32 = comp_def_offset("rank_adl");
+const COMP_DEF_OFFSET_AUTO_DELEVERAGE: u32 = comp_def_offset("auto_deleverage");
+
+/// Candidate positions per ranking and per deleveraging (must match the circuit)
+pub const ADL_ACCOUNTS: usize = 8;
+
+/// Ranking output size: the ranking, plus the computation offset and the
+/// bankrupt and per-candidate state versions passed through
+const RANK_ADL_OUTPUT_BYTES: usize = ADL_ACCOUNTS + 8 + 8 + ADL_ACCOUNTS * 8;
+
+/// Deleveraging output size: an Enc<Mxe, MarginBalances> (nonce + 2
+/// ciphertexts) and Enc<Mxe, Position> (nonce + 3 ciphertexts) for the
+/// bankrupt account and every candidate slot, the Enc<Mxe, OpenInterest>
+/// (nonce + 2 ciphertexts), plus the state versions passed through
+const AUTO_DELEVERAGE_OUTPUT_BYTES: usize =
+    (1 + ADL_ACCOUNTS) * (16 + 2 * 32 + 16 + 3 * 32) + 16 + 2 * 32 + 8 + ADL_ACCOUNTS * 8;
+
+/// Initialize computation definition for ranking ADL candidates
+pub fn init_rank_adl_comp_def(ctx: Context<InitRankAdlCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/rank_adl.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Rank ADL computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Rank up to ADL_ACCOUNTS candidates for deleveraging against a bankrupt account
+/// The candidate margin accounts are passed as remaining accounts. MPC ranks
+/// the profitable positions on the opposite side by PnL × leverage at the
+/// mark price and reveals only the ranking order, which the callback stores
+/// on the account's `AdlRanking`.
+///
+/// The candidate set is keeper-selected: an authorized matcher picks which
+/// accounts are ranked, and nothing on-chain proves they are the most
+/// profitable ones on the opposite side. Within that set the circuit only
+/// ranks active, profitable positions on the opposite side, so a same-side
+/// or losing candidate cannot be deleveraged.
+///
+/// Requirements:
+/// - The payer must be an authorized matcher
+/// - The bankrupt account must be flagged for auto-deleveraging by a liquidation
+/// - The mark price must be fresh
+/// - Socialized losses must already be applied to every account
+pub fn queue_adl_ranking(ctx: Context<QueueAdlRanking>, computation_offset: u64) -> Result<()> {
+    let candidate_infos = ctx.remaining_accounts;
+    require!(
+        !candidate_infos.is_empty() && candidate_infos.len() <= ADL_ACCOUNTS,
+        ErrorCode::InvalidBatchSize
+    );
+
+    let market = &ctx.accounts.market;
+    require!(
+        market.is_price_fresh(Clock::get()?.slot),
+        ErrorCode::StaleMarkPrice
+    );
+
+    let bankrupt_key = ctx.accounts.margin_account.key();
+    let bankrupt = &ctx.accounts.margin_account;
+    require!(bankrupt.adl_pending, ErrorCode::AdlNotPending);
+    require!(
+        bankrupt.has_current_loss_index(market),
+        ErrorCode::PendingSocializedLoss
+    );
+
+    let mut candidates = Vec::with_capacity(candidate_infos.len());
+    for (slot, info) in candidate_infos.iter().enumerate() {
+        require!(
+            *info.key != bankrupt_key
+                && !candidate_infos[..slot]
+                    .iter()
+                    .any(|other| other.key == info.key),
+            ErrorCode::DuplicateBatchAccount
+        );
+        let margin: MarginAccount = load_account(info)?;
+        require!(
+            margin.has_current_loss_index(market),
+            ErrorCode::PendingSocializedLoss
+        );
+        candidates.push(margin);
+    }
+
+    // Enc<Mxe, Position> of the bankrupt account, then
+    // [Enc<Mxe, MarginBalances>; ADL_ACCOUNTS] and
+    // [Enc<Mxe, Position>; ADL_ACCOUNTS], unused slots padded
+    let mut args = Vec::with_capacity(ADL_ACCOUNTS * 8 + 16);
+    push_position_args(&mut args, bankrupt);
+    for slot in 0..ADL_ACCOUNTS {
+        match candidates.get(slot) {
+            Some(margin) => {
+                args.push(Argument::PlaintextU128(margin.nonce));
+                args.push(Argument::EncryptedU64(margin.encrypted_collateral));
+                args.push(Argument::EncryptedU64(margin.encrypted_debt));
+            }
+            None => {
+                args.push(Argument::PlaintextU128(0));
+                args.push(Argument::EncryptedU64([0u8; 32]));
+                args.push(Argument::EncryptedU64([0u8; 32]));
+            }
+        }
+    }
+    for slot in 0..ADL_ACCOUNTS {
+        match candidates.get(slot) {
+            Some(margin) => push_position_args(&mut args, margin),
+            None => push_padding_position_args(&mut args),
+        }
+    }
+    let active_mask = ((1u16 << candidates.len()) - 1) as u8;
+    args.push(Argument::PlaintextU8(active_mask));
+    args.push(Argument::PlaintextU64(market.mark_price));
+    args.push(Argument::PlaintextU64(computation_offset));
+    args.push(Argument::PlaintextU64(bankrupt.state_version));
+    let mut state_versions = [0u64; ADL_ACCOUNTS];
+    for (slot, margin) in candidates.iter().enumerate() {
+        state_versions[slot] = margin.state_version;
+    }
+    args.extend(
+        state_versions
+            .iter()
+            .map(|version| Argument::PlaintextU64(*version)),
+    );
+
+    let bankrupt_state_version = bankrupt.state_version;
+    let ranking = &mut ctx.accounts.adl_ranking;
+    ranking.margin_account = bankrupt_key;
+    ranking.candidates = [Pubkey::default(); ADL_ACCOUNTS];
+    for (slot, info) in candidate_infos.iter().enumerate() {
+        ranking.candidates[slot] = info.key();
+    }
+    ranking.candidate_count = candidates.len() as u8;
+    ranking.computation_offset = computation_offset;
+    ranking.order = [0u8; ADL_ACCOUNTS];
+    ranking.is_ranked = false;
+    ranking.bankrupt_state_version = bankrupt_state_version;
+    ranking.state_versions = state_versions;
+    ranking.bump = ctx.bumps.adl_ranking;
+
+    let (callback_url, num_callback_txs) =
+        ctx.accounts.config.callback_route(RANK_ADL_OUTPUT_BYTES)?;
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        callback_url,
+        vec![RankAdlCallback::callback_ix(&[CallbackAccount {
+            pubkey: ctx.accounts.adl_ranking.key(),
+            is_writable: true,
+        }])],
+        num_callback_txs,
+    )?;
+
+    msg!("ADL ranking queued for {} candidates", candidates.len());
+    Ok(())
+}
+
+/// Callback after ranking ADL candidates
+/// Stores the revealed order. A result for candidates that have since been
+/// replaced by a newer ranking request is ignored.
+#[arcium_callback(encrypted_ix = "rank_adl")]
+pub fn rank_adl_callback(
+    ctx: Context<RankAdlCallback>,
+    output: ComputationOutputs<RankAdlOutput>,
+) -> Result<()> {
+    let (order, computation_offset, bankrupt_state_version, state_versions) = match output {
+        ComputationOutputs::Success(RankAdlOutput {
+            field_0:
+                RankAdlOutputStruct0 {
+                    field_0: order,
+                    field_1: computation_offset,
+                    field_2: bankrupt_state_version,
+                    field_3: state_versions,
+                },
+        }) => (
+            order,
+            computation_offset,
+            bankrupt_state_version,
+            state_versions,
+        ),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let ranking = &mut ctx.accounts.adl_ranking;
+    if computation_offset != ranking.computation_offset
+        || bankrupt_state_version != ranking.bankrupt_state_version
+        || state_versions != ranking.state_versions
+    {
+        msg!("Outdated ADL ranking ignored");
+        return Ok(());
+    }
+
+    ranking.order = order;
+    ranking.is_ranked = true;
+
+    emit!(AdlRanked {
+        margin_account: ranking.margin_account,
+        order,
+        candidates: ranking.candidate_count,
+        computation_offset,
+    });
+
+    msg!("ADL ranking computation completed");
+    Ok(())
+}
+
+/// Initialize computation definition for auto-deleveraging
+pub fn init_auto_deleverage_comp_def(ctx: Context<InitAutoDeleverageCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/auto_deleverage.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Auto-deleverage computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Force-close the `count` top-ranked candidates against a bankrupt account
+/// Remaining accounts are those candidates, in ranking order. MPC closes
+/// them against the bankrupt position at its bankruptcy price until it is
+/// fully closed; candidates that turn out not to be eligible are left
+/// untouched. The ranking is consumed. Only an authorized matcher can
+/// queue it, like the ranking it executes.
+///
+/// Once the callback lands the account may be liquidated again, and any
+/// bad debt left after the insurance fund is socialized.
+///
+/// Requirements:
+/// - The payer must be an authorized matcher
+/// - The bankrupt account must be flagged for auto-deleveraging
+/// - The ranking must be computed from the current state of every account
+/// - The mark price must be fresh
+/// - Socialized losses must already be applied to every account
+pub fn auto_deleverage(
+    ctx: Context<AutoDeleverage>,
+    computation_offset: u64,
+    count: u8,
+) -> Result<()> {
+    let count = count as usize;
+    let candidate_infos = ctx.remaining_accounts;
+    let ranking = &ctx.accounts.adl_ranking;
+    require!(
+        count > 0 && count <= ranking.candidate_count as usize && count == candidate_infos.len(),
+        ErrorCode::InvalidBatchSize
+    );
+
+    let market = &ctx.accounts.market;
+    let current_slot = Clock::get()?.slot;
+    require!(
+        market.is_price_fresh(current_slot),
+        ErrorCode::StaleMarkPrice
+    );
+
+    let bankrupt = &ctx.accounts.margin_account;
+    require!(bankrupt.adl_pending, ErrorCode::AdlNotPending);
+    require!(
+        ranking.is_ranked && ranking.bankrupt_state_version == bankrupt.state_version,
+        ErrorCode::StaleAdlRanking
+    );
+    require!(
+        bankrupt.has_current_loss_index(market),
+        ErrorCode::PendingSocializedLoss
+    );
+
+    let mut candidates = Vec::with_capacity(count);
+    let mut state_versions = [0u64; ADL_ACCOUNTS];
+    let computation_key = ctx.accounts.computation_account.key();
+    let mut callback_accounts = Vec::with_capacity(count + 3);
+    callback_accounts.push(CallbackAccount {
+        pubkey: bankrupt.key(),
+        is_writable: true,
+    });
+    callback_accounts.push(CallbackAccount {
+        pubkey: ctx.accounts.protocol_fees.key(),
+        is_writable: true,
+    });
+    callback_accounts.push(CallbackAccount {
+        pubkey: computation_key,
+        is_writable: false,
+    });
+    for (rank, info) in candidate_infos.iter().enumerate() {
+        let slot = ranking
+            .ranked_slot(rank)
+            .ok_or(ErrorCode::StaleAdlRanking)?;
+        require_keys_eq!(
+            *info.key,
+            ranking.candidates[slot],
+            ErrorCode::StaleAdlRanking
+        );
+        let margin: MarginAccount = load_account(info)?;
+        require!(
+            margin.state_version == ranking.state_versions[slot],
+            ErrorCode::StaleAdlRanking
+        );
+        require!(
+            margin.has_current_loss_index(market),
+            ErrorCode::PendingSocializedLoss
+        );
+        state_versions[rank] = margin.state_version;
+        candidates.push(margin);
+        callback_accounts.push(CallbackAccount {
+            pubkey: info.key(),
+            is_writable: true,
+        });
+    }
+
+    // Enc<Mxe, MarginBalances>, Enc<Mxe, Position> of the bankrupt account,
+    // the candidates' balances and positions in ranking order (unused slots
+    // padded), Enc<Mxe, OpenInterest>, then the public inputs
+    let mut args = vec![
+        Argument::PlaintextU128(bankrupt.nonce),
+        Argument::EncryptedU64(bankrupt.encrypted_collateral),
+        Argument::EncryptedU64(bankrupt.encrypted_debt),
+    ];
+    push_position_args(&mut args, bankrupt);
+    for rank in 0..ADL_ACCOUNTS {
+        match candidates.get(rank) {
+            Some(margin) => {
+                args.push(Argument::PlaintextU128(margin.nonce));
+                args.push(Argument::EncryptedU64(margin.encrypted_collateral));
+                args.push(Argument::EncryptedU64(margin.encrypted_debt));
+            }
+            None => {
+                args.push(Argument::PlaintextU128(0));
+                args.push(Argument::EncryptedU64([0u8; 32]));
+                args.push(Argument::EncryptedU64([0u8; 32]));
+            }
+        }
+    }
+    for rank in 0..ADL_ACCOUNTS {
+        match candidates.get(rank) {
+            Some(margin) => push_position_args(&mut args, margin),
+            None => push_padding_position_args(&mut args),
+        }
+    }
+    push_open_interest_args(&mut args, &ctx.accounts.protocol_fees);
+    let active_mask = ((1u16 << count) - 1) as u8;
+    args.push(Argument::PlaintextU8(active_mask));
+    args.push(Argument::PlaintextU64(market.mark_price));
+    args.push(Argument::PlaintextU64(bankrupt.state_version));
+    args.extend(
+        state_versions
+            .iter()
+            .map(|version| Argument::PlaintextU64(*version)),
+    );
+
+    // Keyed by the computation, so a timed-out round against the same
+    // account cannot release a lock a later one took over
+    ctx.accounts
+        .protocol_fees
+        .acquire(computation_key, current_slot)?;
+    ctx.accounts.adl_ranking.is_ranked = false;
+
+    let (callback_url, num_callback_txs) = ctx
+        .accounts
+        .config
+        .callback_route(AUTO_DELEVERAGE_OUTPUT_BYTES)?;
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        callback_url,
+        vec![AutoDeleverageCallback::callback_ix(&callback_accounts)],
+        num_callback_txs,
+    )?;
+
+    msg!("Auto-deleveraging queued against {} positions", count);
+    Ok(())
+}
+
+/// Callback after auto-deleveraging
+/// Remaining accounts are the candidates in ranking order. Writes every
+/// re-encrypted balance and position and the open interest back, and marks
+/// the round as run so the bankrupt account can be liquidated again. The
+/// result is dropped if any account has moved past the state it was
+/// computed from, or if the protocol fee lock expired and was taken over
+/// meanwhile.
+#[arcium_callback(encrypted_ix = "auto_deleverage")]
+pub fn auto_deleverage_callback(
+    ctx: Context<AutoDeleverageCallback>,
+    output: ComputationOutputs<AutoDeleverageOutput>,
+) -> Result<()> {
+    let (
+        bankrupt_balances,
+        bankrupt_position,
+        balances,
+        positions,
+        open_interest,
+        bankrupt_state_version,
+        state_versions,
+    ) = match output {
+        ComputationOutputs::Success(AutoDeleverageOutput {
+            field_0:
+                AutoDeleverageOutputStruct0 {
+                    field_0: bankrupt_balances,
+                    field_1: bankrupt_position,
+                    field_2: balances,
+                    field_3: positions,
+                    field_4: open_interest,
+                    field_5: bankrupt_state_version,
+                    field_6: state_versions,
+                },
+        }) => (
+            bankrupt_balances,
+            bankrupt_position,
+            balances,
+            positions,
+            open_interest,
+            bankrupt_state_version,
+            state_versions,
+        ),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let holds_lock = ctx
+        .accounts
+        .protocol_fees
+        .release(&ctx.accounts.computation_account.key());
+
+    let candidate_infos = ctx.remaining_accounts;
+    let mut candidates = Vec::with_capacity(candidate_infos.len());
+    for info in candidate_infos {
+        candidates.push(load_account::<MarginAccount>(info)?);
+    }
+    let is_current = bankrupt_state_version == ctx.accounts.margin_account.state_version
+        && candidates
+            .iter()
+            .zip(state_versions.iter())
+            .all(|(margin, version)| margin.state_version == *version);
+    if !is_current {
+        msg!("Outdated auto-deleveraging result ignored");
+        return Ok(());
+    }
+
+    if !holds_lock {
+        msg!("Fee lock expired, auto-deleveraging result dropped");
+        return Ok(());
+    }
+
+    let protocol_fees = &mut ctx.accounts.protocol_fees;
+    protocol_fees.encrypted_open_interest = open_interest.ciphertexts;
+    protocol_fees.open_interest_nonce = open_interest.nonce;
+
+    for (((info, mut margin), result), position) in candidate_infos
+        .iter()
+        .zip(candidates)
+        .zip(balances.iter())
+        .zip(positions.iter())
+    {
+        margin.encrypted_collateral = result.ciphertexts[0];
+        margin.encrypted_debt = result.ciphertexts[1];
+        margin.nonce = result.nonce;
+        margin.encrypted_position = position.ciphertexts;
+        margin.position_nonce = position.nonce;
+        margin.mark_state_changed();
+        store_account(info, &margin)?;
+    }
+
+    let margin_account = &mut ctx.accounts.margin_account;
+    margin_account.encrypted_collateral = bankrupt_balances.ciphertexts[0];
+    margin_account.encrypted_debt = bankrupt_balances.ciphertexts[1];
+    margin_account.nonce = bankrupt_balances.nonce;
+    margin_account.encrypted_position = bankrupt_position.ciphertexts;
+    margin_account.position_nonce = bankrupt_position.nonce;
+    margin_account.mark_state_changed();
+    margin_account.adl_completed = true;
+
+    emit!(AutoDeleveraged {
+        margin_account: margin_account.key(),
+        positions: candidate_infos.len() as u8,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Auto-deleveraging computation completed");
+    Ok(())
+}
+
+// ========== ACCOUNT STRUCTS ==========
+
+/// Initialize rank_adl computation definition
+#[init_computation_definition_accounts("rank_adl", payer)]
+#[derive(Accounts)]
+pub struct InitRankAdlCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for ranking ADL candidates
+/// Remaining accounts: the candidate margin accounts
+#[queue_computation_accounts("rank_adl", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct QueueAdlRanking<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RANK_ADL))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Program config holding the authorized matchers and the callback server
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        constraint = config.is_matcher(&payer.key()) @ ErrorCode::UnauthorizedMatcher,
+    )]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    /// Market providing the oracle mark price
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// The bankrupt margin account
+    #[account(
+        seeds = [MarginAccount::SEED_PREFIX, margin_account.owner.as_ref()],
+        bump = margin_account.bump
+    )]
+    pub margin_account: Box<Account<'info, MarginAccount>>,
+
+    /// Ranking of the bankrupt account's candidates
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [AdlRanking::SEED_PREFIX, margin_account.key().as_ref()],
+        bump,
+        space = AdlRanking::SPACE,
+    )]
+    pub adl_ranking: Box<Account<'info, AdlRanking>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Rank ADL callback
+#[callback_accounts("rank_adl")]
+#[derive(Accounts)]
+pub struct RankAdlCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RANK_ADL))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Ranking being computed
+    #[account(mut)]
+    pub adl_ranking: Account<'info, AdlRanking>,
+}
+
+/// Initialize auto_deleverage computation definition
+#[init_computation_definition_accounts("auto_deleverage", payer)]
+#[derive(Accounts)]
+pub struct InitAutoDeleverageCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for auto-deleveraging
+/// Remaining accounts: the top-ranked candidate margin accounts, in ranking order
+#[queue_computation_accounts("auto_deleverage", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct AutoDeleverage<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_AUTO_DELEVERAGE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Program config holding the authorized matchers and the callback server
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        constraint = config.is_matcher(&payer.key()) @ ErrorCode::UnauthorizedMatcher,
+    )]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    /// Market providing the oracle mark price
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// The bankrupt margin account
+    #[account(
+        seeds = [MarginAccount::SEED_PREFIX, margin_account.owner.as_ref()],
+        bump = margin_account.bump
+    )]
+    pub margin_account: Box<Account<'info, MarginAccount>>,
+
+    /// Ranking of the bankrupt account's candidates
+    #[account(
+        mut,
+        seeds = [AdlRanking::SEED_PREFIX, margin_account.key().as_ref()],
+        bump = adl_ranking.bump,
+    )]
+    pub adl_ranking: Box<Account<'info, AdlRanking>>,
+
+    /// Protocol fee account holding the encrypted open interest
+    #[account(
+        mut,
+        seeds = [ProtocolFees::SEED_PREFIX],
+        bump = protocol_fees.bump,
+    )]
+    pub protocol_fees: Box<Account<'info, ProtocolFees>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Auto-deleverage callback
+/// Remaining accounts: the candidate margin accounts, in ranking order
+#[callback_accounts("auto_deleverage")]
+#[derive(Accounts)]
+pub struct AutoDeleverageCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_AUTO_DELEVERAGE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// The bankrupt margin account
+    #[account(mut)]
+    pub margin_account: Account<'info, MarginAccount>,
+
+    /// Protocol fee account holding the encrypted open interest
+    #[account(mut)]
+    pub protocol_fees: Account<'info, ProtocolFees>,
+
+    /// CHECK: computation_account that took the fee lock, matched by key only
+    pub computation_account: UncheckedAccount<'info>,
+}
+
+// ========== EVENTS ==========
+
+/// Emitted when ADL candidates are ranked. Only the order is public.
+#[event]
+pub struct AdlRanked {
+    pub margin_account: Pubkey,
+    /// Entry k is the candidate slot of the k-th ranked account
+    pub order: [u8; 8],
+    pub candidates: u8,
+    pub computation_offset: u64,
+}
+
+/// Emitted when positions are force-closed against a bankrupt account.
+/// Sizes and prices stay encrypted.
+#[event]
+pub struct AutoDeleveraged {
+    pub margin_account: Pubkey,
+    pub positions: u8,
+    pub timestamp: i64,
+}
@@ -1,141 +1,540 @@
 use anchor_lang::prelude::*;
-// use arcium_anchor::prelude::*;
+use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
 
+use crate::error::ErrorCode;
+use crate::instructions::margin_arcium::{push_padding_position_args, push_position_args};
+use crate::instructions::shared::{load_account, store_account, validate_callback_ixs};
 use crate::state::margin_account::MarginAccount;
+use crate::state::market::Market;
+use crate::state::program_config::ProgramConfig;
+use crate::state::SignerAccount;
+use crate::ID;
+use crate::ID as ID_CONST;
 
-// Computation definition offset for check_health encrypted instruction
-// TODO: Implement health check circuit and use this constant
-#[allow(dead_code)]
-const COMP_DEF_OFFSET_CHECK_HEALTH: u32 = 0; // Placeholder for future health check implementation
+// Computation definition offsets for health check encrypted instructions
+const COMP_DEF_OFFSET_CHECK_HEALTH: u32 = comp_def_offset("check_health");
+const COMP_DEF_OFFSET_CHECK_HEALTH_BATCH: u32 = comp_def_offset("check_health_batch");
+
+/// Margin accounts checked per sweep (must match the circuit)
+pub const HEALTH_SWEEP_ACCOUNTS: usize = 8;
+
+/// Sweep output size: the liquidatable bitmap, plus the computation offset,
+/// slot and per-account state versions passed through
+const HEALTH_SWEEP_OUTPUT_BYTES: usize = 1 + 8 + 8 + HEALTH_SWEEP_ACCOUNTS * 8;
 
 /// Initialize the computation definition for health checks
 /// This registers the Arcium MPC computation on-chain
 /// Only needs to be called once after program deployment
-/// TODO: Implement with Arcium SDK once circuits are built
-pub fn init_health_check_comp_def(_ctx: Context<InitHealthCheckCompDef>) -> Result<()> {
-    // init_comp_def(ctx.accounts, COMP_DEF_OFFSET_CHECK_HEALTH, None, None)?;
-    msg!("Health check computation definition initialized");
+pub fn init_health_check_comp_def(ctx: Context<InitHealthCheckCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/check_health.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Health check computation definition initialized with circuit");
     Ok(())
 }
 
 /// Queue a health check computation for a margin account
-/// Submits encrypted collateral and debt data to Arcium MPC
-/// The MPC cluster will compute if the position is liquidatable
-/// TODO: Implement with Arcium SDK once circuits are built
-pub fn queue_health_check(
-    _ctx: Context<QueueHealthCheck>,
-    _computation_offset: u64,
-    _ciphertext_collateral: [u8; 32],
-    _ciphertext_debt: [u8; 32],
-    _ciphertext_price: [u8; 32],
-    _pub_key: [u8; 32],
-    _nonce: u128,
-) -> Result<()> {
-    // TODO: Implement Arcium queue_computation call
-    msg!("Health check queued (placeholder)");
+/// The check reads the account's stored encrypted balances and position and
+/// values the position at the oracle mark price, so no caller-supplied data
+/// enters the computation. Anyone can queue it.
+/// The MPC cluster will compare equity to the market's maintenance margin,
+/// reveal only the liquidatable bit (bound to this computation offset, the
+/// current slot and the account's state version) and encrypt the margin ratio for the owner's viewing key.
+/// Until the owner registers a viewing key, a zero ratio is encrypted instead.
+pub fn queue_health_check(ctx: Context<QueueHealthCheck>, computation_offset: u64) -> Result<()> {
+    let current_slot = Clock::get()?.slot;
+    let market = &ctx.accounts.market;
+    require!(
+        market.is_price_fresh(current_slot),
+        ErrorCode::StaleMarkPrice
+    );
+
+    // Enc<Mxe, MarginBalances>, Enc<Mxe, Position>, then the public inputs
+    let margin = &mut ctx.accounts.margin_account;
+    require!(
+        margin.has_current_loss_index(market),
+        ErrorCode::PendingSocializedLoss
+    );
+    let mut args = vec![
+        Argument::PlaintextU128(margin.nonce),
+        Argument::EncryptedU64(margin.encrypted_collateral),
+        Argument::EncryptedU64(margin.encrypted_debt),
+    ];
+    push_position_args(&mut args, margin);
+    // Shared owner: viewing key, then the account's next viewing nonce, so
+    // the margin ratio is never encrypted twice under the same key and nonce
+    let viewing_nonce = margin.next_viewing_nonce()?;
+    args.push(Argument::ArcisPubkey(margin.viewing_key));
+    args.push(Argument::PlaintextU128(viewing_nonce));
+    args.push(Argument::PlaintextBool(margin.has_viewing_key()));
+    args.push(Argument::PlaintextU64(market.mark_price));
+    args.push(Argument::PlaintextU64(market.maintenance_margin_bps as u64));
+    args.push(Argument::PlaintextU64(computation_offset));
+    args.push(Argument::PlaintextU64(current_slot));
+    args.push(Argument::PlaintextU64(margin.state_version));
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![CheckHealthCallback::callback_ix(&[CallbackAccount {
+            pubkey: ctx.accounts.margin_account.key(),
+            is_writable: true,
+        }])],
+        1,
+    )?;
+
+    msg!("Health check queued to MPC cluster");
     Ok(())
 }
 
-/// Callback function called by Arcium MPC cluster after health check completes
-/// Receives the encrypted result (is_liquidatable boolean)
-/// Emits an event that can trigger liquidation
-/// TODO: Add #[arcium_callback] macro once circuits are built
-pub fn health_check_callback(
-    ctx: Context<HealthCheckCallback>,
-    encrypted_result: [u8; 32],
-    nonce: [u8; 16],
+/// Callback after the health check computation
+/// Sets `is_liquidatable` from the revealed bit, together with the slot and
+/// computation offset it was computed at. A result older than the one
+/// already recorded (callbacks can land out of order), or computed from a
+/// state the account has since moved past, is ignored.
+/// The owner's encrypted margin ratio is emitted with the result.
+#[arcium_callback(encrypted_ix = "check_health")]
+pub fn check_health_callback(
+    ctx: Context<CheckHealthCallback>,
+    output: ComputationOutputs<CheckHealthOutput>,
 ) -> Result<()> {
-    // Emit event with encrypted liquidation flag
-    // Off-chain services can decrypt this and trigger liquidation if needed
+    let (is_liquidatable, margin_ratio, computation_offset, check_slot, state_version) =
+        match output {
+            ComputationOutputs::Success(CheckHealthOutput {
+                field_0:
+                    CheckHealthOutputStruct0 {
+                        field_0: is_liquidatable,
+                        field_1: margin_ratio,
+                        field_2: computation_offset,
+                        field_3: check_slot,
+                        field_4: state_version,
+                    },
+            }) => (
+                is_liquidatable,
+                margin_ratio,
+                computation_offset,
+                check_slot,
+                state_version,
+            ),
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+    let margin_account = &mut ctx.accounts.margin_account;
+    if !margin_account.record_health_check(
+        is_liquidatable,
+        check_slot,
+        computation_offset,
+        state_version,
+    ) {
+        msg!("Outdated health check result ignored");
+        return Ok(());
+    }
+
     emit!(HealthCheckResult {
-        margin_account: ctx.accounts.margin_account.key(),
-        encrypted_is_liquidatable: encrypted_result,
-        nonce,
+        margin_account: margin_account.key(),
+        is_liquidatable,
+        encrypted_margin_ratio: margin_ratio.ciphertexts[0],
+        margin_ratio_nonce: margin_ratio.nonce.to_le_bytes(),
+        computation_offset,
+        slot: check_slot,
+    });
+
+    msg!("Health check computation completed");
+    Ok(())
+}
+
+/// Initialize the computation definition for batched health sweeps
+pub fn init_health_sweep_comp_def(ctx: Context<InitHealthSweepCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/check_health_batch.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Health sweep computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Check up to HEALTH_SWEEP_ACCOUNTS margin accounts in one computation
+/// The margin accounts are passed as remaining accounts. Same rule and
+/// inputs as `queue_health_check`, but only the bitmap of liquidatable
+/// accounts is revealed, so keepers can scan the book cheaply. Anyone can
+/// queue it. Large outputs are delivered through the callback server.
+pub fn queue_health_sweep(ctx: Context<QueueHealthSweep>, computation_offset: u64) -> Result<()> {
+    let margin_infos = ctx.remaining_accounts;
+    require!(
+        !margin_infos.is_empty() && margin_infos.len() <= HEALTH_SWEEP_ACCOUNTS,
+        ErrorCode::InvalidBatchSize
+    );
+
+    let current_slot = Clock::get()?.slot;
+    let market = &ctx.accounts.market;
+    require!(
+        market.is_price_fresh(current_slot),
+        ErrorCode::StaleMarkPrice
+    );
+
+    let mut margins = Vec::with_capacity(margin_infos.len());
+    let mut callback_accounts = Vec::with_capacity(margin_infos.len());
+    for (slot, info) in margin_infos.iter().enumerate() {
+        require!(
+            !margin_infos[..slot]
+                .iter()
+                .any(|other| other.key == info.key),
+            ErrorCode::DuplicateBatchAccount
+        );
+        let margin: MarginAccount = load_account(info)?;
+        require!(
+            margin.has_current_loss_index(market),
+            ErrorCode::PendingSocializedLoss
+        );
+        margins.push(margin);
+        callback_accounts.push(CallbackAccount {
+            pubkey: info.key(),
+            is_writable: true,
+        });
+    }
+
+    // [Enc<Mxe, MarginBalances>; HEALTH_SWEEP_ACCOUNTS], then
+    // [Enc<Mxe, Position>; HEALTH_SWEEP_ACCOUNTS], unused slots padded
+    let mut args = Vec::with_capacity(HEALTH_SWEEP_ACCOUNTS * 8 + 5);
+    for slot in 0..HEALTH_SWEEP_ACCOUNTS {
+        match margins.get(slot) {
+            Some(margin) => {
+                args.push(Argument::PlaintextU128(margin.nonce));
+                args.push(Argument::EncryptedU64(margin.encrypted_collateral));
+                args.push(Argument::EncryptedU64(margin.encrypted_debt));
+            }
+            None => {
+                args.push(Argument::PlaintextU128(0));
+                args.push(Argument::EncryptedU64([0u8; 32]));
+                args.push(Argument::EncryptedU64([0u8; 32]));
+            }
+        }
+    }
+    for slot in 0..HEALTH_SWEEP_ACCOUNTS {
+        match margins.get(slot) {
+            Some(margin) => push_position_args(&mut args, margin),
+            None => push_padding_position_args(&mut args),
+        }
+    }
+    let active_mask = ((1u16 << margins.len()) - 1) as u8;
+    args.push(Argument::PlaintextU8(active_mask));
+    args.push(Argument::PlaintextU64(market.mark_price));
+    args.push(Argument::PlaintextU64(market.maintenance_margin_bps as u64));
+    args.push(Argument::PlaintextU64(computation_offset));
+    args.push(Argument::PlaintextU64(current_slot));
+    for slot in 0..HEALTH_SWEEP_ACCOUNTS {
+        let state_version = margins.get(slot).map_or(0, |margin| margin.state_version);
+        args.push(Argument::PlaintextU64(state_version));
+    }
+
+    let (callback_url, num_callback_txs) = ctx
+        .accounts
+        .config
+        .callback_route(HEALTH_SWEEP_OUTPUT_BYTES)?;
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        callback_url,
+        vec![CheckHealthBatchCallback::callback_ix(&callback_accounts)],
+        num_callback_txs,
+    )?;
+
+    msg!("Health sweep queued for {} accounts", margins.len());
+    Ok(())
+}
+
+/// Callback after a health sweep
+/// Remaining accounts are the margin accounts in slot order. Sets each
+/// account's `is_liquidatable` from its bit in the revealed bitmap, with the
+/// same out-of-order protection as `check_health_callback`.
+#[arcium_callback(encrypted_ix = "check_health_batch")]
+pub fn check_health_batch_callback(
+    ctx: Context<CheckHealthBatchCallback>,
+    output: ComputationOutputs<CheckHealthBatchOutput>,
+) -> Result<()> {
+    let (bitmap, computation_offset, check_slot, state_versions) = match output {
+        ComputationOutputs::Success(CheckHealthBatchOutput {
+            field_0:
+                CheckHealthBatchOutputStruct0 {
+                    field_0: bitmap,
+                    field_1: computation_offset,
+                    field_2: check_slot,
+                    field_3: state_versions,
+                },
+        }) => (bitmap, computation_offset, check_slot, state_versions),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let margin_infos = ctx.remaining_accounts;
+    let mut recorded: u8 = 0;
+    for (slot, info) in margin_infos.iter().enumerate() {
+        let mut margin: MarginAccount = load_account(info)?;
+        let is_liquidatable = (bitmap >> slot) & 1 == 1;
+        if margin.record_health_check(
+            is_liquidatable,
+            check_slot,
+            computation_offset,
+            state_versions[slot],
+        ) {
+            store_account(info, &margin)?;
+            recorded += 1;
+        }
+    }
+
+    emit!(HealthSweepCompleted {
+        liquidatable_bitmap: bitmap,
+        accounts: margin_infos.len() as u8,
+        recorded,
+        computation_offset,
+        slot: check_slot,
     });
 
+    msg!("Health sweep computation completed");
     Ok(())
 }
 
 /// Accounts for initializing health check computation definition
+#[init_computation_definition_accounts("check_health", payer)]
 #[derive(Accounts)]
 pub struct InitHealthCheckCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
-    /// Computation definition account
-    /// CHECK: Arcium computation definition
-    #[account(
-        mut,
-        seeds = [b"comp_def"],
-        bump
-    )]
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
     pub comp_def_account: UncheckedAccount<'info>,
 
+    pub arcium_program: Program<'info, Arcium>,
     pub system_program: Program<'info, System>,
 }
 
 /// Accounts for queuing a health check
+#[queue_computation_accounts("check_health", payer)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64)]
 pub struct QueueHealthCheck<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
     /// The margin account being checked
     #[account(
+        mut,
         seeds = [MarginAccount::SEED_PREFIX, margin_account.owner.as_ref()],
         bump = margin_account.bump
     )]
     pub margin_account: Account<'info, MarginAccount>,
 
-    /// PDA for signing Arcium transactions
-    /// CHECK: PDA for Arcium signing
+    /// Market providing the oracle mark price
     #[account(
-        seeds = [b"sign_pda"],
-        bump
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
     )]
-    pub sign_pda_account: UncheckedAccount<'info>,
+    pub market: Account<'info, Market>,
 
-    /// Computation account
-    /// CHECK: Arcium computation account
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Accounts for health check callback
+#[callback_accounts("check_health")]
+#[derive(Accounts)]
+pub struct CheckHealthCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// The margin account that was checked
     #[account(mut)]
-    pub computation_account: UncheckedAccount<'info>,
+    pub margin_account: Account<'info, MarginAccount>,
+}
 
-    /// Arcium cluster account
-    /// CHECK: Validated by Arcium program
-    pub cluster_account: UncheckedAccount<'info>,
+/// Accounts for initializing the health sweep computation definition
+#[init_computation_definition_accounts("check_health_batch", payer)]
+#[derive(Accounts)]
+pub struct InitHealthSweepCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
 
-    /// MXE account
-    /// CHECK: Validated by Arcium program
-    pub mxe_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
 
-    /// Mempool account
-    /// CHECK: Validated by Arcium program
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for queuing a health sweep
+#[queue_computation_accounts("check_health_batch", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct QueueHealthSweep<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
     pub mempool_account: UncheckedAccount<'info>,
 
-    /// Executing pool account
-    /// CHECK: Validated by Arcium program
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
     pub executing_pool: UncheckedAccount<'info>,
 
-    /// Computation definition account
-    /// CHECK: Arcium computation definition
-    #[account(mut)]
-    pub comp_def_account: UncheckedAccount<'info>,
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_BATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Program config holding the callback server
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    /// Market providing the oracle mark price
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
 
     pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
 }
 
-/// Accounts for health check callback
+/// Accounts for health sweep callback
+/// Remaining accounts: the margin accounts that were checked
+#[callback_accounts("check_health_batch")]
 #[derive(Accounts)]
-pub struct HealthCheckCallback<'info> {
-    /// The margin account that was checked
-    pub margin_account: Account<'info, MarginAccount>,
+pub struct CheckHealthBatchCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CHECK_HEALTH_BATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
 }
 
 /// Event emitted when health check completes
 #[event]
 pub struct HealthCheckResult {
     pub margin_account: Pubkey,
-    pub encrypted_is_liquidatable: [u8; 32],
-    pub nonce: [u8; 16],
+    pub is_liquidatable: bool,
+    /// Margin ratio in bps, encrypted for the owner's viewing key
+    pub encrypted_margin_ratio: [u8; 32],
+    pub margin_ratio_nonce: [u8; 16],
+    pub computation_offset: u64,
+    pub slot: u64,
+}
+
+/// Event emitted when a health sweep completes
+#[event]
+pub struct HealthSweepCompleted {
+    /// Bit i set = remaining account i is liquidatable
+    pub liquidatable_bitmap: u8,
+    pub accounts: u8,
+    /// Accounts whose flag was updated (older results are ignored)
+    pub recorded: u8,
+    pub computation_offset: u64,
+    pub slot: u64,
 }
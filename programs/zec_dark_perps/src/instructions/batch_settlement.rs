@@ -0,0 +1,493 @@
+use anchor_lang::prelude::*;
+use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
+
+use crate::error::ErrorCode;
+use crate::instructions::margin_arcium::{
+    push_open_interest_args, push_padding_position_args, push_position_args,
+};
+use crate::instructions::shared::{load_account, store_account, validate_callback_ixs};
+use crate::state::fill::{Fill, FillStatus};
+use crate::state::margin_account::MarginAccount;
+use crate::state::market::Market;
+use crate::state::program_config::ProgramConfig;
+use crate::state::protocol_fees::ProtocolFees;
+use crate::state::SignerAccount;
+use crate::ID;
+use crate::ID as ID_CONST;
+
+// Computation definition offset for settle_batch encrypted instruction
+const COMP_DEF_OFFSET_SETTLE_BATCH: u32 = comp_def_offset("settle_batch");
+
+/// Fills settled per batch (must match the circuit)
+pub const SETTLE_BATCH_FILLS: usize = 4;
+
+/// Distinct margin accounts per batch (must match the circuit)
+pub const SETTLE_BATCH_ACCOUNTS: usize = 8;
+
+/// Circuit output size: one Enc<Mxe, MarginBalances> (nonce + 2 ciphertexts)
+/// and one Enc<Mxe, Position> (nonce + 3 ciphertexts) per account slot, the
+/// Enc<Mxe, ProtocolFees> (nonce + 1 ciphertext), the Enc<Mxe, OpenInterest>
+/// (nonce + 2 ciphertexts), the success bit, the state version of each slot
+/// and the account count
+const SETTLE_BATCH_OUTPUT_BYTES: usize =
+    SETTLE_BATCH_ACCOUNTS * (16 + 2 * 32 + 16 + 3 * 32 + 8) + 16 + 32 + 16 + 2 * 32 + 1 + 1;
+
+/// Initialize computation definition for batched settlement
+pub fn init_settle_batch_comp_def(ctx: Context<InitSettleBatchCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/settle_batch.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Settle batch computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Settle up to SETTLE_BATCH_FILLS matched fills in one MPC computation
+/// Called by an authorized matching engine.
+///
+/// Remaining accounts: the first `fill_count` are fills, the rest are the
+/// distinct margin accounts they reference; every margin account must be
+/// referenced by at least one fill. Per-account deltas, including
+/// maker and taker fees, are netted inside MPC, net positions are updated,
+/// and the batch settles all-or-nothing. The output does not fit in one
+/// transaction, so it is delivered through the callback server.
+pub fn queue_settle_batch(
+    ctx: Context<QueueSettleBatch>,
+    computation_offset: u64,
+    fill_count: u8,
+) -> Result<()> {
+    let fill_count = fill_count as usize;
+    let remaining = ctx.remaining_accounts;
+    require!(
+        fill_count > 0 && fill_count <= SETTLE_BATCH_FILLS && fill_count < remaining.len(),
+        ErrorCode::InvalidBatchSize
+    );
+    let (fill_infos, margin_infos) = remaining.split_at(fill_count);
+    require!(
+        margin_infos.len() <= SETTLE_BATCH_ACCOUNTS,
+        ErrorCode::InvalidBatchSize
+    );
+    require!(
+        ctx.accounts.market.is_price_fresh(Clock::get()?.slot),
+        ErrorCode::StaleMarkPrice
+    );
+
+    let mut args = Vec::with_capacity(SETTLE_BATCH_ACCOUNTS * 8 + SETTLE_BATCH_FILLS * 5 + 10);
+    let computation_key = ctx.accounts.computation_account.key();
+    let mut callback_accounts = Vec::with_capacity(remaining.len() + 2);
+    callback_accounts.push(CallbackAccount {
+        pubkey: ctx.accounts.protocol_fees.key(),
+        is_writable: true,
+    });
+    callback_accounts.push(CallbackAccount {
+        pubkey: computation_key,
+        is_writable: false,
+    });
+
+    // [Enc<Mxe, MarginBalances>; SETTLE_BATCH_ACCOUNTS], unused slots padded
+    let mut state_versions = [0u64; SETTLE_BATCH_ACCOUNTS];
+    for slot in 0..SETTLE_BATCH_ACCOUNTS {
+        match margin_infos.get(slot) {
+            Some(info) => {
+                require!(
+                    !margin_infos[..slot]
+                        .iter()
+                        .any(|other| other.key == info.key),
+                    ErrorCode::DuplicateBatchAccount
+                );
+                let margin: MarginAccount = load_account(info)?;
+                require!(
+                    margin.has_current_loss_index(&ctx.accounts.market),
+                    ErrorCode::PendingSocializedLoss
+                );
+                args.push(Argument::PlaintextU128(margin.nonce));
+                args.push(Argument::EncryptedU64(margin.encrypted_collateral));
+                args.push(Argument::EncryptedU64(margin.encrypted_debt));
+                state_versions[slot] = margin.state_version;
+                callback_accounts.push(CallbackAccount {
+                    pubkey: info.key(),
+                    is_writable: true,
+                });
+            }
+            None => {
+                args.push(Argument::PlaintextU128(0));
+                args.push(Argument::EncryptedU64([0u8; 32]));
+                args.push(Argument::EncryptedU64([0u8; 32]));
+            }
+        }
+    }
+
+    // [Enc<Mxe, Position>; SETTLE_BATCH_ACCOUNTS], same slots
+    for slot in 0..SETTLE_BATCH_ACCOUNTS {
+        match margin_infos.get(slot) {
+            Some(info) => push_position_args(&mut args, &load_account::<MarginAccount>(info)?),
+            None => push_padding_position_args(&mut args),
+        }
+    }
+
+    // [Enc<Mxe, Fill>; SETTLE_BATCH_FILLS], then the public account slot of
+    // each fill's buyer and seller
+    let margin_slot = |key: &Pubkey| -> Result<u8> {
+        margin_infos
+            .iter()
+            .position(|info| info.key == key)
+            .map(|slot| slot as u8)
+            .ok_or_else(|| ErrorCode::OrderAccountMismatch.into())
+    };
+    let mut buyer_idx = [0u8; SETTLE_BATCH_FILLS];
+    let mut seller_idx = [0u8; SETTLE_BATCH_FILLS];
+    let mut fill_mask: u8 = 0;
+    let mut maker_mask: u8 = 0;
+    let mut referenced_mask: u8 = 0;
+
+    for slot in 0..SETTLE_BATCH_FILLS {
+        match fill_infos.get(slot) {
+            Some(info) => {
+                require!(
+                    !fill_infos[..slot].iter().any(|other| other.key == info.key),
+                    ErrorCode::DuplicateBatchAccount
+                );
+                let mut fill: Fill = load_account(info)?;
+                require!(
+                    fill.status == FillStatus::Matched,
+                    ErrorCode::InvalidFillStatus
+                );
+                buyer_idx[slot] = margin_slot(&fill.buyer_margin)?;
+                seller_idx[slot] = margin_slot(&fill.seller_margin)?;
+                referenced_mask |= (1 << buyer_idx[slot]) | (1 << seller_idx[slot]);
+
+                args.push(Argument::PlaintextU128(fill.fill_nonce));
+                args.push(Argument::EncryptedU64(fill.encrypted_fill[0]));
+                args.push(Argument::EncryptedU64(fill.encrypted_fill[1]));
+
+                fill.start_settling(computation_key);
+                store_account(info, &fill)?;
+
+                callback_accounts.push(CallbackAccount {
+                    pubkey: info.key(),
+                    is_writable: true,
+                });
+                fill_mask |= 1 << slot;
+                if fill.buyer_is_maker {
+                    maker_mask |= 1 << slot;
+                }
+            }
+            None => {
+                args.push(Argument::PlaintextU128(0));
+                args.push(Argument::EncryptedU64([0u8; 32]));
+                args.push(Argument::EncryptedU64([0u8; 32]));
+            }
+        }
+    }
+    require!(
+        referenced_mask.count_ones() as usize == margin_infos.len(),
+        ErrorCode::InvalidBatchAccount
+    );
+    args.extend(buyer_idx.iter().map(|idx| Argument::PlaintextU8(*idx)));
+    args.extend(seller_idx.iter().map(|idx| Argument::PlaintextU8(*idx)));
+    args.push(Argument::PlaintextU8(fill_mask));
+    args.push(Argument::PlaintextU8(maker_mask));
+
+    // Enc<Mxe, ProtocolFees>, Enc<Mxe, OpenInterest>, then the public fee
+    // rates, mark price and maintenance margin
+    let market = &ctx.accounts.market;
+    let (taker_bps, maker_bps, maker_rebate) = market.fee_rates();
+    let mark_price = market.mark_price;
+    let maintenance_margin_bps = market.maintenance_margin_bps as u64;
+    let fees = &mut ctx.accounts.protocol_fees;
+    args.push(Argument::PlaintextU128(fees.fees_nonce));
+    args.push(Argument::EncryptedU64(fees.encrypted_fees));
+    push_open_interest_args(&mut args, fees);
+    args.push(Argument::PlaintextU64(taker_bps));
+    args.push(Argument::PlaintextU64(maker_bps));
+    args.push(Argument::PlaintextBool(maker_rebate));
+    args.push(Argument::PlaintextU64(mark_price));
+    args.push(Argument::PlaintextU64(maintenance_margin_bps));
+    args.extend(
+        state_versions
+            .iter()
+            .map(|version| Argument::PlaintextU64(*version)),
+    );
+    args.push(Argument::PlaintextU8(margin_infos.len() as u8));
+    fees.acquire(computation_key, Clock::get()?.slot)?;
+
+    let (callback_url, num_callback_txs) = ctx
+        .accounts
+        .config
+        .callback_route(SETTLE_BATCH_OUTPUT_BYTES)?;
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        callback_url,
+        vec![SettleBatchCallback::callback_ix(&callback_accounts)],
+        num_callback_txs,
+    )?;
+
+    msg!(
+        "Batch settlement queued for {} fills across {} accounts",
+        fill_infos.len(),
+        margin_infos.len()
+    );
+    Ok(())
+}
+
+/// Callback after batched settlement
+/// Remaining accounts are the margin accounts (in slot order) followed by the
+/// fills; the number of margin accounts comes back from the circuit.
+/// On success, writes every re-encrypted balance and position, the protocol
+/// fee balance and the open interest back; only the success bit is public.
+/// If any account moved past the state the batch was computed from, or a fill
+/// was reset with `reset_settling_fill`, the result is dropped and the fills
+/// still waiting on it go back to Matched; if the protocol fee lock expired
+/// and was taken over meanwhile, it is dropped and they fail.
+#[arcium_callback(encrypted_ix = "settle_batch")]
+pub fn settle_batch_callback(
+    ctx: Context<SettleBatchCallback>,
+    output: ComputationOutputs<SettleBatchOutput>,
+) -> Result<()> {
+    let (balances, positions, fees, open_interest, success, state_versions, account_count) =
+        match output {
+            ComputationOutputs::Success(SettleBatchOutput {
+                field_0:
+                    SettleBatchOutputStruct0 {
+                        field_0: balances,
+                        field_1: positions,
+                        field_2: fees,
+                        field_3: open_interest,
+                        field_4: success,
+                        field_5: state_versions,
+                        field_6: account_count,
+                    },
+            }) => (
+                balances,
+                positions,
+                fees,
+                open_interest,
+                success,
+                state_versions,
+                account_count,
+            ),
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+    let remaining = ctx.remaining_accounts;
+    let account_count = account_count as usize;
+    require!(account_count < remaining.len(), ErrorCode::InvalidBatchSize);
+    let (margin_infos, fill_infos) = remaining.split_at(account_count);
+    let computation_key = ctx.accounts.computation_account.key();
+    let holds_lock = ctx.accounts.protocol_fees.release(&computation_key);
+
+    let mut stale = false;
+    for (info, version) in margin_infos.iter().zip(state_versions.iter()) {
+        let margin: MarginAccount = load_account(info)?;
+        stale |= margin.state_version != *version;
+    }
+    let mut fills = Vec::with_capacity(fill_infos.len());
+    for info in fill_infos {
+        let fill: Fill = load_account(info)?;
+        stale |= !fill.is_settling_under(&computation_key);
+        fills.push(fill);
+    }
+    if stale || !holds_lock {
+        for (info, mut fill) in fill_infos.iter().zip(fills) {
+            if !fill.is_settling_under(&computation_key) {
+                continue;
+            }
+            fill.finish_settling(if stale {
+                FillStatus::Matched
+            } else {
+                FillStatus::Failed
+            });
+            store_account(info, &fill)?;
+        }
+        if stale {
+            msg!("Batch accounts changed while the batch was in flight, result dropped");
+        } else {
+            msg!("Fee lock expired, batch result dropped");
+        }
+        return Ok(());
+    }
+
+    // Nothing changes inside MPC on failure, so nothing is written back
+    if success {
+        for ((info, result), position) in margin_infos
+            .iter()
+            .zip(balances.iter())
+            .zip(positions.iter())
+        {
+            let mut margin: MarginAccount = load_account(info)?;
+            margin.encrypted_collateral = result.ciphertexts[0];
+            margin.encrypted_debt = result.ciphertexts[1];
+            margin.nonce = result.nonce;
+            margin.encrypted_position = position.ciphertexts;
+            margin.position_nonce = position.nonce;
+            margin.mark_state_changed();
+            store_account(info, &margin)?;
+        }
+    }
+
+    for (info, mut fill) in fill_infos.iter().zip(fills) {
+        fill.finish_settling(if success {
+            FillStatus::Settled
+        } else {
+            FillStatus::Failed
+        });
+        store_account(info, &fill)?;
+    }
+
+    if success {
+        let protocol_fees = &mut ctx.accounts.protocol_fees;
+        protocol_fees.encrypted_fees = fees.ciphertexts[0];
+        protocol_fees.fees_nonce = fees.nonce;
+        protocol_fees.encrypted_open_interest = open_interest.ciphertexts;
+        protocol_fees.open_interest_nonce = open_interest.nonce;
+    }
+
+    emit!(BatchSettled {
+        fills: fill_infos.len() as u8,
+        accounts: margin_infos.len() as u8,
+        success,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Batch settlement computation completed");
+    Ok(())
+}
+
+// ========== ACCOUNT STRUCTS ==========
+
+/// Initialize settle_batch computation definition
+#[init_computation_definition_accounts("settle_batch", payer)]
+#[derive(Accounts)]
+pub struct InitSettleBatchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Queue batched settlement
+#[queue_computation_accounts("settle_batch", matcher)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct QueueSettleBatch<'info> {
+    /// Authorized matching engine, pays for the computation
+    #[account(mut)]
+    pub matcher: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = matcher,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SETTLE_BATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Program config holding the authorized matchers and callback server
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        constraint = config.is_matcher(&matcher.key()) @ ErrorCode::UnauthorizedMatcher,
+    )]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    /// Market providing the maker and taker fee rates
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// Encrypted protocol fee balance
+    #[account(
+        mut,
+        seeds = [ProtocolFees::SEED_PREFIX],
+        bump = protocol_fees.bump,
+    )]
+    pub protocol_fees: Box<Account<'info, ProtocolFees>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Settle batch callback
+#[callback_accounts("settle_batch")]
+#[derive(Accounts)]
+pub struct SettleBatchCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SETTLE_BATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Encrypted protocol fee balance
+    #[account(mut)]
+    pub protocol_fees: Account<'info, ProtocolFees>,
+
+    /// CHECK: computation_account of this batch, matched by key only
+    pub computation_account: UncheckedAccount<'info>,
+}
+
+// ========== EVENTS ==========
+
+/// Emitted when a settlement batch completes. Amounts stay encrypted.
+#[event]
+pub struct BatchSettled {
+    pub fills: u8,
+    pub accounts: u8,
+    pub success: bool,
+    pub timestamp: i64,
+}
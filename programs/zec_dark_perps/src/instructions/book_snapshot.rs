@@ -0,0 +1,395 @@
+use anchor_lang::prelude::*;
+use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
+
+use crate::error::ErrorCode;
+use crate::instructions::matching::{push_order_args, push_padding_order_args};
+use crate::instructions::shared::{load_account, validate_callback_ixs};
+use crate::state::book_snapshot::BookSnapshot;
+use crate::state::market::Market;
+use crate::state::order::{Order, OrderStatus};
+use crate::state::program_config::ProgramConfig;
+use crate::state::SignerAccount;
+use crate::ID;
+use crate::ID as ID_CONST;
+
+// Computation definition offset for aggregate_book encrypted instruction
+const COMP_DEF_OFFSET_AGGREGATE_BOOK: u32 = comp_def_offset("aggregate_book");
+
+/// Orders aggregated per computation (must match the circuit)
+pub const BOOK_BATCH_SIZE: usize = 8;
+
+/// Circuit output size: Enc<Mxe, BookDepth> (nonce + ciphertexts), the
+/// revealed bid and ask bucket per level, plus the computation offset
+const AGGREGATE_BOOK_OUTPUT_BYTES: usize =
+    16 + BookSnapshot::DEPTH_ENCRYPTED_FIELDS * 32 + 2 * BookSnapshot::DEPTH_LEVELS + 8;
+
+/// Create the book snapshot account. Only the market authority can call this.
+pub fn init_book_snapshot(ctx: Context<InitBookSnapshot>, interval_slots: u64) -> Result<()> {
+    require!(interval_slots > 0, ErrorCode::InvalidMarketParams);
+
+    let snapshot = &mut ctx.accounts.snapshot;
+    snapshot.interval_slots = interval_slots;
+    snapshot.building = false;
+    snapshot.in_flight = false;
+    snapshot.in_flight_offset = 0;
+    snapshot.finalizing = false;
+    snapshot.encrypted_depth = [[0u8; 32]; BookSnapshot::DEPTH_ENCRYPTED_FIELDS];
+    snapshot.depth_nonce = 0;
+    snapshot.build_base_level = 0;
+    snapshot.last_order = Pubkey::default();
+    snapshot.build_order_count = 0;
+    snapshot.build_started_slot = 0;
+    snapshot.bid_buckets = [0u8; BookSnapshot::DEPTH_LEVELS];
+    snapshot.ask_buckets = [0u8; BookSnapshot::DEPTH_LEVELS];
+    snapshot.base_level = 0;
+    snapshot.order_count = 0;
+    snapshot.published_slot = 0;
+    snapshot.bump = ctx.bumps.snapshot;
+
+    msg!(
+        "Book snapshot initialized, published every {} slots",
+        interval_slots
+    );
+    Ok(())
+}
+
+/// Initialize computation definition for book aggregation
+pub fn init_aggregate_book_comp_def(ctx: Context<InitAggregateBookCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/aggregate_book.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Aggregate book computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Aggregate a batch of up to BOOK_BATCH_SIZE open orders into the snapshot
+/// Called by an authorized matching engine.
+///
+/// `reset` starts a new build (allowed once the snapshot interval has
+/// elapsed) centered on the current mark price. It supersedes any batch
+/// still in flight, whose callback is then ignored, so a lost callback
+/// cannot stall the snapshot. Open orders are passed as
+/// remaining accounts in strictly increasing address order across the whole
+/// build. `finalize` publishes the per-level size buckets when the batch
+/// completes. The output does not fit in one transaction, so it is delivered
+/// through the callback server.
+pub fn queue_aggregate_book(
+    ctx: Context<QueueAggregateBook>,
+    computation_offset: u64,
+    reset: bool,
+    finalize: bool,
+) -> Result<()> {
+    let orders = ctx.remaining_accounts;
+    require!(orders.len() <= BOOK_BATCH_SIZE, ErrorCode::InvalidBatchSize);
+
+    let current_slot = Clock::get()?.slot;
+    let mark_price = ctx.accounts.market.mark_price;
+    let snapshot = &mut ctx.accounts.snapshot;
+
+    if reset {
+        require!(snapshot.is_due(current_slot), ErrorCode::SnapshotNotDue);
+        require!(
+            ctx.accounts.market.is_price_fresh(current_slot),
+            ErrorCode::StaleMarkPrice
+        );
+        snapshot.building = true;
+        snapshot.build_base_level = BookSnapshot::base_level_for(mark_price);
+        snapshot.last_order = Pubkey::default();
+        snapshot.build_order_count = 0;
+        snapshot.build_started_slot = current_slot;
+    } else {
+        require!(
+            snapshot.building && !snapshot.in_flight,
+            ErrorCode::InvalidSnapshotState
+        );
+    }
+
+    // Enc<Mxe, BookDepth>: nonce, then bid and ask totals
+    // (discarded inside MPC on reset)
+    let mut args =
+        Vec::with_capacity(1 + BookSnapshot::DEPTH_ENCRYPTED_FIELDS + BOOK_BATCH_SIZE * 8 + 4);
+    args.push(Argument::PlaintextU128(snapshot.depth_nonce));
+    args.extend(
+        snapshot
+            .encrypted_depth
+            .iter()
+            .map(|ct| Argument::EncryptedU64(*ct)),
+    );
+
+    // [Enc<Mxe, OrderData>; BOOK_BATCH_SIZE], unused slots padded and masked out
+    let mut active_mask: u8 = 0;
+    for slot in 0..BOOK_BATCH_SIZE {
+        match orders.get(slot) {
+            Some(info) => {
+                require!(
+                    *info.key > snapshot.last_order,
+                    ErrorCode::UnsortedSnapshotOrders
+                );
+                let order: Order = load_account(info)?;
+                require!(
+                    order.status == OrderStatus::Open,
+                    ErrorCode::InvalidOrderStatus
+                );
+                push_order_args(&mut args, &order);
+
+                snapshot.last_order = info.key();
+                active_mask |= 1 << slot;
+            }
+            None => push_padding_order_args(&mut args),
+        }
+    }
+    args.push(Argument::PlaintextU8(active_mask));
+    args.push(Argument::PlaintextU64(snapshot.build_base_level));
+    args.push(Argument::PlaintextBool(reset));
+    args.push(Argument::PlaintextBool(finalize));
+    args.push(Argument::PlaintextU64(computation_offset));
+
+    snapshot.build_order_count = snapshot
+        .build_order_count
+        .checked_add(orders.len() as u32)
+        .ok_or(ErrorCode::MathOverflow)?;
+    snapshot.in_flight = true;
+    snapshot.in_flight_offset = computation_offset;
+    snapshot.finalizing = finalize;
+
+    let (callback_url, num_callback_txs) = ctx
+        .accounts
+        .config
+        .callback_route(AGGREGATE_BOOK_OUTPUT_BYTES)?;
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        callback_url,
+        vec![AggregateBookCallback::callback_ix(&[CallbackAccount {
+            pubkey: ctx.accounts.snapshot.key(),
+            is_writable: true,
+        }])],
+        num_callback_txs,
+    )?;
+
+    msg!("Book aggregation queued for {} orders", orders.len());
+    Ok(())
+}
+
+/// Callback after book aggregation
+/// Stores the encrypted running totals; on the final batch, publishes the
+/// revealed size buckets. Individual orders are never revealed. Results of
+/// any computation other than the batch in flight are ignored.
+#[arcium_callback(encrypted_ix = "aggregate_book")]
+pub fn aggregate_book_callback(
+    ctx: Context<AggregateBookCallback>,
+    output: ComputationOutputs<AggregateBookOutput>,
+) -> Result<()> {
+    let (encrypted_depth, bid_buckets, ask_buckets, computation_offset) = match output {
+        ComputationOutputs::Success(AggregateBookOutput {
+            field_0:
+                AggregateBookOutputStruct0 {
+                    field_0: encrypted_depth,
+                    field_1: bid_buckets,
+                    field_2: ask_buckets,
+                    field_3: computation_offset,
+                },
+        }) => (
+            encrypted_depth,
+            bid_buckets,
+            ask_buckets,
+            computation_offset,
+        ),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let snapshot = &mut ctx.accounts.snapshot;
+    if !(snapshot.building && snapshot.in_flight && snapshot.in_flight_offset == computation_offset)
+    {
+        msg!("Superseded book aggregation result ignored");
+        return Ok(());
+    }
+
+    snapshot.encrypted_depth = encrypted_depth.ciphertexts;
+    snapshot.depth_nonce = encrypted_depth.nonce;
+    snapshot.in_flight = false;
+
+    if !snapshot.finalizing {
+        msg!("Book aggregation batch completed");
+        return Ok(());
+    }
+
+    snapshot.building = false;
+    snapshot.finalizing = false;
+    snapshot.bid_buckets = bid_buckets;
+    snapshot.ask_buckets = ask_buckets;
+    snapshot.base_level = snapshot.build_base_level;
+    snapshot.order_count = snapshot.build_order_count;
+    snapshot.published_slot = snapshot.build_started_slot;
+
+    emit!(BookSnapshotPublished {
+        base_level: snapshot.base_level,
+        bid_buckets,
+        ask_buckets,
+        order_count: snapshot.order_count,
+        slot: snapshot.published_slot,
+    });
+
+    msg!("Book snapshot published");
+    Ok(())
+}
+
+// ========== ACCOUNT STRUCTS ==========
+
+/// Create the book snapshot
+#[derive(Accounts)]
+pub struct InitBookSnapshot<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+        has_one = authority @ ErrorCode::UnauthorizedMarketAuthority,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [BookSnapshot::SEED_PREFIX],
+        bump,
+        space = BookSnapshot::SPACE,
+    )]
+    pub snapshot: Box<Account<'info, BookSnapshot>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Initialize aggregate_book computation definition
+#[init_computation_definition_accounts("aggregate_book", payer)]
+#[derive(Accounts)]
+pub struct InitAggregateBookCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Queue a book aggregation batch
+#[queue_computation_accounts("aggregate_book", matcher)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct QueueAggregateBook<'info> {
+    /// Authorized matching engine, pays for the computation
+    #[account(mut)]
+    pub matcher: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = matcher,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_AGGREGATE_BOOK))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Program config holding the authorized matchers and callback server
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        constraint = config.is_matcher(&matcher.key()) @ ErrorCode::UnauthorizedMatcher,
+    )]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    /// Market providing the mark price the snapshot is centered on
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [BookSnapshot::SEED_PREFIX],
+        bump = snapshot.bump,
+    )]
+    pub snapshot: Box<Account<'info, BookSnapshot>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Aggregate book callback
+#[callback_accounts("aggregate_book")]
+#[derive(Accounts)]
+pub struct AggregateBookCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_AGGREGATE_BOOK))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub snapshot: Box<Account<'info, BookSnapshot>>,
+}
+
+// ========== EVENTS ==========
+
+/// Emitted when a new snapshot is published. Level `i` is `base_level + i` dollars.
+#[event]
+pub struct BookSnapshotPublished {
+    pub base_level: u64,
+    pub bid_buckets: [u8; 16],
+    pub ask_buckets: [u8; 16],
+    pub order_count: u32,
+    pub slot: u64,
+}
@@ -0,0 +1,148 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::state::program_config::ProgramConfig;
+
+// ========== INITIALIZE CONFIG ==========
+
+/// Initialize the program configuration with an empty matcher set
+/// Only the program upgrade authority can call this, so the first caller
+/// after deployment cannot take over the matcher registry.
+pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.authority = ctx.accounts.authority.key();
+    config.matchers = [Pubkey::default(); ProgramConfig::MAX_MATCHERS];
+    config.matcher_count = 0;
+    config.callback_server_url = String::new();
+    config.bump = ctx.bumps.config;
+
+    msg!("Program config initialized");
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump,
+        space = ProgramConfig::SPACE,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// This program, to locate its program data account
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::ZecDarkPerps>,
+
+    /// Program data account holding the upgrade authority
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::UnauthorizedUpgradeAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ========== MATCHER REGISTRY ==========
+
+/// Authorize a matching engine key to settle trades
+pub fn add_matcher(ctx: Context<UpdateMatchers>, matcher: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    require!(
+        !config.is_matcher(&matcher),
+        ErrorCode::MatcherAlreadyRegistered
+    );
+
+    let count = config.matcher_count as usize;
+    require!(
+        count < ProgramConfig::MAX_MATCHERS,
+        ErrorCode::MatcherRegistryFull
+    );
+
+    config.matchers[count] = matcher;
+    config.matcher_count += 1;
+
+    emit!(MatcherUpdated {
+        matcher,
+        authorized: true,
+    });
+
+    msg!("Matcher authorized: {}", matcher);
+    Ok(())
+}
+
+/// Revoke a matching engine key
+pub fn remove_matcher(ctx: Context<UpdateMatchers>, matcher: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let index = config
+        .active_matchers()
+        .iter()
+        .position(|key| key == &matcher)
+        .ok_or(ErrorCode::UnauthorizedMatcher)?;
+
+    // Swap-remove to keep the active entries contiguous
+    let last = config.matcher_count as usize - 1;
+    config.matchers[index] = config.matchers[last];
+    config.matchers[last] = Pubkey::default();
+    config.matcher_count -= 1;
+
+    emit!(MatcherUpdated {
+        matcher,
+        authorized: false,
+    });
+
+    msg!("Matcher revoked: {}", matcher);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateMatchers<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::UnauthorizedConfigAuthority,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+// ========== CALLBACK SERVER ==========
+
+/// Set the callback server used for computation outputs that do not fit
+/// in a single callback transaction (empty string disables it)
+pub fn set_callback_server(ctx: Context<UpdateConfig>, url: String) -> Result<()> {
+    require!(
+        url.len() <= ProgramConfig::MAX_CALLBACK_URL_LEN,
+        ErrorCode::CallbackUrlTooLong
+    );
+    ctx.accounts.config.callback_server_url = url;
+
+    msg!("Callback server updated");
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::UnauthorizedConfigAuthority,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[event]
+pub struct MatcherUpdated {
+    pub matcher: Pubkey,
+    pub authorized: bool,
+}
@@ -0,0 +1,248 @@
+use anchor_lang::prelude::*;
+use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
+
+use crate::error::ErrorCode;
+use crate::instructions::shared::validate_callback_ixs;
+use crate::state::program_config::ProgramConfig;
+use crate::state::protocol_fees::ProtocolFees;
+use crate::state::SignerAccount;
+use crate::ID;
+use crate::ID as ID_CONST;
+
+// Computation definition offset for reveal_protocol_fees encrypted instruction
+const COMP_DEF_OFFSET_REVEAL_PROTOCOL_FEES: u32 = comp_def_offset("reveal_protocol_fees");
+
+/// Create the encrypted protocol fee balance. Only the config authority can call this.
+pub fn init_protocol_fees(ctx: Context<InitProtocolFees>) -> Result<()> {
+    let fees = &mut ctx.accounts.protocol_fees;
+    fees.encrypted_fees = [0u8; 32];
+    fees.fees_nonce = 0;
+    fees.encrypted_open_interest = [[0u8; 32]; 2];
+    fees.open_interest_nonce = 0;
+    fees.total_swept = 0;
+    fees.last_sweep_slot = 0;
+    fees.lock_holder = Pubkey::default();
+    fees.lock_slot = 0;
+    fees.bump = ctx.bumps.protocol_fees;
+
+    msg!("Protocol fee balance initialized");
+    Ok(())
+}
+
+/// Initialize computation definition for revealing the protocol fee balance
+pub fn init_reveal_protocol_fees_comp_def(
+    ctx: Context<InitRevealProtocolFeesCompDef>,
+) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/reveal_protocol_fees.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Reveal protocol fees computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Sweep collected protocol fees
+/// Reveals the cumulative fee total; the amount swept is the difference from
+/// the total revealed by the previous sweep. Only the config authority can call this.
+pub fn sweep_protocol_fees(ctx: Context<SweepProtocolFees>, computation_offset: u64) -> Result<()> {
+    let fees = &ctx.accounts.protocol_fees;
+
+    // Enc<Mxe, ProtocolFees>: nonce, then the collected total
+    let args = vec![
+        Argument::PlaintextU128(fees.fees_nonce),
+        Argument::EncryptedU64(fees.encrypted_fees),
+    ];
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![RevealProtocolFeesCallback::callback_ix(&[
+            CallbackAccount {
+                pubkey: ctx.accounts.protocol_fees.key(),
+                is_writable: true,
+            },
+        ])],
+        1,
+    )?;
+
+    msg!("Protocol fee sweep queued to MPC cluster");
+    Ok(())
+}
+
+/// Callback after revealing the protocol fee balance
+#[arcium_callback(encrypted_ix = "reveal_protocol_fees")]
+pub fn reveal_protocol_fees_callback(
+    ctx: Context<RevealProtocolFeesCallback>,
+    output: ComputationOutputs<RevealProtocolFeesOutput>,
+) -> Result<()> {
+    let total = match output {
+        ComputationOutputs::Success(RevealProtocolFeesOutput { field_0 }) => field_0,
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let fees = &mut ctx.accounts.protocol_fees;
+    // A sweep of an older ciphertext may land after a newer one
+    let swept = total.saturating_sub(fees.total_swept);
+    fees.total_swept = fees.total_swept.max(total);
+
+    let slot = Clock::get()?.slot;
+    fees.last_sweep_slot = slot;
+
+    emit!(ProtocolFeesSwept {
+        total: fees.total_swept,
+        swept,
+        slot,
+    });
+
+    msg!("Protocol fees swept: {}", swept);
+    Ok(())
+}
+
+// ========== ACCOUNT STRUCTS ==========
+
+/// Create the protocol fee balance
+#[derive(Accounts)]
+pub struct InitProtocolFees<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::UnauthorizedConfigAuthority,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [ProtocolFees::SEED_PREFIX],
+        bump,
+        space = ProtocolFees::SPACE,
+    )]
+    pub protocol_fees: Account<'info, ProtocolFees>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Initialize reveal_protocol_fees computation definition
+#[init_computation_definition_accounts("reveal_protocol_fees", payer)]
+#[derive(Accounts)]
+pub struct InitRevealProtocolFeesCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Queue a protocol fee sweep
+#[queue_computation_accounts("reveal_protocol_fees", authority)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SweepProtocolFees<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = authority,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_PROTOCOL_FEES))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::UnauthorizedConfigAuthority,
+    )]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        seeds = [ProtocolFees::SEED_PREFIX],
+        bump = protocol_fees.bump,
+    )]
+    pub protocol_fees: Box<Account<'info, ProtocolFees>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Reveal protocol fees callback
+#[callback_accounts("reveal_protocol_fees")]
+#[derive(Accounts)]
+pub struct RevealProtocolFeesCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_PROTOCOL_FEES))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub protocol_fees: Account<'info, ProtocolFees>,
+}
+
+// ========== EVENTS ==========
+
+/// Emitted when collected protocol fees are swept
+#[event]
+pub struct ProtocolFeesSwept {
+    /// Cumulative fees revealed so far
+    pub total: u64,
+    /// Fees collected since the previous sweep
+    pub swept: u64,
+    pub slot: u64,
+}
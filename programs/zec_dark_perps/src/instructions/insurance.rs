@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::error::ErrorCode;
+use crate::state::insurance_fund::InsuranceFund;
+use crate::state::program_config::ProgramConfig;
+
+/// Create the insurance fund and its wZEC vault. Only the config authority can call this.
+pub fn init_insurance_fund(ctx: Context<InitInsuranceFund>) -> Result<()> {
+    let fund = &mut ctx.accounts.insurance_fund;
+    fund.vault = ctx.accounts.insurance_vault.key();
+    fund.total_received = 0;
+    fund.total_paid_out = 0;
+    fund.bump = ctx.bumps.insurance_fund;
+
+    msg!("Insurance fund initialized");
+    Ok(())
+}
+
+// ========== ACCOUNT STRUCTS ==========
+
+/// Create the insurance fund
+#[derive(Accounts)]
+pub struct InitInsuranceFund<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::UnauthorizedConfigAuthority,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [InsuranceFund::SEED_PREFIX],
+        bump,
+        space = InsuranceFund::SPACE,
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// ATA holding the fund, owned by the insurance fund PDA.
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = insurance_fund,
+    )]
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    /// wZEC mint.
+    pub mint: Account<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
@@ -1,33 +1,267 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
 
 use crate::error::ErrorCode;
+use crate::instructions::margin_arcium::{push_open_interest_args, push_position_args};
+use crate::instructions::shared::validate_callback_ixs;
+use crate::state::insurance_fund::InsuranceFund;
 use crate::state::margin_account::MarginAccount;
+use crate::state::market::Market;
+use crate::state::protocol_fees::ProtocolFees;
+use crate::state::SignerAccount;
+use crate::ID;
+use crate::ID as ID_CONST;
+
+// Computation definition offsets for liquidation encrypted instructions
+const COMP_DEF_OFFSET_LIQUIDATE_POSITION: u32 = comp_def_offset("liquidate_position");
+const COMP_DEF_OFFSET_APPLY_LOSS_INDEX: u32 = comp_def_offset("apply_loss_index");
+
+/// Initialize computation definition for partial liquidation
+pub fn init_liquidate_position_comp_def(ctx: Context<InitLiquidatePositionCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/liquidate_position.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Liquidate position computation definition initialized with circuit");
+    Ok(())
+}
 
 /// Liquidate an unhealthy margin account.
 ///
 /// **PRIVACY:** Balances are encrypted, so we can't read the exact collateral amount.
-/// Instead, we transfer ALL tokens from the vault and reset encrypted balances to zero.
+/// Instead, the MPC cluster closes the smallest part of the position (at the
+/// mark price) that restores the maintenance margin after the liquidation
+/// penalty, and reveals only the penalty seized. Only that amount leaves the
+/// vault, in the callback, split between the liquidator's reward and the
+/// insurance fund according to `Market::insurance_fund_share_bps`.
+///
+/// If the account is bankrupt (debt left after closing the whole position),
+/// its bad debt is covered from the insurance fund's uncommitted balance
+/// first (see `InsuranceFund`) and the remainder
+/// is socialized onto the opposite side's loss index. If the fund cannot
+/// cover it, nothing is closed until an auto-deleveraging round has run
+/// against the account (see `auto_deleverage`). The closed size leaves the
+/// open interest, so the liquidation takes the protocol fee lock.
 ///
 /// Requirements:
 /// - The margin account must have is_liquidatable = true (set by Arcium health check)
-/// - Liquidator receives all collateral from the vault
-/// - Encrypted balances are reset to zero
-///
-/// This is a simplified liquidation for MVP:
-/// - Full collateral goes to liquidator
-/// - No partial liquidations
-/// - No liquidation penalty/bonus (can add later)
-pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
+/// - That health check must have been computed from the account's current
+///   state version and queued within the market's `health_check_max_age_slots`
+/// - The mark price must be fresh
+/// - Socialized losses must already be applied to the account
+/// - No auto-deleveraging round may be pending against the account
+pub fn liquidate(ctx: Context<Liquidate>, computation_offset: u64) -> Result<()> {
     // Check if account is liquidatable (set by Arcium health check callback)
+    let margin_account = &ctx.accounts.margin_account;
+    require!(margin_account.is_liquidatable, ErrorCode::HealthyPosition);
+    require!(!margin_account.adl_pending, ErrorCode::AdlPending);
+
+    // The flag must describe the account as it is now, and be recent
+    let market = &ctx.accounts.market;
+    let current_slot = Clock::get()?.slot;
+    require!(
+        margin_account.health_check_version == margin_account.state_version
+            && current_slot.saturating_sub(margin_account.health_check_slot)
+                <= market.health_check_max_age_slots,
+        ErrorCode::StaleHealthCheck
+    );
+    require!(
+        market.is_price_fresh(current_slot),
+        ErrorCode::StaleMarkPrice
+    );
     require!(
-        ctx.accounts.margin_account.is_liquidatable,
-        ErrorCode::HealthyPosition
+        margin_account.has_current_loss_index(market),
+        ErrorCode::PendingSocializedLoss
     );
 
-    // Get the actual token balance from the vault (this is public on-chain)
-    let vault_balance = ctx.accounts.margin_vault.amount;
-    require!(vault_balance > 0, ErrorCode::InvalidAmount);
+    // Enc<Mxe, MarginBalances>, Enc<Mxe, Position>, Enc<Mxe, OpenInterest>,
+    // then the public inputs
+    let mut args = vec![
+        Argument::PlaintextU128(margin_account.nonce),
+        Argument::EncryptedU64(margin_account.encrypted_collateral),
+        Argument::EncryptedU64(margin_account.encrypted_debt),
+    ];
+    push_position_args(&mut args, margin_account);
+    push_open_interest_args(&mut args, &ctx.accounts.protocol_fees);
+    args.push(Argument::PlaintextU64(market.mark_price));
+    args.push(Argument::PlaintextU64(market.maintenance_margin_bps as u64));
+    args.push(Argument::PlaintextU64(
+        market.liquidation_penalty_bps as u64,
+    ));
+    args.push(Argument::PlaintextU64(
+        ctx.accounts
+            .insurance_fund
+            .available(ctx.accounts.insurance_vault.amount),
+    ));
+    args.push(Argument::PlaintextBool(margin_account.adl_completed));
+    args.push(Argument::PlaintextU64(margin_account.state_version));
+
+    // Keyed by the computation, so a timed-out liquidation of the same
+    // account cannot release a lock a later one took over
+    let computation_key = ctx.accounts.computation_account.key();
+    ctx.accounts
+        .protocol_fees
+        .acquire(computation_key, current_slot)?;
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![LiquidatePositionCallback::callback_ix(&[
+            CallbackAccount {
+                pubkey: ctx.accounts.margin_account.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.margin_vault.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.liquidator_token_account.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.insurance_fund.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.insurance_vault.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.market.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.protocol_fees.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: computation_key,
+                is_writable: false,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.token_program.key(),
+                is_writable: false,
+            },
+        ])],
+        1,
+    )?;
+
+    msg!("Liquidation queued to MPC cluster");
+    Ok(())
+}
+
+/// Callback after the partial liquidation computation
+/// Stores the reduced position and balances and transfers the seized
+/// collateral from the vault, split between the liquidator and the
+/// insurance fund. For a bankrupt account, books the insurance draw against
+/// the fund (the tokens stay in the insurance vault) and adds the socialized
+/// loss to the opposite side's loss index, or, if the account must be deleveraged first, only flags it for
+/// auto-deleveraging. A result computed from a state the account has since moved past is
+/// dropped, so two liquidations queued against the same state only apply once.
+/// The result is also dropped if the protocol fee lock expired and was taken
+/// over meanwhile, since the open interest has moved on without it.
+#[arcium_callback(encrypted_ix = "liquidate_position")]
+pub fn liquidate_position_callback(
+    ctx: Context<LiquidatePositionCallback>,
+    output: ComputationOutputs<LiquidatePositionOutput>,
+) -> Result<()> {
+    let (
+        balances,
+        position,
+        open_interest,
+        seized,
+        insurance_draw,
+        loss_per_unit,
+        loss_on_longs,
+        needs_adl,
+        state_version,
+    ) = match output {
+        ComputationOutputs::Success(LiquidatePositionOutput {
+            field_0:
+                LiquidatePositionOutputStruct0 {
+                    field_0: balances,
+                    field_1: position,
+                    field_2: open_interest,
+                    field_3: seized,
+                    field_4: insurance_draw,
+                    field_5: loss_per_unit,
+                    field_6: loss_on_longs,
+                    field_7: needs_adl,
+                    field_8: state_version,
+                },
+        }) => (
+            balances,
+            position,
+            open_interest,
+            seized,
+            insurance_draw,
+            loss_per_unit,
+            loss_on_longs,
+            needs_adl,
+            state_version,
+        ),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let holds_lock = ctx
+        .accounts
+        .protocol_fees
+        .release(&ctx.accounts.computation_account.key());
+
+    if state_version != ctx.accounts.margin_account.state_version {
+        msg!("Outdated liquidation result ignored");
+        return Ok(());
+    }
+
+    if !holds_lock {
+        msg!("Fee lock expired, liquidation result dropped");
+        return Ok(());
+    }
+
+    let protocol_fees = &mut ctx.accounts.protocol_fees;
+    protocol_fees.encrypted_open_interest = open_interest.ciphertexts;
+    protocol_fees.open_interest_nonce = open_interest.nonce;
+
+    // Nothing was closed; the opposite side is deleveraged first
+    if needs_adl {
+        let margin_account = &mut ctx.accounts.margin_account;
+        margin_account.adl_pending = true;
+
+        emit!(AdlRequired {
+            margin_account: margin_account.key(),
+            state_version,
+        });
+
+        msg!("Insurance fund cannot cover bad debt, auto-deleveraging required");
+        return Ok(());
+    }
+
+    let margin_account = &mut ctx.accounts.margin_account;
+    margin_account.encrypted_collateral = balances.ciphertexts[0];
+    margin_account.encrypted_debt = balances.ciphertexts[1];
+    margin_account.nonce = balances.nonce;
+    margin_account.encrypted_position = position.ciphertexts;
+    margin_account.position_nonce = position.nonce;
+    margin_account.mark_state_changed();
+
+    // The vault can hold less than the encrypted collateral accounts for
+    let collateral_seized = seized.min(ctx.accounts.margin_vault.amount);
+    let (liquidator_reward, insurance_fund_share) = ctx
+        .accounts
+        .market
+        .split_liquidation_penalty(collateral_seized);
 
     // Prepare seeds for PDA signing
     let owner_key = ctx.accounts.margin_account.owner;
@@ -35,73 +269,501 @@ pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
     let seeds = &[MarginAccount::SEED_PREFIX, owner_key.as_ref(), &[bump]];
     let signer_seeds = &[&seeds[..]];
 
-    // Transfer ALL collateral from margin vault to liquidator
-    let cpi_accounts = Transfer {
-        from: ctx.accounts.margin_vault.to_account_info(),
-        to: ctx.accounts.liquidator_token_account.to_account_info(),
-        authority: ctx.accounts.margin_account.to_account_info(),
-    };
-    let cpi_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
-        cpi_accounts,
-        signer_seeds,
-    );
-    token::transfer(cpi_ctx, vault_balance)?;
+    if liquidator_reward > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.margin_vault.to_account_info(),
+            to: ctx.accounts.liquidator_token_account.to_account_info(),
+            authority: ctx.accounts.margin_account.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, liquidator_reward)?;
+    }
 
-    // Reset encrypted balances to zero
-    let margin_account = &mut ctx.accounts.margin_account;
-    margin_account.encrypted_collateral = [0u8; 32];
-    margin_account.encrypted_debt = [0u8; 32];
-    margin_account.nonce = 0;
-    margin_account.is_liquidatable = false;
+    if insurance_fund_share > 0 {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.margin_vault.to_account_info(),
+            to: ctx.accounts.insurance_vault.to_account_info(),
+            authority: ctx.accounts.margin_account.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, insurance_fund_share)?;
+
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.total_received = fund.total_received.saturating_add(insurance_fund_share);
+    }
+
+    if insurance_draw > 0 || loss_per_unit > 0 {
+        // Only the fund's uncommitted balance can be drawn. No tokens move:
+        // the bankrupt account's debt is written off inside MPC, and the
+        // draw stays in the insurance vault backing the winning side.
+        let fund = &mut ctx.accounts.insurance_fund;
+        let insurance_draw =
+            insurance_draw.min(fund.available(ctx.accounts.insurance_vault.amount));
+        fund.total_paid_out = fund.total_paid_out.saturating_add(insurance_draw);
+
+        let market = &mut ctx.accounts.market;
+        if loss_on_longs {
+            market.long_loss_index = market.long_loss_index.saturating_add(loss_per_unit);
+        } else {
+            market.short_loss_index = market.short_loss_index.saturating_add(loss_per_unit);
+        }
+
+        emit!(BadDebtResolved {
+            margin_account_owner: owner_key,
+            insurance_draw,
+            loss_per_unit,
+            loss_on_longs,
+            long_loss_index: market.long_loss_index,
+            short_loss_index: market.short_loss_index,
+        });
+    }
 
-    // Emit event
     emit!(Liquidated {
-        liquidator: ctx.accounts.liquidator.key(),
+        liquidator: ctx.accounts.liquidator_token_account.owner,
         margin_account_owner: owner_key,
-        collateral_seized: vault_balance,
+        collateral_seized,
+        liquidator_reward,
+        insurance_fund_share,
         timestamp: Clock::get()?.unix_timestamp,
     });
 
-    msg!("Account liquidated. Encrypted balances reset to zero.");
+    msg!(
+        "Account liquidated. Seized {} collateral.",
+        collateral_seized
+    );
+    Ok(())
+}
+
+/// Initialize computation definition for charging socialized losses
+pub fn init_apply_loss_index_comp_def(ctx: Context<InitApplyLossIndexCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/apply_loss_index.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Apply loss index computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Charge a margin account the socialized losses added since it was last charged
+/// Both loss index deltas are passed; MPC charges the one of the account's
+/// own side, so the side stays private. Anyone can queue it, and an account
+/// must be charged before it can settle, be health checked or be liquidated.
+pub fn apply_socialized_loss(
+    ctx: Context<ApplySocializedLoss>,
+    computation_offset: u64,
+) -> Result<()> {
+    let margin = &ctx.accounts.margin_account;
+    let market = &ctx.accounts.market;
+
+    // Enc<Mxe, MarginBalances>, Enc<Mxe, Position>, then the public inputs
+    let mut args = vec![
+        Argument::PlaintextU128(margin.nonce),
+        Argument::EncryptedU64(margin.encrypted_collateral),
+        Argument::EncryptedU64(margin.encrypted_debt),
+    ];
+    push_position_args(&mut args, margin);
+    args.push(Argument::PlaintextU64(
+        market
+            .long_loss_index
+            .saturating_sub(margin.long_loss_snapshot),
+    ));
+    args.push(Argument::PlaintextU64(
+        market
+            .short_loss_index
+            .saturating_sub(margin.short_loss_snapshot),
+    ));
+    args.push(Argument::PlaintextU64(market.long_loss_index));
+    args.push(Argument::PlaintextU64(market.short_loss_index));
+    args.push(Argument::PlaintextU64(margin.state_version));
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![ApplyLossIndexCallback::callback_ix(&[CallbackAccount {
+            pubkey: ctx.accounts.margin_account.key(),
+            is_writable: true,
+        }])],
+        1,
+    )?;
+
+    msg!("Socialized loss charge queued to MPC cluster");
+    Ok(())
+}
+
+/// Callback after charging socialized losses
+/// Stores the charged balances and records the loss indexes they include.
+/// A result computed from a state the account has since moved past is
+/// dropped; the charge is then queued again.
+#[arcium_callback(encrypted_ix = "apply_loss_index")]
+pub fn apply_loss_index_callback(
+    ctx: Context<ApplyLossIndexCallback>,
+    output: ComputationOutputs<ApplyLossIndexOutput>,
+) -> Result<()> {
+    let (balances, long_loss_index, short_loss_index, state_version) = match output {
+        ComputationOutputs::Success(ApplyLossIndexOutput {
+            field_0:
+                ApplyLossIndexOutputStruct0 {
+                    field_0: balances,
+                    field_1: long_loss_index,
+                    field_2: short_loss_index,
+                    field_3: state_version,
+                },
+        }) => (balances, long_loss_index, short_loss_index, state_version),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let margin_account = &mut ctx.accounts.margin_account;
+    if state_version != margin_account.state_version {
+        msg!("Outdated socialized loss charge ignored");
+        return Ok(());
+    }
+
+    margin_account.encrypted_collateral = balances.ciphertexts[0];
+    margin_account.encrypted_debt = balances.ciphertexts[1];
+    margin_account.nonce = balances.nonce;
+    margin_account.long_loss_snapshot = long_loss_index;
+    margin_account.short_loss_snapshot = short_loss_index;
+    margin_account.mark_state_changed();
+
+    emit!(SocializedLossApplied {
+        margin_account: margin_account.key(),
+        long_loss_index,
+        short_loss_index,
+    });
+
+    msg!("Socialized loss applied");
     Ok(())
 }
 
+// ========== ACCOUNT STRUCTS ==========
+
+/// Initialize liquidate_position computation definition
+#[init_computation_definition_accounts("liquidate_position", payer)]
+#[derive(Accounts)]
+pub struct InitLiquidatePositionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
 /// Accounts for liquidation
+#[queue_computation_accounts("liquidate_position", liquidator)]
 #[derive(Accounts)]
+#[instruction(computation_offset: u64)]
 pub struct Liquidate<'info> {
     /// The liquidator (anyone can liquidate an unhealthy account)
     #[account(mut)]
     pub liquidator: Signer<'info>,
 
-    /// The margin account being liquidated
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = liquidator,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_LIQUIDATE_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
     #[account(
         mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// The margin account being liquidated
+    #[account(
         seeds = [MarginAccount::SEED_PREFIX, margin_account.owner.as_ref()],
         bump = margin_account.bump
     )]
-    pub margin_account: Account<'info, MarginAccount>,
+    pub margin_account: Box<Account<'info, MarginAccount>>,
+
+    /// Market providing the mark price and risk parameters
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
 
     /// The margin vault (ATA owned by margin_account PDA)
     #[account(
-        mut,
         associated_token::mint = liquidator_token_account.mint,
         associated_token::authority = margin_account,
     )]
-    pub margin_vault: Account<'info, TokenAccount>,
+    pub margin_vault: Box<Account<'info, TokenAccount>>,
 
     /// Liquidator's token account to receive collateral
+    #[account(token::authority = liquidator)]
+    pub liquidator_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Insurance fund receiving its share of the penalty
+    #[account(
+        seeds = [InsuranceFund::SEED_PREFIX],
+        bump = insurance_fund.bump,
+    )]
+    pub insurance_fund: Box<Account<'info, InsuranceFund>>,
+
+    /// Insurance fund vault
+    #[account(
+        address = insurance_fund.vault,
+        token::mint = margin_vault.mint,
+    )]
+    pub insurance_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Protocol fee account holding the encrypted open interest
+    #[account(
+        mut,
+        seeds = [ProtocolFees::SEED_PREFIX],
+        bump = protocol_fees.bump,
+    )]
+    pub protocol_fees: Box<Account<'info, ProtocolFees>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Liquidate position callback
+#[callback_accounts("liquidate_position")]
+#[derive(Accounts)]
+pub struct LiquidatePositionCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_LIQUIDATE_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// The margin account being liquidated
     #[account(mut)]
+    pub margin_account: Account<'info, MarginAccount>,
+
+    /// The margin vault (ATA owned by margin_account PDA)
+    #[account(mut, token::authority = margin_account)]
+    pub margin_vault: Account<'info, TokenAccount>,
+
+    /// Liquidator's token account to receive collateral
+    #[account(mut, token::mint = margin_vault.mint)]
     pub liquidator_token_account: Account<'info, TokenAccount>,
 
+    /// Insurance fund receiving its share of the penalty
+    #[account(mut)]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// Insurance fund vault
+    #[account(mut, address = insurance_fund.vault)]
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    /// Market defining the penalty split and holding the loss indexes
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+
+    /// Protocol fee account holding the encrypted open interest
+    #[account(mut)]
+    pub protocol_fees: Account<'info, ProtocolFees>,
+
+    /// CHECK: computation_account that took the fee lock, matched by key only
+    pub computation_account: UncheckedAccount<'info>,
+
     pub token_program: Program<'info, Token>,
 }
 
+/// Initialize apply_loss_index computation definition
+#[init_computation_definition_accounts("apply_loss_index", payer)]
+#[derive(Accounts)]
+pub struct InitApplyLossIndexCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for charging socialized losses to a margin account
+#[queue_computation_accounts("apply_loss_index", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ApplySocializedLoss<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_APPLY_LOSS_INDEX))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// The margin account being charged
+    #[account(
+        seeds = [MarginAccount::SEED_PREFIX, margin_account.owner.as_ref()],
+        bump = margin_account.bump
+    )]
+    pub margin_account: Account<'info, MarginAccount>,
+
+    /// Market holding the loss indexes
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Apply loss index callback
+#[callback_accounts("apply_loss_index")]
+#[derive(Accounts)]
+pub struct ApplyLossIndexCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_APPLY_LOSS_INDEX))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// The margin account being charged
+    #[account(mut)]
+    pub margin_account: Account<'info, MarginAccount>,
+}
+
+// ========== EVENTS ==========
+
 /// Event emitted when liquidation occurs
 #[event]
 pub struct Liquidated {
     pub liquidator: Pubkey,
     pub margin_account_owner: Pubkey,
+    /// Penalty seized from the margin vault
     pub collateral_seized: u64,
+    /// Part of the penalty paid to the liquidator
+    pub liquidator_reward: u64,
+    /// Part of the penalty paid into the insurance fund
+    pub insurance_fund_share: u64,
     pub timestamp: i64,
 }
+
+/// Emitted when a bankrupt account's bad debt is covered
+#[event]
+pub struct BadDebtResolved {
+    pub margin_account_owner: Pubkey,
+    /// Bad debt covered by the insurance fund
+    pub insurance_draw: u64,
+    /// Loss per unit of size socialized onto the opposite side (1e6 fixed-point)
+    pub loss_per_unit: u64,
+    /// Whether the loss was socialized onto longs (otherwise shorts)
+    pub loss_on_longs: bool,
+    pub long_loss_index: u64,
+    pub short_loss_index: u64,
+}
+
+/// Emitted when a bankrupt account must be auto-deleveraged before its
+/// liquidation can proceed
+#[event]
+pub struct AdlRequired {
+    pub margin_account: Pubkey,
+    pub state_version: u64,
+}
+
+/// Emitted when socialized losses are charged to a margin account
+#[event]
+pub struct SocializedLossApplied {
+    pub margin_account: Pubkey,
+    pub long_loss_index: u64,
+    pub short_loss_index: u64,
+}
@@ -10,13 +10,28 @@ use crate::state::margin_account::MarginAccount;
 pub fn initialize_margin_account(ctx: Context<InitializeMarginAccount>) -> Result<()> {
     let margin_account = &mut ctx.accounts.margin_account;
     margin_account.owner = ctx.accounts.owner.key();
+    margin_account.viewing_key = [0u8; 32];
 
     // Initialize with encrypted zeros (all zeros represents encrypted zero)
     margin_account.encrypted_collateral = [0u8; 32];
     margin_account.encrypted_debt = [0u8; 32];
     margin_account.nonce = 0;
+    margin_account.encrypted_position = [[0u8; 32]; 3];
+    margin_account.position_nonce = 0;
 
     margin_account.is_liquidatable = false;
+    margin_account.health_check_slot = 0;
+    margin_account.health_check_offset = 0;
+    margin_account.state_version = 0;
+    margin_account.health_check_version = 0;
+    margin_account.long_loss_snapshot = 0;
+    margin_account.short_loss_snapshot = 0;
+    margin_account.adl_pending = false;
+    margin_account.adl_completed = false;
+    margin_account.order_sequence = 0;
+    margin_account.viewing_nonce = 0;
+    margin_account.pending_deposit = 0;
+    margin_account.approved_withdrawal = 0;
     margin_account.bump = ctx.bumps.margin_account;
 
     msg!("Margin account initialized with encrypted balances");
@@ -25,7 +40,8 @@ pub fn initialize_margin_account(ctx: Context<InitializeMarginAccount>) -> Resul
 
 /// Deposit wZEC from the user's token account into the margin vault.
 /// The actual balance update happens via Arcium MPC (see margin_arcium::queue_deposit)
-/// This function only handles the token transfer.
+/// This function only handles the token transfer and records the amount as
+/// pending, which is all `queue_deposit` can credit.
 pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Result<()> {
     require!(amount > 0, ErrorCode::InvalidAmount);
 
@@ -38,6 +54,12 @@ pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Resul
     let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
     token::transfer(cpi_ctx, amount)?;
 
+    let margin_account = &mut ctx.accounts.margin_account;
+    margin_account.pending_deposit = margin_account
+        .pending_deposit
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
     // NOTE: Encrypted balance update must be done separately via margin_arcium::queue_deposit
     // This keeps the token transfer and encrypted accounting as separate steps
     msg!("Tokens deposited. Call queue_deposit to update encrypted balance.");
@@ -47,15 +69,18 @@ pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Resul
 
 /// Withdraw wZEC from the margin vault back to the user.
 /// The actual balance validation happens via Arcium MPC (see margin_arcium::queue_withdraw)
-/// This function only handles the token transfer after MPC approval.
+/// This function only handles the token transfer after MPC approval: at most
+/// the amount MPC already debited from the encrypted collateral, after
+/// checking the account still meets the maintenance margin, can leave.
 pub fn withdraw_collateral(ctx: Context<WithdrawCollateral>, amount: u64) -> Result<()> {
     require!(amount > 0, ErrorCode::InvalidAmount);
 
-    let margin_account = &ctx.accounts.margin_account;
-
-    // NOTE: Balance check must be done via margin_arcium::queue_withdraw first
-    // MPC will verify encrypted balance >= amount before allowing withdrawal
-    // This function assumes the check has already passed
+    let margin_account = &mut ctx.accounts.margin_account;
+    require!(
+        amount <= margin_account.approved_withdrawal,
+        ErrorCode::InsufficientCollateral
+    );
+    margin_account.approved_withdrawal -= amount;
 
     // Seeds for PDA signing: [b"margin", owner, bump]
     let owner_key = margin_account.owner;
@@ -77,7 +102,17 @@ pub fn withdraw_collateral(ctx: Context<WithdrawCollateral>, amount: u64) -> Res
     );
     token::transfer(cpi_ctx, amount)?;
 
-    msg!("Tokens withdrawn. Encrypted balance updated via MPC.");
+    msg!("Tokens withdrawn. Encrypted balance was debited via MPC.");
+    Ok(())
+}
+
+/// Register the x25519 key the owner decrypts results with
+/// (e.g. the margin ratio computed by health checks).
+pub fn set_viewing_key(ctx: Context<SetViewingKey>, viewing_key: [u8; 32]) -> Result<()> {
+    require!(viewing_key != [0u8; 32], ErrorCode::InvalidViewingKey);
+    ctx.accounts.margin_account.viewing_key = viewing_key;
+
+    msg!("Viewing key updated");
     Ok(())
 }
 
@@ -191,3 +226,17 @@ pub struct WithdrawCollateral<'info> {
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
+
+#[derive(Accounts)]
+pub struct SetViewingKey<'info> {
+    /// The owner of the margin account.
+    pub owner: Signer<'info>,
+
+    /// Margin account PDA.
+    #[account(
+        mut,
+        seeds = [MarginAccount::SEED_PREFIX, owner.key().as_ref()],
+        bump = margin_account.bump,
+    )]
+    pub margin_account: Account<'info, MarginAccount>,
+}
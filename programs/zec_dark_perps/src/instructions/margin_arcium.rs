@@ -1,8 +1,14 @@
 use anchor_lang::prelude::*;
 use arcium_anchor::prelude::*;
-use arcium_client::idl::arcium::types::{CircuitSource, OffChainCircuitSource};
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
 
+use crate::error::ErrorCode;
+use crate::instructions::shared::validate_callback_ixs;
+use crate::state::fill::{Fill, FillStatus};
 use crate::state::margin_account::MarginAccount;
+use crate::state::market::Market;
+use crate::state::program_config::ProgramConfig;
+use crate::state::protocol_fees::ProtocolFees;
 use crate::state::SignerAccount;
 use crate::ID;
 use crate::ID as ID_CONST;
@@ -12,11 +18,6 @@ const COMP_DEF_OFFSET_DEPOSIT: u32 = comp_def_offset("deposit_collateral");
 const COMP_DEF_OFFSET_WITHDRAW: u32 = comp_def_offset("withdraw_collateral");
 const COMP_DEF_OFFSET_SETTLE: u32 = comp_def_offset("settle_trade");
 
-// Helper function expected by arcium_callback macro
-fn validate_callback_ixs(_account_info: &AccountInfo, _pubkey: &Pubkey) -> Result<()> {
-    Ok(())
-}
-
 // ========== DEPOSIT COLLATERAL ==========
 
 /// Initialize computation definition for deposit
@@ -35,21 +36,28 @@ pub fn init_deposit_comp_def(ctx: Context<InitDepositCompDef>) -> Result<()> {
 }
 
 /// Queue deposit computation to MPC
-/// This updates the encrypted collateral balance
+/// Credits `amount` of the tokens already received into the vault (see
+/// `margin::deposit_collateral`) to the encrypted collateral.
 pub fn queue_deposit(
     ctx: Context<QueueDeposit>,
     computation_offset: u64,
     amount: u64,
 ) -> Result<()> {
-    let margin_account = &ctx.accounts.margin_account;
-
-    // For Enc<Mxe, DepositInput>, we need to pass:
-    // 1. Nonce (PlaintextU128)
-    // 2. Then encrypted struct fields: current_collateral, deposit_amount
+    require!(amount > 0, ErrorCode::InvalidAmount);
+    let margin_account = &mut ctx.accounts.margin_account;
+    require!(
+        amount <= margin_account.pending_deposit,
+        ErrorCode::DepositNotReceived
+    );
+    margin_account.pending_deposit -= amount;
+
+    // Enc<Mxe, MarginBalances>, then the public amount and state version
     let args = vec![
         Argument::PlaintextU128(margin_account.nonce),
         Argument::EncryptedU64(margin_account.encrypted_collateral),
+        Argument::EncryptedU64(margin_account.encrypted_debt),
         Argument::PlaintextU64(amount),
+        Argument::PlaintextU64(margin_account.state_version),
     ];
 
     // Set bump
@@ -61,7 +69,10 @@ pub fn queue_deposit(
         computation_offset,
         args,
         None,
-        vec![DepositCollateralCallback::callback_ix(&[])],
+        vec![DepositCollateralCallback::callback_ix(&[CallbackAccount {
+            pubkey: ctx.accounts.margin_account.key(),
+            is_writable: true,
+        }])],
         1,
     )?;
 
@@ -70,27 +81,43 @@ pub fn queue_deposit(
 }
 
 /// Callback after deposit computation
-/// Emits event with encrypted balance result
+/// Writes the re-encrypted balances back on success. A deposit that was not
+/// credited (overflow, or the account changed while the computation was in
+/// flight) goes back to `pending_deposit` so it can be queued again.
 #[arcium_callback(encrypted_ix = "deposit_collateral")]
 pub fn deposit_collateral_callback(
     ctx: Context<DepositCollateralCallback>,
     output: ComputationOutputs<DepositCollateralOutput>,
 ) -> Result<()> {
-    let result = match output {
-        ComputationOutputs::Success(DepositCollateralOutput { field_0 }) => field_0,
-        _ => return Err(crate::error::ErrorCode::AbortedComputation.into()),
+    let (balances, success, amount, state_version) = match output {
+        ComputationOutputs::Success(DepositCollateralOutput {
+            field_0:
+                DepositCollateralOutputStruct0 {
+                    field_0: balances,
+                    field_1: success,
+                    field_2: amount,
+                    field_3: state_version,
+                },
+        }) => (balances, success, amount, state_version),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
     };
 
-    // Extract encrypted results from MPC computation
-    let new_balance_ciphertext = result.ciphertexts[0];
-    let success_flag = result.ciphertexts[1];
+    let margin_account = &mut ctx.accounts.margin_account;
+    let credited = success && state_version == margin_account.state_version;
+    if credited {
+        margin_account.encrypted_collateral = balances.ciphertexts[0];
+        margin_account.encrypted_debt = balances.ciphertexts[1];
+        margin_account.nonce = balances.nonce;
+        margin_account.mark_state_changed();
+    } else {
+        margin_account.pending_deposit = margin_account.pending_deposit.saturating_add(amount);
+        msg!("Deposit not credited, returned to pending");
+    }
 
-    // Emit event with encrypted results
-    // Account updates happen in a separate instruction
     emit!(DepositEvent {
-        nonce: result.nonce.to_le_bytes(),
-        new_balance: new_balance_ciphertext,
-        success: success_flag[0],
+        margin_account: margin_account.key(),
+        amount,
+        success: credited,
         timestamp: Clock::get()?.unix_timestamp,
     });
 
@@ -116,21 +143,45 @@ pub fn init_withdraw_comp_def(ctx: Context<InitWithdrawCompDef>) -> Result<()> {
 }
 
 /// Queue withdraw computation to MPC
+/// Debits `amount` from the encrypted collateral if the account still meets
+/// the maintenance margin afterwards; on success the owner can transfer it
+/// out with `margin::withdraw_collateral`.
+///
+/// Requirements:
+/// - The payer must own the margin account
+/// - The mark price must be fresh
+/// - Socialized losses must already be applied to the account
 pub fn queue_withdraw(
     ctx: Context<QueueWithdraw>,
     computation_offset: u64,
     amount: u64,
 ) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
     let margin_account = &ctx.accounts.margin_account;
-
-    // For Enc<Mxe, WithdrawInput>, we need to pass:
-    // 1. Nonce (PlaintextU128)
-    // 2. Then encrypted struct fields: current_collateral, withdraw_amount
-    let args = vec![
+    let market = &ctx.accounts.market;
+    require!(
+        market.is_price_fresh(Clock::get()?.slot),
+        ErrorCode::StaleMarkPrice
+    );
+    require!(
+        margin_account.has_current_loss_index(market),
+        ErrorCode::PendingSocializedLoss
+    );
+
+    // Enc<Mxe, MarginBalances>, Enc<Mxe, Position>, then the public amount,
+    // mark price, maintenance margin and state version
+    let mut args = vec![
         Argument::PlaintextU128(margin_account.nonce),
         Argument::EncryptedU64(margin_account.encrypted_collateral),
-        Argument::PlaintextU64(amount),
+        Argument::EncryptedU64(margin_account.encrypted_debt),
     ];
+    push_position_args(&mut args, margin_account);
+    args.extend([
+        Argument::PlaintextU64(amount),
+        Argument::PlaintextU64(market.mark_price),
+        Argument::PlaintextU64(market.maintenance_margin_bps as u64),
+        Argument::PlaintextU64(margin_account.state_version),
+    ]);
 
     // Set bump
     ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
@@ -141,7 +192,12 @@ pub fn queue_withdraw(
         computation_offset,
         args,
         None,
-        vec![WithdrawCollateralCallback::callback_ix(&[])],
+        vec![WithdrawCollateralCallback::callback_ix(&[
+            CallbackAccount {
+                pubkey: ctx.accounts.margin_account.key(),
+                is_writable: true,
+            },
+        ])],
         1,
     )?;
 
@@ -150,27 +206,46 @@ pub fn queue_withdraw(
 }
 
 /// Callback after withdraw computation
-/// Emits event with encrypted balance result
+/// On success, writes the re-encrypted balances back and approves the
+/// amount for transfer out of the vault. The result is dropped if the
+/// account changed while the computation was in flight.
 #[arcium_callback(encrypted_ix = "withdraw_collateral")]
 pub fn withdraw_collateral_callback(
     ctx: Context<WithdrawCollateralCallback>,
     output: ComputationOutputs<WithdrawCollateralOutput>,
 ) -> Result<()> {
-    let result = match output {
-        ComputationOutputs::Success(WithdrawCollateralOutput { field_0 }) => field_0,
-        _ => return Err(crate::error::ErrorCode::AbortedComputation.into()),
+    let (balances, success, amount, state_version) = match output {
+        ComputationOutputs::Success(WithdrawCollateralOutput {
+            field_0:
+                WithdrawCollateralOutputStruct0 {
+                    field_0: balances,
+                    field_1: success,
+                    field_2: amount,
+                    field_3: state_version,
+                },
+        }) => (balances, success, amount, state_version),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
     };
 
-    // Extract encrypted results from MPC computation
-    let new_balance_ciphertext = result.ciphertexts[0];
-    let success_flag = result.ciphertexts[1];
+    let margin_account = &mut ctx.accounts.margin_account;
+    if state_version != margin_account.state_version {
+        msg!("Outdated withdrawal result ignored");
+        return Ok(());
+    }
+
+    if success {
+        margin_account.encrypted_collateral = balances.ciphertexts[0];
+        margin_account.encrypted_debt = balances.ciphertexts[1];
+        margin_account.nonce = balances.nonce;
+        margin_account.mark_state_changed();
+        margin_account.approved_withdrawal =
+            margin_account.approved_withdrawal.saturating_add(amount);
+    }
 
-    // Emit event with encrypted results
-    // Account updates happen in a separate instruction
     emit!(WithdrawEvent {
-        nonce: result.nonce.to_le_bytes(),
-        new_balance: new_balance_ciphertext,
-        success: success_flag[0],
+        margin_account: margin_account.key(),
+        amount,
+        success,
         timestamp: Clock::get()?.unix_timestamp,
     });
 
@@ -270,6 +345,10 @@ pub struct DepositCollateralCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by constraint
     pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Margin account being credited
+    #[account(mut)]
+    pub margin_account: Account<'info, MarginAccount>,
 }
 
 /// Initialize withdraw computation definition
@@ -338,14 +417,22 @@ pub struct QueueWithdraw<'info> {
     #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
     pub clock_account: Account<'info, ClockAccount>,
 
-    /// Margin account being updated
+    /// Margin account being updated, owned by the payer
     #[account(
         mut,
         seeds = [MarginAccount::SEED_PREFIX, margin_account.owner.as_ref()],
-        bump = margin_account.bump
+        bump = margin_account.bump,
+        constraint = margin_account.owner == payer.key() @ ErrorCode::UnauthorizedMarginOwner,
     )]
     pub margin_account: Account<'info, MarginAccount>,
 
+    /// Market providing the mark price and holding the loss indexes
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
     pub system_program: Program<'info, System>,
     pub arcium_program: Program<'info, Arcium>,
 }
@@ -362,23 +449,29 @@ pub struct WithdrawCollateralCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by constraint
     pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Margin account being debited
+    #[account(mut)]
+    pub margin_account: Account<'info, MarginAccount>,
 }
 
 // ========== EVENTS ==========
 
+/// Emitted when a deposit computation completes. The balance stays encrypted.
 #[event]
 pub struct DepositEvent {
-    pub nonce: [u8; 16],
-    pub new_balance: [u8; 32],
-    pub success: u8,
+    pub margin_account: Pubkey,
+    pub amount: u64,
+    pub success: bool,
     pub timestamp: i64,
 }
 
+/// Emitted when a withdraw computation completes. The balance stays encrypted.
 #[event]
 pub struct WithdrawEvent {
-    pub nonce: [u8; 16],
-    pub new_balance: [u8; 32],
-    pub success: u8,
+    pub margin_account: Pubkey,
+    pub amount: u64,
+    pub success: bool,
     pub timestamp: i64,
 }
 
@@ -401,28 +494,82 @@ pub fn init_settle_comp_def(ctx: Context<InitSettleCompDef>) -> Result<()> {
 
 /// Queue settle trade computation to MPC
 /// Updates both buyer and seller encrypted balances atomically
-pub fn queue_settle_trade(
-    ctx: Context<QueueSettleTrade>,
-    computation_offset: u64,
-    trade_value: u64,
-) -> Result<()> {
+/// Only an authorized matcher can queue a settlement, and only for a fill
+/// produced by `match_orders` between the two margin accounts.
+/// The fill price and size stay encrypted: the notional is computed in MPC.
+pub fn queue_settle_trade(ctx: Context<QueueSettleTrade>, computation_offset: u64) -> Result<()> {
     let buyer = &ctx.accounts.buyer_margin;
     let seller = &ctx.accounts.seller_margin;
-
-    // For Enc<Mxe, TradeSettlement>, we need to pass:
-    // 1. Nonce (PlaintextU128) - shared nonce for the encrypted context
-    // 2. Then the encrypted struct fields in order:
-    //    - buyer_collateral (EncryptedU64)
-    //    - seller_collateral (EncryptedU64)
-    //    - trade_value (PlaintextU64)
-
-    // Use buyer's nonce (both should have same nonce for MXE encryption)
-    let args = vec![
+    let fill = &ctx.accounts.fill;
+
+    require!(
+        fill.status == FillStatus::Matched,
+        ErrorCode::InvalidFillStatus
+    );
+    require_keys_eq!(
+        fill.buyer_margin,
+        buyer.key(),
+        ErrorCode::OrderAccountMismatch
+    );
+    require_keys_eq!(
+        fill.seller_margin,
+        seller.key(),
+        ErrorCode::OrderAccountMismatch
+    );
+
+    let market = &ctx.accounts.market;
+    require!(
+        market.is_price_fresh(Clock::get()?.slot),
+        ErrorCode::StaleMarkPrice
+    );
+    require!(
+        buyer.has_current_loss_index(market) && seller.has_current_loss_index(market),
+        ErrorCode::PendingSocializedLoss
+    );
+
+    let fees = &ctx.accounts.protocol_fees;
+    let (taker_bps, maker_bps, maker_rebate) = market.fee_rates();
+
+    // Each Enc<Mxe, _> input is passed as its nonce followed by its fields:
+    // buyer balances (collateral, debt), seller balances, buyer and seller
+    // positions, fill (price, size), protocol fees, open interest; then the
+    // public fee rates, maker side, mark price and maintenance margin
+    let mut args = vec![
         Argument::PlaintextU128(buyer.nonce),
         Argument::EncryptedU64(buyer.encrypted_collateral),
+        Argument::EncryptedU64(buyer.encrypted_debt),
+        Argument::PlaintextU128(seller.nonce),
         Argument::EncryptedU64(seller.encrypted_collateral),
-        Argument::PlaintextU64(trade_value),
+        Argument::EncryptedU64(seller.encrypted_debt),
     ];
+    push_position_args(&mut args, buyer);
+    push_position_args(&mut args, seller);
+    args.extend([
+        Argument::PlaintextU128(fill.fill_nonce),
+        Argument::EncryptedU64(fill.encrypted_fill[0]),
+        Argument::EncryptedU64(fill.encrypted_fill[1]),
+        Argument::PlaintextU128(fees.fees_nonce),
+        Argument::EncryptedU64(fees.encrypted_fees),
+    ]);
+    push_open_interest_args(&mut args, fees);
+    args.extend([
+        Argument::PlaintextU64(taker_bps),
+        Argument::PlaintextU64(maker_bps),
+        Argument::PlaintextBool(maker_rebate),
+        Argument::PlaintextBool(fill.buyer_is_maker),
+        Argument::PlaintextU64(market.mark_price),
+        Argument::PlaintextU64(market.maintenance_margin_bps as u64),
+        Argument::PlaintextU64(buyer.state_version),
+        Argument::PlaintextU64(seller.state_version),
+    ]);
+
+    // Keyed by the computation, so a settlement that was reset cannot
+    // release a lock a later settlement of the same fill took
+    let computation_key = ctx.accounts.computation_account.key();
+    ctx.accounts
+        .protocol_fees
+        .acquire(computation_key, Clock::get()?.slot)?;
+    ctx.accounts.fill.start_settling(computation_key);
 
     // Set bump
     ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
@@ -433,7 +580,28 @@ pub fn queue_settle_trade(
         computation_offset,
         args,
         None,
-        vec![SettleTradeCallback::callback_ix(&[])],
+        vec![SettleTradeCallback::callback_ix(&[
+            CallbackAccount {
+                pubkey: ctx.accounts.buyer_margin.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.seller_margin.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.fill.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.protocol_fees.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: computation_key,
+                is_writable: false,
+            },
+        ])],
         1,
     )?;
 
@@ -442,30 +610,119 @@ pub fn queue_settle_trade(
 }
 
 /// Callback after settle trade computation
-/// Emits event with encrypted balance results for both parties
+/// On success, writes the re-encrypted balances and positions of both parties
+/// back to their margin accounts and the re-encrypted protocol fee balance and
+/// open interest. Only the parties and the success bit are public.
+///
+/// The result is computed from the balances read at queue time, so it is
+/// dropped if either account has moved past that state (e.g. socialized
+/// losses were charged meanwhile); the fill goes back to Matched and can be
+/// settled again. If the protocol fee lock expired and was taken over
+/// meanwhile, the fees and open interest have moved on without this fill, so
+/// the whole result is dropped and the fill fails. A result for a settlement
+/// that was reset with `reset_settling_fill` is ignored.
 #[arcium_callback(encrypted_ix = "settle_trade")]
 pub fn settle_trade_callback(
     ctx: Context<SettleTradeCallback>,
     output: ComputationOutputs<SettleTradeOutput>,
 ) -> Result<()> {
-    let result = match output {
-        ComputationOutputs::Success(SettleTradeOutput { field_0 }) => field_0,
-        _ => return Err(crate::error::ErrorCode::AbortedComputation.into()),
+    let (
+        buyer_balances,
+        seller_balances,
+        buyer_position,
+        seller_position,
+        fees,
+        open_interest,
+        success,
+        buyer_state_version,
+        seller_state_version,
+    ) = match output {
+        ComputationOutputs::Success(SettleTradeOutput {
+            field_0:
+                SettleTradeOutputStruct0 {
+                    field_0: buyer_balances,
+                    field_1: seller_balances,
+                    field_2: buyer_position,
+                    field_3: seller_position,
+                    field_4: fees,
+                    field_5: open_interest,
+                    field_6: success,
+                    field_7: buyer_state_version,
+                    field_8: seller_state_version,
+                },
+        }) => (
+            buyer_balances,
+            seller_balances,
+            buyer_position,
+            seller_position,
+            fees,
+            open_interest,
+            success,
+            buyer_state_version,
+            seller_state_version,
+        ),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
     };
 
-    // Extract encrypted results from MPC computation
-    // Result has: new_buyer_collateral, new_seller_collateral, success
-    let new_buyer_balance = result.ciphertexts[0];
-    let new_seller_balance = result.ciphertexts[1];
-    let success_flag = result.ciphertexts[2];
+    let computation_key = ctx.accounts.computation_account.key();
+    let fill = &mut ctx.accounts.fill;
+    let buyer = &mut ctx.accounts.buyer_margin;
+    let seller = &mut ctx.accounts.seller_margin;
+    let protocol_fees = &mut ctx.accounts.protocol_fees;
+    let holds_lock = protocol_fees.release(&computation_key);
+
+    if !fill.is_settling_under(&computation_key) {
+        msg!("Settlement was reset while in flight, result ignored");
+        return Ok(());
+    }
+
+    if buyer_state_version != buyer.state_version || seller_state_version != seller.state_version {
+        fill.finish_settling(FillStatus::Matched);
+        msg!("Margin account changed while the settlement was in flight, result dropped");
+        return Ok(());
+    }
+
+    if !holds_lock {
+        fill.finish_settling(FillStatus::Failed);
+        msg!("Fee lock expired, settlement result dropped");
+        return Ok(());
+    }
+
+    fill.finish_settling(if success {
+        FillStatus::Settled
+    } else {
+        FillStatus::Failed
+    });
+
+    // Nothing changes inside MPC on failure, so nothing is written back
+    if success {
+        buyer.encrypted_collateral = buyer_balances.ciphertexts[0];
+        buyer.encrypted_debt = buyer_balances.ciphertexts[1];
+        buyer.nonce = buyer_balances.nonce;
+        buyer.encrypted_position = buyer_position.ciphertexts;
+        buyer.position_nonce = buyer_position.nonce;
+        buyer.mark_state_changed();
+
+        seller.encrypted_collateral = seller_balances.ciphertexts[0];
+        seller.encrypted_debt = seller_balances.ciphertexts[1];
+        seller.nonce = seller_balances.nonce;
+        seller.encrypted_position = seller_position.ciphertexts;
+        seller.position_nonce = seller_position.nonce;
+        seller.mark_state_changed();
+    }
+
+    if success {
+        protocol_fees.encrypted_fees = fees.ciphertexts[0];
+        protocol_fees.fees_nonce = fees.nonce;
+        protocol_fees.encrypted_open_interest = open_interest.ciphertexts;
+        protocol_fees.open_interest_nonce = open_interest.nonce;
+    }
 
-    // Emit event with encrypted results
-    // Account updates happen in a separate instruction
     emit!(TradeSettledEvent {
-        nonce: result.nonce.to_le_bytes(),
-        buyer_balance: new_buyer_balance,
-        seller_balance: new_seller_balance,
-        success: success_flag[0],
+        fill: fill.key(),
+        buyer: buyer.owner,
+        seller: seller.owner,
+        success,
         timestamp: Clock::get()?.unix_timestamp,
     });
 
@@ -473,6 +730,62 @@ pub fn settle_trade_callback(
     Ok(())
 }
 
+/// Return a fill stuck in Settling to Matched
+/// Permissionless: once the settlement (single or batched) no longer holds
+/// the protocol fee lock, its callback can no longer apply, so the fill is
+/// handed back for settlement. A late callback from the reset settlement is
+/// ignored.
+pub fn reset_settling_fill(ctx: Context<ResetSettlingFill>) -> Result<()> {
+    let fill = &mut ctx.accounts.fill;
+    require!(
+        fill.status == FillStatus::Settling,
+        ErrorCode::InvalidFillStatus
+    );
+    require!(
+        !ctx.accounts
+            .protocol_fees
+            .is_held_by(&fill.settling_computation, Clock::get()?.slot),
+        ErrorCode::SettlementInFlight
+    );
+
+    let computation = fill.settling_computation;
+    fill.finish_settling(FillStatus::Matched);
+
+    emit!(FillSettlementReset {
+        fill: fill.key(),
+        computation,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Fill settlement reset, fill can be settled again");
+    Ok(())
+}
+
+/// Push a margin account's Enc<Mxe, Position> as circuit arguments:
+/// nonce, then size, side, entry_notional
+pub fn push_position_args(args: &mut Vec<Argument>, margin: &MarginAccount) {
+    args.push(Argument::PlaintextU128(margin.position_nonce));
+    args.push(Argument::EncryptedU64(margin.encrypted_position[0]));
+    args.push(Argument::EncryptedU8(margin.encrypted_position[1]));
+    args.push(Argument::EncryptedU64(margin.encrypted_position[2]));
+}
+
+/// Push the Enc<Mxe, OpenInterest> held on the protocol fee account as
+/// circuit arguments: nonce, then long, short
+pub fn push_open_interest_args(args: &mut Vec<Argument>, fees: &ProtocolFees) {
+    args.push(Argument::PlaintextU128(fees.open_interest_nonce));
+    args.push(Argument::EncryptedU64(fees.encrypted_open_interest[0]));
+    args.push(Argument::EncryptedU64(fees.encrypted_open_interest[1]));
+}
+
+/// Push zeroed Enc<Mxe, Position> arguments for an unused batch slot
+pub fn push_padding_position_args(args: &mut Vec<Argument>) {
+    args.push(Argument::PlaintextU128(0));
+    args.push(Argument::EncryptedU64([0u8; 32]));
+    args.push(Argument::EncryptedU8([0u8; 32]));
+    args.push(Argument::EncryptedU64([0u8; 32]));
+}
+
 // ========== SETTLE TRADE ACCOUNT STRUCTS ==========
 
 /// Initialize settle_trade computation definition
@@ -541,14 +854,52 @@ pub struct QueueSettleTrade<'info> {
     #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
     pub clock_account: Account<'info, ClockAccount>,
 
+    /// Authorized matching engine queuing the settlement
+    pub matcher: Signer<'info>,
+
+    /// Program config holding the authorized matchers
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        constraint = config.is_matcher(&matcher.key()) @ ErrorCode::UnauthorizedMatcher,
+    )]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
     /// Buyer's margin account
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [MarginAccount::SEED_PREFIX, buyer_margin.owner.as_ref()],
+        bump = buyer_margin.bump
+    )]
     pub buyer_margin: Account<'info, MarginAccount>,
 
     /// Seller's margin account
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [MarginAccount::SEED_PREFIX, seller_margin.owner.as_ref()],
+        bump = seller_margin.bump
+    )]
     pub seller_margin: Account<'info, MarginAccount>,
 
+    /// Encrypted fill produced by `match_orders`
+    #[account(mut)]
+    pub fill: Box<Account<'info, Fill>>,
+
+    /// Market providing the maker and taker fee rates
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// Encrypted protocol fee balance
+    #[account(
+        mut,
+        seeds = [ProtocolFees::SEED_PREFIX],
+        bump = protocol_fees.bump,
+    )]
+    pub protocol_fees: Box<Account<'info, ProtocolFees>>,
+
     pub system_program: Program<'info, System>,
     pub arcium_program: Program<'info, Arcium>,
 }
@@ -565,13 +916,56 @@ pub struct SettleTradeCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by constraint
     pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Buyer's margin account
+    #[account(mut)]
+    pub buyer_margin: Account<'info, MarginAccount>,
+
+    /// Seller's margin account
+    #[account(mut)]
+    pub seller_margin: Account<'info, MarginAccount>,
+
+    /// Fill being settled
+    #[account(mut)]
+    pub fill: Account<'info, Fill>,
+
+    /// Encrypted protocol fee balance
+    #[account(mut)]
+    pub protocol_fees: Account<'info, ProtocolFees>,
+
+    /// CHECK: computation_account of this settlement, matched by key only
+    pub computation_account: UncheckedAccount<'info>,
+}
+
+/// Reset a fill whose settlement callback never landed
+#[derive(Accounts)]
+pub struct ResetSettlingFill<'info> {
+    /// Fill stuck in Settling
+    #[account(mut)]
+    pub fill: Account<'info, Fill>,
+
+    /// Protocol fee account holding the settlement lock
+    #[account(
+        seeds = [ProtocolFees::SEED_PREFIX],
+        bump = protocol_fees.bump,
+    )]
+    pub protocol_fees: Account<'info, ProtocolFees>,
 }
 
 #[event]
 pub struct TradeSettledEvent {
-    pub nonce: [u8; 16],
-    pub buyer_balance: [u8; 32],
-    pub seller_balance: [u8; 32],
-    pub success: u8,
+    pub fill: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub success: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when a fill stuck in Settling is handed back for settlement
+#[event]
+pub struct FillSettlementReset {
+    pub fill: Pubkey,
+    /// Settlement computation whose result will be ignored
+    pub computation: Pubkey,
     pub timestamp: i64,
 }
@@ -0,0 +1,234 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::state::market::Market;
+
+// ========== INITIALIZE MARKET ==========
+
+/// Initialize the market configuration with its price oracle and order constraints
+/// Only the program upgrade authority can call this, so the first caller
+/// after deployment cannot take over the market authority.
+pub fn initialize_market(
+    ctx: Context<InitializeMarket>,
+    oracle: Pubkey,
+    tick_size: u64,
+    lot_size: u64,
+    price_band_bps: u16,
+) -> Result<()> {
+    validate_market_params(tick_size, lot_size, price_band_bps)?;
+
+    let market = &mut ctx.accounts.market;
+    market.authority = ctx.accounts.authority.key();
+    market.oracle = oracle;
+    market.mark_price = 0;
+    market.mark_price_slot = 0;
+    market.tick_size = tick_size;
+    market.lot_size = lot_size;
+    market.price_band_bps = price_band_bps;
+    market.maker_fee_bps = 0;
+    market.taker_fee_bps = 0;
+    market.maintenance_margin_bps = Market::DEFAULT_MAINTENANCE_MARGIN_BPS;
+    market.liquidation_penalty_bps = Market::DEFAULT_LIQUIDATION_PENALTY_BPS;
+    market.insurance_fund_share_bps = Market::DEFAULT_INSURANCE_FUND_SHARE_BPS;
+    market.health_check_max_age_slots = Market::DEFAULT_HEALTH_CHECK_MAX_AGE_SLOTS;
+    market.long_loss_index = 0;
+    market.short_loss_index = 0;
+    market.bump = ctx.bumps.market;
+
+    msg!("Market initialized with oracle: {}", oracle);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeMarket<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [Market::SEED_PREFIX],
+        bump,
+        space = Market::SPACE,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// This program, to locate its program data account
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::ZecDarkPerps>,
+
+    /// Program data account holding the upgrade authority
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::UnauthorizedUpgradeAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ========== UPDATE MARKET PARAMS ==========
+
+/// Update order constraints. Only the market authority can call this.
+pub fn update_market_params(
+    ctx: Context<UpdateMarketParams>,
+    tick_size: u64,
+    lot_size: u64,
+    price_band_bps: u16,
+) -> Result<()> {
+    validate_market_params(tick_size, lot_size, price_band_bps)?;
+
+    let market = &mut ctx.accounts.market;
+    market.tick_size = tick_size;
+    market.lot_size = lot_size;
+    market.price_band_bps = price_band_bps;
+
+    msg!(
+        "Market params updated: tick {}, lot {}, band {} bps",
+        tick_size,
+        lot_size,
+        price_band_bps
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarketParams<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+        has_one = authority @ ErrorCode::UnauthorizedMarketAuthority,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+fn validate_market_params(tick_size: u64, lot_size: u64, price_band_bps: u16) -> Result<()> {
+    require!(tick_size > 0, ErrorCode::InvalidMarketParams);
+    require!(lot_size > 0, ErrorCode::InvalidMarketParams);
+    require!(
+        price_band_bps > 0 && price_band_bps <= Market::MAX_PRICE_BAND_BPS,
+        ErrorCode::InvalidMarketParams
+    );
+    Ok(())
+}
+
+// ========== UPDATE MARKET FEES ==========
+
+/// Update maker and taker fee rates. Only the market authority can call this.
+/// A negative maker rate is a rebate, funded by the taker fee of the same fill.
+pub fn update_market_fees(
+    ctx: Context<UpdateMarketParams>,
+    maker_fee_bps: i16,
+    taker_fee_bps: u16,
+) -> Result<()> {
+    require!(
+        taker_fee_bps <= Market::MAX_FEE_BPS,
+        ErrorCode::InvalidMarketParams
+    );
+    require!(
+        maker_fee_bps.unsigned_abs() <= Market::MAX_FEE_BPS,
+        ErrorCode::InvalidMarketParams
+    );
+    // The protocol never pays out more in rebates than it collects
+    require!(
+        maker_fee_bps >= 0 || maker_fee_bps.unsigned_abs() <= taker_fee_bps,
+        ErrorCode::InvalidMarketParams
+    );
+
+    let market = &mut ctx.accounts.market;
+    market.maker_fee_bps = maker_fee_bps;
+    market.taker_fee_bps = taker_fee_bps;
+
+    msg!(
+        "Market fees updated: maker {} bps, taker {} bps",
+        maker_fee_bps,
+        taker_fee_bps
+    );
+    Ok(())
+}
+
+// ========== UPDATE RISK PARAMS ==========
+
+/// Update the maintenance margin used by health checks, the liquidation
+/// penalty and the insurance fund's share of it, and how old (in slots) a
+/// health check may be when it is used to liquidate.
+/// Only the market authority can call this.
+pub fn update_risk_params(
+    ctx: Context<UpdateMarketParams>,
+    maintenance_margin_bps: u16,
+    liquidation_penalty_bps: u16,
+    insurance_fund_share_bps: u16,
+    health_check_max_age_slots: u64,
+) -> Result<()> {
+    require!(
+        maintenance_margin_bps > 0 && maintenance_margin_bps <= Market::MAX_MAINTENANCE_MARGIN_BPS,
+        ErrorCode::InvalidMarketParams
+    );
+    require!(
+        liquidation_penalty_bps < maintenance_margin_bps,
+        ErrorCode::InvalidMarketParams
+    );
+    require!(
+        insurance_fund_share_bps <= Market::MAX_INSURANCE_FUND_SHARE_BPS,
+        ErrorCode::InvalidMarketParams
+    );
+    require!(
+        health_check_max_age_slots > 0,
+        ErrorCode::InvalidMarketParams
+    );
+
+    let market = &mut ctx.accounts.market;
+    market.maintenance_margin_bps = maintenance_margin_bps;
+    market.liquidation_penalty_bps = liquidation_penalty_bps;
+    market.insurance_fund_share_bps = insurance_fund_share_bps;
+    market.health_check_max_age_slots = health_check_max_age_slots;
+
+    msg!(
+        "Market risk params updated: maintenance margin {} bps, liquidation penalty {} bps ({} bps to insurance), health check max age {} slots",
+        maintenance_margin_bps,
+        liquidation_penalty_bps,
+        insurance_fund_share_bps,
+        health_check_max_age_slots
+    );
+    Ok(())
+}
+
+// ========== UPDATE MARK PRICE ==========
+
+/// Push a new mark price. Only the configured oracle can call this.
+pub fn update_mark_price(ctx: Context<UpdateMarkPrice>, mark_price: u64) -> Result<()> {
+    require!(mark_price > 0, ErrorCode::InvalidAmount);
+
+    let market = &mut ctx.accounts.market;
+    market.mark_price = mark_price;
+    market.mark_price_slot = Clock::get()?.slot;
+
+    emit!(MarkPriceUpdated {
+        mark_price,
+        slot: market.mark_price_slot,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarkPrice<'info> {
+    pub oracle: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+        has_one = oracle @ ErrorCode::UnauthorizedOracle,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+#[event]
+pub struct MarkPriceUpdated {
+    pub mark_price: u64,
+    pub slot: u64,
+}
@@ -0,0 +1,747 @@
+use anchor_lang::prelude::*;
+use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
+
+use crate::error::ErrorCode;
+use crate::instructions::order_groups::{oco_leg_allowed, push_oco_accounts, resolve_oco_group};
+use crate::instructions::shared::validate_callback_ixs;
+use crate::instructions::trading::validate_order_pair;
+use crate::state::fill::{Fill, FillStatus};
+use crate::state::margin_account::MarginAccount;
+use crate::state::order::{Order, OrderKind, OrderStatus};
+use crate::state::program_config::ProgramConfig;
+use crate::state::SignerAccount;
+use crate::ID;
+use crate::ID as ID_CONST;
+
+// Computation definition offsets for the matching encrypted instructions
+const COMP_DEF_OFFSET_MATCH_ORDERS: u32 = comp_def_offset("match_orders");
+const COMP_DEF_OFFSET_RETURN_FILL: u32 = comp_def_offset("return_fill");
+
+/// Initialize computation definition for order matching
+pub fn init_match_orders_comp_def(ctx: Context<InitMatchOrdersCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/match_orders.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Match orders computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Queue matching of a buy order against a sell order
+/// Called by an authorized matching engine. Prices and sizes are compared
+/// inside MPC; the resulting fill is stored encrypted for settlement.
+///
+/// For orders in an OCO group, the group and sibling leg accounts are
+/// passed as remaining accounts so the callback can cancel the sibling.
+///
+/// Both orders are held by the fill until the callback lands, so no other
+/// match or TWAP release can rewrite them in the meantime.
+pub fn queue_match_orders(ctx: Context<QueueMatchOrders>, computation_offset: u64) -> Result<()> {
+    let buy_order = &ctx.accounts.buy_order;
+    let sell_order = &ctx.accounts.sell_order;
+    validate_order_pair(
+        buy_order,
+        &ctx.accounts.buyer_margin,
+        sell_order,
+        &ctx.accounts.seller_margin,
+    )?;
+
+    let current_slot = Clock::get()?.slot;
+    for order in [buy_order, sell_order] {
+        require!(
+            !order.is_market_expired(current_slot),
+            ErrorCode::MarketOrderExpired
+        );
+    }
+
+    // Market orders always take liquidity; otherwise the resting (older)
+    // order is the maker and sets the execution price
+    let buy_is_maker = match (buy_order.kind, sell_order.kind) {
+        (OrderKind::Market, OrderKind::Market) => return err!(ErrorCode::MarketOrderPair),
+        (OrderKind::Market, _) => false,
+        (_, OrderKind::Market) => true,
+        _ => buy_order.created_slot <= sell_order.created_slot,
+    };
+
+    let buy_key = buy_order.key();
+    let sell_key = sell_order.key();
+    require!(
+        !buy_order.has_oco_group() || buy_order.oco_group != sell_order.oco_group,
+        ErrorCode::OrderGroupMismatch
+    );
+
+    let mut callback_accounts = vec![
+        CallbackAccount {
+            pubkey: buy_key,
+            is_writable: true,
+        },
+        CallbackAccount {
+            pubkey: sell_key,
+            is_writable: true,
+        },
+        CallbackAccount {
+            pubkey: ctx.accounts.fill.key(),
+            is_writable: true,
+        },
+    ];
+    push_oco_accounts(
+        buy_key,
+        buy_order,
+        ctx.remaining_accounts,
+        &mut callback_accounts,
+    )?;
+    push_oco_accounts(
+        sell_key,
+        sell_order,
+        ctx.remaining_accounts,
+        &mut callback_accounts,
+    )?;
+
+    let mut args = Vec::with_capacity(17);
+    push_order_args(&mut args, buy_order);
+    push_order_args(&mut args, sell_order);
+    args.push(Argument::PlaintextBool(buy_is_maker));
+
+    let fill_key = ctx.accounts.fill.key();
+    ctx.accounts.buy_order.lock(fill_key, current_slot)?;
+    ctx.accounts.sell_order.lock(fill_key, current_slot)?;
+
+    let fill = &mut ctx.accounts.fill;
+    fill.buy_order = buy_key;
+    fill.sell_order = sell_key;
+    fill.buyer_margin = ctx.accounts.buyer_margin.key();
+    fill.seller_margin = ctx.accounts.seller_margin.key();
+    fill.status = FillStatus::Pending;
+    fill.encrypted_fill = [[0u8; 32]; 2];
+    fill.fill_nonce = 0;
+    fill.created_slot = current_slot;
+    fill.buyer_is_maker = buy_is_maker;
+    fill.settling_computation = Pubkey::default();
+    fill.bump = ctx.bumps.fill;
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![MatchOrdersCallback::callback_ix(&callback_accounts)],
+        1,
+    )?;
+
+    msg!("Order match queued to MPC cluster");
+    Ok(())
+}
+
+/// Callback after order matching
+/// Stores remaining order sizes and the encrypted fill.
+/// Orders emptied by self-trade prevention are cancelled, never filled.
+/// A fill on an OCO leg cancels its sibling; a fill computed for a leg whose
+/// sibling executed in the meantime is discarded, and so is a result for an
+/// order that was cancelled or lost its in-flight hold while the match ran.
+#[arcium_callback(encrypted_ix = "match_orders")]
+pub fn match_orders_callback(
+    ctx: Context<MatchOrdersCallback>,
+    output: ComputationOutputs<MatchOrdersOutput>,
+) -> Result<()> {
+    let (buy, sell, encrypted_fill, crossed, buy_filled, sell_filled, self_trade) = match output {
+        ComputationOutputs::Success(MatchOrdersOutput {
+            field_0:
+                MatchOrdersOutputStruct0 {
+                    field_0: buy,
+                    field_1: sell,
+                    field_2: encrypted_fill,
+                    field_3: crossed,
+                    field_4: buy_filled,
+                    field_5: sell_filled,
+                    field_6: self_trade,
+                },
+        }) => (
+            buy,
+            sell,
+            encrypted_fill,
+            crossed,
+            buy_filled,
+            sell_filled,
+            self_trade,
+        ),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let fill = &mut ctx.accounts.fill;
+    require!(
+        fill.status == FillStatus::Pending,
+        ErrorCode::InvalidFillStatus
+    );
+
+    let fill_key = fill.key();
+    let buy_held = ctx.accounts.buy_order.unlock(&fill_key);
+    let sell_held = ctx.accounts.sell_order.unlock(&fill_key);
+    if !buy_held
+        || !sell_held
+        || ctx.accounts.buy_order.status != OrderStatus::Open
+        || ctx.accounts.sell_order.status != OrderStatus::Open
+    {
+        fill.status = FillStatus::NoMatch;
+        msg!("Order changed while the match was in flight, result discarded");
+        return Ok(());
+    }
+
+    if self_trade {
+        fill.status = FillStatus::NoMatch;
+
+        let buy_order = &mut ctx.accounts.buy_order;
+        buy_order.encrypted_order = buy.ciphertexts;
+        buy_order.order_nonce = buy.nonce;
+        if buy_filled {
+            buy_order.status = OrderStatus::Cancelled;
+        }
+
+        let sell_order = &mut ctx.accounts.sell_order;
+        sell_order.encrypted_order = sell.ciphertexts;
+        sell_order.order_nonce = sell.nonce;
+        if sell_filled {
+            sell_order.status = OrderStatus::Cancelled;
+        }
+
+        emit!(SelfTradePrevented {
+            buy_order: buy_order.key(),
+            sell_order: sell_order.key(),
+            buy_cancelled: buy_filled,
+            sell_cancelled: sell_filled,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Self-trade prevented");
+        return Ok(());
+    }
+
+    if !crossed {
+        // A market order that does not cross this counterparty stays live:
+        // the matcher chooses the pairs, so its remainder is only cancelled
+        // once its matching window has passed (see `expire_market_order`)
+        fill.status = FillStatus::NoMatch;
+        msg!("Orders did not cross");
+        return Ok(());
+    }
+
+    let oco_accounts = ctx.remaining_accounts;
+    for order in [&ctx.accounts.buy_order, &ctx.accounts.sell_order] {
+        if !oco_leg_allowed(order.key(), order, oco_accounts)? {
+            fill.status = FillStatus::NoMatch;
+            msg!("OCO sibling already executed, fill discarded");
+            return Ok(());
+        }
+    }
+
+    fill.encrypted_fill = encrypted_fill.ciphertexts;
+    fill.fill_nonce = encrypted_fill.nonce;
+    fill.status = FillStatus::Matched;
+
+    let buy_order = &mut ctx.accounts.buy_order;
+    buy_order.encrypted_order = buy.ciphertexts;
+    buy_order.order_nonce = buy.nonce;
+    if buy_filled {
+        buy_order.status = OrderStatus::Filled;
+    }
+
+    let sell_order = &mut ctx.accounts.sell_order;
+    sell_order.encrypted_order = sell.ciphertexts;
+    sell_order.order_nonce = sell.nonce;
+    if sell_filled {
+        sell_order.status = OrderStatus::Filled;
+    }
+
+    resolve_oco_group(buy_order.key(), buy_order, oco_accounts)?;
+    resolve_oco_group(sell_order.key(), sell_order, oco_accounts)?;
+
+    emit!(OrdersMatched {
+        fill: fill.key(),
+        buy_order: buy_order.key(),
+        sell_order: sell_order.key(),
+        buy_filled,
+        sell_filled,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Orders matched. Call queue_settle_trade to settle the fill.");
+    Ok(())
+}
+
+/// Push an order's Enc<Mxe, OrderData> as circuit arguments:
+/// nonce, then size, price, side, display_size, visible_size, stp_mode, owner_id
+pub fn push_order_args(args: &mut Vec<Argument>, order: &Order) {
+    args.push(Argument::PlaintextU128(order.order_nonce));
+    args.push(Argument::EncryptedU64(order.encrypted_order[0]));
+    args.push(Argument::EncryptedU64(order.encrypted_order[1]));
+    args.push(Argument::EncryptedU8(order.encrypted_order[2]));
+    args.push(Argument::EncryptedU64(order.encrypted_order[3]));
+    args.push(Argument::EncryptedU64(order.encrypted_order[4]));
+    args.push(Argument::EncryptedU8(order.encrypted_order[5]));
+    args.push(Argument::EncryptedU128(order.encrypted_order[6]));
+}
+
+/// Push zeroed Enc<Mxe, OrderData> arguments for an unused batch slot
+pub fn push_padding_order_args(args: &mut Vec<Argument>) {
+    args.push(Argument::PlaintextU128(0));
+    args.push(Argument::EncryptedU64([0u8; 32]));
+    args.push(Argument::EncryptedU64([0u8; 32]));
+    args.push(Argument::EncryptedU8([0u8; 32]));
+    args.push(Argument::EncryptedU64([0u8; 32]));
+    args.push(Argument::EncryptedU64([0u8; 32]));
+    args.push(Argument::EncryptedU8([0u8; 32]));
+    args.push(Argument::EncryptedU128([0u8; 32]));
+}
+
+/// Cancel the remainder of a market order whose matching window has passed
+/// Permissionless: market orders must never rest on the book
+pub fn expire_market_order(ctx: Context<ExpireMarketOrder>) -> Result<()> {
+    let order = &mut ctx.accounts.order;
+    require!(
+        order.kind == OrderKind::Market,
+        ErrorCode::InvalidOrderAccount
+    );
+    require!(
+        order.status == OrderStatus::Open,
+        ErrorCode::InvalidOrderStatus
+    );
+    require!(
+        order.is_market_expired(Clock::get()?.slot),
+        ErrorCode::MarketOrderNotExpired
+    );
+
+    order.status = OrderStatus::Cancelled;
+    emit!(MarketOrderCancelled {
+        order: order.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Market order remainder cancelled");
+    Ok(())
+}
+
+/// Initialize computation definition for returning failed fills
+pub fn init_return_fill_comp_def(ctx: Context<InitReturnFillCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/return_fill.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Return fill computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Queue the return of a failed fill's size to both orders
+/// Permissionless: `match_orders` took the fill size out of both orders, and
+/// a settlement that failed leaves it unused. Both orders are held by this
+/// computation until the callback lands.
+pub fn queue_return_fill(ctx: Context<QueueReturnFill>, computation_offset: u64) -> Result<()> {
+    let fill = &ctx.accounts.fill;
+    require!(
+        fill.status == FillStatus::Failed,
+        ErrorCode::InvalidFillStatus
+    );
+
+    // Both Enc<Mxe, OrderData>, then Enc<Mxe, Fill>
+    let mut args = Vec::with_capacity(19);
+    push_order_args(&mut args, &ctx.accounts.buy_order);
+    push_order_args(&mut args, &ctx.accounts.sell_order);
+    args.push(Argument::PlaintextU128(fill.fill_nonce));
+    args.push(Argument::EncryptedU64(fill.encrypted_fill[0]));
+    args.push(Argument::EncryptedU64(fill.encrypted_fill[1]));
+
+    // Keyed by the computation, so a timed-out return cannot release the
+    // hold of one queued again for the same fill
+    let computation_key = ctx.accounts.computation_account.key();
+    let current_slot = Clock::get()?.slot;
+    ctx.accounts.buy_order.lock(computation_key, current_slot)?;
+    ctx.accounts
+        .sell_order
+        .lock(computation_key, current_slot)?;
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![ReturnFillCallback::callback_ix(&[
+            CallbackAccount {
+                pubkey: ctx.accounts.buy_order.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.sell_order.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.fill.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: computation_key,
+                is_writable: false,
+            },
+        ])],
+        1,
+    )?;
+
+    msg!("Fill return queued to MPC cluster");
+    Ok(())
+}
+
+/// Callback after returning a failed fill
+/// Writes the topped-up orders back; a filled order is live again. A
+/// cancelled order keeps nothing. The result is discarded if either order
+/// lost its hold, or the fill was already returned, while it ran.
+#[arcium_callback(encrypted_ix = "return_fill")]
+pub fn return_fill_callback(
+    ctx: Context<ReturnFillCallback>,
+    output: ComputationOutputs<ReturnFillOutput>,
+) -> Result<()> {
+    let (buy, sell) = match output {
+        ComputationOutputs::Success(ReturnFillOutput {
+            field_0:
+                ReturnFillOutputStruct0 {
+                    field_0: buy,
+                    field_1: sell,
+                },
+        }) => (buy, sell),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let computation_key = ctx.accounts.computation_account.key();
+    let buy_held = ctx.accounts.buy_order.unlock(&computation_key);
+    let sell_held = ctx.accounts.sell_order.unlock(&computation_key);
+    let fill = &mut ctx.accounts.fill;
+    if !buy_held || !sell_held || fill.status != FillStatus::Failed {
+        msg!("Order or fill changed while the return was in flight, result discarded");
+        return Ok(());
+    }
+    fill.status = FillStatus::Returned;
+
+    for (order, result) in [
+        (&mut ctx.accounts.buy_order, buy),
+        (&mut ctx.accounts.sell_order, sell),
+    ] {
+        if matches!(order.status, OrderStatus::Open | OrderStatus::Filled) {
+            order.encrypted_order = result.ciphertexts;
+            order.order_nonce = result.nonce;
+            order.status = OrderStatus::Open;
+        }
+    }
+
+    emit!(FillReturned {
+        fill: fill.key(),
+        buy_order: ctx.accounts.buy_order.key(),
+        sell_order: ctx.accounts.sell_order.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Failed fill returned to both orders");
+    Ok(())
+}
+
+// ========== ACCOUNT STRUCTS ==========
+
+/// Initialize match_orders computation definition
+#[init_computation_definition_accounts("match_orders", payer)]
+#[derive(Accounts)]
+pub struct InitMatchOrdersCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Queue order matching
+#[queue_computation_accounts("match_orders", matcher)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct QueueMatchOrders<'info> {
+    /// Authorized matching engine, pays for the computation and fill account
+    #[account(mut)]
+    pub matcher: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = matcher,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_ORDERS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Program config holding the authorized matchers
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        constraint = config.is_matcher(&matcher.key()) @ ErrorCode::UnauthorizedMatcher,
+    )]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    /// Buyer's margin account
+    #[account(
+        seeds = [MarginAccount::SEED_PREFIX, buyer_margin.owner.as_ref()],
+        bump = buyer_margin.bump
+    )]
+    pub buyer_margin: Box<Account<'info, MarginAccount>>,
+
+    /// Seller's margin account
+    #[account(
+        seeds = [MarginAccount::SEED_PREFIX, seller_margin.owner.as_ref()],
+        bump = seller_margin.bump
+    )]
+    pub seller_margin: Box<Account<'info, MarginAccount>>,
+
+    /// Buy order being matched
+    #[account(mut)]
+    pub buy_order: Box<Account<'info, Order>>,
+
+    /// Sell order being matched
+    #[account(mut)]
+    pub sell_order: Box<Account<'info, Order>>,
+
+    /// Fill account receiving the encrypted match result
+    #[account(
+        init,
+        payer = matcher,
+        seeds = [Fill::SEED_PREFIX, &computation_offset.to_le_bytes()],
+        bump,
+        space = Fill::SPACE,
+    )]
+    pub fill: Box<Account<'info, Fill>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Match orders callback
+#[callback_accounts("match_orders")]
+#[derive(Accounts)]
+pub struct MatchOrdersCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_ORDERS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub buy_order: Account<'info, Order>,
+
+    #[account(mut)]
+    pub sell_order: Account<'info, Order>,
+
+    #[account(mut)]
+    pub fill: Account<'info, Fill>,
+}
+
+/// Expire a market order
+#[derive(Accounts)]
+pub struct ExpireMarketOrder<'info> {
+    #[account(mut)]
+    pub order: Account<'info, Order>,
+}
+
+/// Initialize return_fill computation definition
+#[init_computation_definition_accounts("return_fill", payer)]
+#[derive(Accounts)]
+pub struct InitReturnFillCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Queue the return of a failed fill
+#[queue_computation_accounts("return_fill", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct QueueReturnFill<'info> {
+    /// Anyone, pays for the computation
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RETURN_FILL))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Fill whose settlement failed
+    pub fill: Box<Account<'info, Fill>>,
+
+    /// Buy order of the fill
+    #[account(mut, address = fill.buy_order @ ErrorCode::OrderAccountMismatch)]
+    pub buy_order: Box<Account<'info, Order>>,
+
+    /// Sell order of the fill
+    #[account(mut, address = fill.sell_order @ ErrorCode::OrderAccountMismatch)]
+    pub sell_order: Box<Account<'info, Order>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Return fill callback
+#[callback_accounts("return_fill")]
+#[derive(Accounts)]
+pub struct ReturnFillCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RETURN_FILL))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub buy_order: Account<'info, Order>,
+
+    #[account(mut)]
+    pub sell_order: Account<'info, Order>,
+
+    #[account(mut)]
+    pub fill: Account<'info, Fill>,
+
+    /// CHECK: computation_account of this return, matched by key only
+    pub computation_account: UncheckedAccount<'info>,
+}
+
+// ========== EVENTS ==========
+
+/// Emitted when two orders cross. Fill price and size stay encrypted.
+#[event]
+pub struct OrdersMatched {
+    pub fill: Pubkey,
+    pub buy_order: Pubkey,
+    pub sell_order: Pubkey,
+    pub buy_filled: bool,
+    pub sell_filled: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when two orders from the same margin account would have crossed.
+/// Orders with no size left after self-trade prevention are cancelled.
+#[event]
+pub struct SelfTradePrevented {
+    pub buy_order: Pubkey,
+    pub sell_order: Pubkey,
+    pub buy_cancelled: bool,
+    pub sell_cancelled: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when the unfilled remainder of a market order is cancelled
+#[event]
+pub struct MarketOrderCancelled {
+    pub order: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when a failed fill's size goes back to both orders.
+/// The size stays encrypted.
+#[event]
+pub struct FillReturned {
+    pub fill: Pubkey,
+    pub buy_order: Pubkey,
+    pub sell_order: Pubkey,
+    pub timestamp: i64,
+}
@@ -1,15 +1,40 @@
+pub mod adl;
 pub mod arcium;
+pub mod batch_settlement;
+pub mod book_snapshot;
 pub mod bridge;
+pub mod config;
+pub mod fees;
+pub mod insurance;
 pub mod liquidation;
 pub mod margin;
 pub mod margin_arcium;
+pub mod market;
+pub mod matching;
+pub mod order_groups;
+pub mod rfq;
+pub mod shared;
 pub mod trading;
 pub mod trading_arcium;
+pub mod trigger_orders;
+pub mod twap;
 
+pub use adl::*;
 pub use arcium::*;
+pub use batch_settlement::*;
+pub use book_snapshot::*;
 pub use bridge::*;
+pub use config::*;
+pub use fees::*;
+pub use insurance::*;
 pub use liquidation::*;
 pub use margin::*;
 pub use margin_arcium::*;
+pub use market::*;
+pub use matching::*;
+pub use order_groups::*;
+pub use rfq::*;
 pub use trading::*;
 pub use trading_arcium::*;
+pub use trigger_orders::*;
+pub use twap::*;
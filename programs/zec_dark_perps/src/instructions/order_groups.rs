@@ -0,0 +1,263 @@
+use anchor_lang::prelude::*;
+use arcium_client::idl::arcium::types::CallbackAccount;
+
+use crate::error::ErrorCode;
+use crate::instructions::shared::{has_discriminator, load_account, store_account};
+use crate::state::margin_account::MarginAccount;
+use crate::state::order::{Order, OrderKind, OrderStatus};
+use crate::state::order_group::{OrderGroup, OrderGroupStatus};
+
+/// Link two orders of the same margin account into a one-cancels-other group
+/// Only limit and trigger orders that have not executed yet can be grouped
+pub fn create_oco_group(ctx: Context<CreateOcoGroup>, _group_id: u64) -> Result<()> {
+    let margin_account = ctx.accounts.margin_account.key();
+    let first_key = ctx.accounts.first_order.key();
+    let second_key = ctx.accounts.second_order.key();
+    require_keys_neq!(first_key, second_key, ErrorCode::OrderGroupMismatch);
+
+    for order in [&ctx.accounts.first_order, &ctx.accounts.second_order] {
+        require_keys_eq!(
+            order.margin_account,
+            margin_account,
+            ErrorCode::OrderAccountMismatch
+        );
+        require!(
+            matches!(order.kind, OrderKind::Limit | OrderKind::Trigger),
+            ErrorCode::InvalidOrderAccount
+        );
+        require!(
+            matches!(
+                order.status,
+                OrderStatus::Pending | OrderStatus::Untriggered | OrderStatus::Open
+            ),
+            ErrorCode::InvalidOrderStatus
+        );
+        require!(!order.has_oco_group(), ErrorCode::OrderAlreadyGrouped);
+    }
+
+    let group_key = ctx.accounts.group.key();
+    ctx.accounts.first_order.oco_group = group_key;
+    ctx.accounts.second_order.oco_group = group_key;
+
+    let group = &mut ctx.accounts.group;
+    group.owner = ctx.accounts.owner.key();
+    group.margin_account = margin_account;
+    group.legs = [first_key, second_key];
+    group.status = OrderGroupStatus::Active;
+    group.resolved_by = Pubkey::default();
+    group.bump = ctx.bumps.group;
+
+    emit!(OcoGroupCreated {
+        group: group_key,
+        legs: group.legs,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("OCO group created");
+    Ok(())
+}
+
+/// Unlink an active OCO group; both legs keep trading independently
+pub fn cancel_oco_group(ctx: Context<CancelOcoGroup>) -> Result<()> {
+    require!(
+        ctx.accounts.group.status == OrderGroupStatus::Active,
+        ErrorCode::InvalidOrderGroupStatus
+    );
+
+    ctx.accounts.first_order.oco_group = Pubkey::default();
+    ctx.accounts.second_order.oco_group = Pubkey::default();
+
+    msg!("OCO group dissolved");
+    Ok(())
+}
+
+// ========== HOOKS FOR MATCHING / TRIGGERS ==========
+
+/// Number of leading accounts before the first `OrderGroup` account.
+/// Batches pass their orders first, then the OCO group and sibling accounts.
+pub(crate) fn oco_batch_len(accounts: &[AccountInfo]) -> usize {
+    accounts
+        .iter()
+        .take_while(|info| !has_discriminator(info, OrderGroup::DISCRIMINATOR))
+        .count()
+}
+
+/// For a grouped order about to be sent to MPC, check that its group still
+/// allows it to execute and add the group and sibling leg to the callback
+/// accounts (once each), so the callback can resolve the group
+pub(crate) fn push_oco_accounts(
+    order_key: Pubkey,
+    order: &Order,
+    accounts: &[AccountInfo],
+    callback_accounts: &mut Vec<CallbackAccount>,
+) -> Result<()> {
+    if !order.has_oco_group() {
+        return Ok(());
+    }
+
+    let group_info = find_account(accounts, &order.oco_group)?;
+    let group: OrderGroup = load_account(group_info)?;
+    require!(group.allows(&order_key), ErrorCode::OcoLegResolved);
+    let sibling_key = group
+        .sibling_of(&order_key)
+        .ok_or(ErrorCode::OrderGroupMismatch)?;
+    find_account(accounts, &sibling_key)?;
+
+    for key in [order.oco_group, sibling_key] {
+        if !callback_accounts
+            .iter()
+            .any(|account| account.pubkey == key)
+        {
+            callback_accounts.push(CallbackAccount {
+                pubkey: key,
+                is_writable: true,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Whether a grouped order may still apply an execution computed in MPC:
+/// its sibling may have resolved the group while the computation was in flight
+pub(crate) fn oco_leg_allowed(
+    order_key: Pubkey,
+    order: &Order,
+    accounts: &[AccountInfo],
+) -> Result<bool> {
+    if !order.has_oco_group() {
+        return Ok(true);
+    }
+
+    let group: OrderGroup = load_account(find_account(accounts, &order.oco_group)?)?;
+    Ok(group.allows(&order_key))
+}
+
+/// Resolve the group of an order that just executed: the first leg to
+/// execute wins and the sibling leg is cancelled
+pub(crate) fn resolve_oco_group(
+    order_key: Pubkey,
+    order: &Order,
+    accounts: &[AccountInfo],
+) -> Result<()> {
+    if !order.has_oco_group() {
+        return Ok(());
+    }
+
+    let group_info = find_account(accounts, &order.oco_group)?;
+    let mut group: OrderGroup = load_account(group_info)?;
+    if group.status != OrderGroupStatus::Active {
+        return Ok(());
+    }
+    let sibling_key = group
+        .sibling_of(&order_key)
+        .ok_or(ErrorCode::OrderGroupMismatch)?;
+
+    group.status = OrderGroupStatus::Resolved;
+    group.resolved_by = order_key;
+    store_account(group_info, &group)?;
+
+    let sibling_info = find_account(accounts, &sibling_key)?;
+    let mut sibling: Order = load_account(sibling_info)?;
+    if matches!(
+        sibling.status,
+        OrderStatus::Pending | OrderStatus::Untriggered | OrderStatus::Open
+    ) {
+        sibling.status = OrderStatus::Cancelled;
+        store_account(sibling_info, &sibling)?;
+    }
+
+    emit!(OcoGroupResolved {
+        group: group_info.key(),
+        executed_order: order_key,
+        cancelled_order: sibling_key,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+fn find_account<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    key: &Pubkey,
+) -> Result<&'a AccountInfo<'info>> {
+    accounts
+        .iter()
+        .find(|info| info.key == key)
+        .ok_or_else(|| error!(ErrorCode::OrderGroupMismatch))
+}
+
+// ========== ACCOUNT STRUCTS ==========
+
+/// Create an OCO group
+#[derive(Accounts)]
+#[instruction(group_id: u64)]
+pub struct CreateOcoGroup<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Margin account both legs trade against
+    #[account(
+        seeds = [MarginAccount::SEED_PREFIX, owner.key().as_ref()],
+        bump = margin_account.bump,
+    )]
+    pub margin_account: Account<'info, MarginAccount>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            OrderGroup::SEED_PREFIX,
+            margin_account.key().as_ref(),
+            &group_id.to_le_bytes(),
+        ],
+        bump,
+        space = OrderGroup::SPACE,
+    )]
+    pub group: Account<'info, OrderGroup>,
+
+    #[account(mut)]
+    pub first_order: Box<Account<'info, Order>>,
+
+    #[account(mut)]
+    pub second_order: Box<Account<'info, Order>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Dissolve an OCO group and refund its rent
+#[derive(Accounts)]
+pub struct CancelOcoGroup<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::UnauthorizedGroupOwner,
+        close = owner,
+    )]
+    pub group: Account<'info, OrderGroup>,
+
+    #[account(mut, address = group.legs[0] @ ErrorCode::OrderGroupMismatch)]
+    pub first_order: Box<Account<'info, Order>>,
+
+    #[account(mut, address = group.legs[1] @ ErrorCode::OrderGroupMismatch)]
+    pub second_order: Box<Account<'info, Order>>,
+}
+
+// ========== EVENTS ==========
+
+/// Emitted when two orders are linked into an OCO group
+#[event]
+pub struct OcoGroupCreated {
+    pub group: Pubkey,
+    pub legs: [Pubkey; 2],
+    pub timestamp: i64,
+}
+
+/// Emitted when one leg executes and the other is cancelled
+#[event]
+pub struct OcoGroupResolved {
+    pub group: Pubkey,
+    pub executed_order: Pubkey,
+    pub cancelled_order: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,662 @@
+use anchor_lang::prelude::*;
+use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
+
+use crate::error::ErrorCode;
+use crate::instructions::margin_arcium::{push_open_interest_args, push_position_args};
+use crate::instructions::shared::validate_callback_ixs;
+use crate::state::margin_account::MarginAccount;
+use crate::state::market::Market;
+use crate::state::protocol_fees::ProtocolFees;
+use crate::state::rfq::{Rfq, RfqStatus};
+use crate::state::SignerAccount;
+use crate::ID;
+use crate::ID as ID_CONST;
+
+// Computation definition offsets for RFQ encrypted instructions
+const COMP_DEF_OFFSET_REQUEST_QUOTE: u32 = comp_def_offset("request_quote");
+const COMP_DEF_OFFSET_SETTLE_BLOCK_TRADE: u32 = comp_def_offset("settle_block_trade");
+
+// ========== REQUEST QUOTE ==========
+
+/// Initialize computation definition for quote requests
+pub fn init_request_quote_comp_def(ctx: Context<InitRequestQuoteCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/request_quote.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Request quote computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Post an encrypted request for quote to a single counterparty
+/// Size, side and limit price are encrypted client-side; the request
+/// never touches the order book and only `responder` can answer it
+#[allow(clippy::too_many_arguments)]
+pub fn request_quote(
+    ctx: Context<RequestQuote>,
+    computation_offset: u64,
+    responder: Pubkey,
+    ttl_slots: u64,
+    ciphertext_size: [u8; 32],
+    ciphertext_side: [u8; 32],
+    ciphertext_limit_price: [u8; 32],
+    pub_key: [u8; 32],
+    nonce: u128,
+) -> Result<()> {
+    require!(ttl_slots > 0, ErrorCode::InvalidRfqParams);
+    require_keys_neq!(
+        responder,
+        ctx.accounts.requester.key(),
+        ErrorCode::InvalidRfqParams
+    );
+
+    let current_slot = Clock::get()?.slot;
+    let rfq = &mut ctx.accounts.rfq;
+    rfq.requester = ctx.accounts.requester.key();
+    rfq.requester_margin = ctx.accounts.requester_margin.key();
+    rfq.responder = responder;
+    rfq.responder_margin = Pubkey::default();
+    rfq.status = RfqStatus::Pending;
+    rfq.encrypted_request = [[0u8; 32]; 3];
+    rfq.request_nonce = 0;
+    rfq.created_slot = current_slot;
+    rfq.expiry_slot = current_slot
+        .checked_add(ttl_slots)
+        .ok_or(ErrorCode::MathOverflow)?;
+    rfq.bump = ctx.bumps.rfq;
+
+    // Enc<Shared, RfqInput>: pubkey, nonce, then struct fields in order
+    let args = vec![
+        Argument::ArcisPubkey(pub_key),
+        Argument::PlaintextU128(nonce),
+        Argument::EncryptedU64(ciphertext_size),
+        Argument::EncryptedU8(ciphertext_side),
+        Argument::EncryptedU64(ciphertext_limit_price),
+    ];
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![RequestQuoteCallback::callback_ix(&[CallbackAccount {
+            pubkey: ctx.accounts.rfq.key(),
+            is_writable: true,
+        }])],
+        1,
+    )?;
+
+    msg!("Quote request queued to MPC cluster");
+    Ok(())
+}
+
+/// Callback after a quote request
+/// Stores the MXE-encrypted request, or rejects it if malformed
+#[arcium_callback(encrypted_ix = "request_quote")]
+pub fn request_quote_callback(
+    ctx: Context<RequestQuoteCallback>,
+    output: ComputationOutputs<RequestQuoteOutput>,
+) -> Result<()> {
+    let (encrypted_request, valid) = match output {
+        ComputationOutputs::Success(RequestQuoteOutput {
+            field_0:
+                RequestQuoteOutputStruct0 {
+                    field_0: encrypted_request,
+                    field_1: valid,
+                },
+        }) => (encrypted_request, valid),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let rfq = &mut ctx.accounts.rfq;
+    require!(
+        rfq.status == RfqStatus::Pending,
+        ErrorCode::InvalidRfqStatus
+    );
+
+    if !valid {
+        rfq.status = RfqStatus::Rejected;
+        msg!("Quote request rejected");
+        return Ok(());
+    }
+
+    rfq.encrypted_request = encrypted_request.ciphertexts;
+    rfq.request_nonce = encrypted_request.nonce;
+    rfq.status = RfqStatus::Open;
+
+    emit!(QuoteRequested {
+        rfq: rfq.key(),
+        requester: rfq.requester,
+        responder: rfq.responder,
+        expiry_slot: rfq.expiry_slot,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Quote request stored encrypted on-chain");
+    Ok(())
+}
+
+/// Cancel an open quote request that has not been answered yet
+pub fn cancel_quote_request(ctx: Context<CancelQuoteRequest>) -> Result<()> {
+    let rfq = &mut ctx.accounts.rfq;
+    require!(rfq.status == RfqStatus::Open, ErrorCode::InvalidRfqStatus);
+    rfq.status = RfqStatus::Cancelled;
+
+    msg!("Quote request cancelled");
+    Ok(())
+}
+
+// ========== SETTLE BLOCK TRADE ==========
+
+/// Initialize computation definition for block trade settlement
+pub fn init_settle_block_trade_comp_def(ctx: Context<InitSettleBlockTradeCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/settle_block_trade.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Settle block trade computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Answer a quote request with an encrypted price
+/// The quote is checked against the requester's limit and both margins
+/// inside MPC, and the block settles directly into both margin accounts.
+/// The settlement updates the open interest, so it takes the protocol fee
+/// lock like any other settlement.
+pub fn submit_quote(
+    ctx: Context<SubmitQuote>,
+    computation_offset: u64,
+    ciphertext_price: [u8; 32],
+    pub_key: [u8; 32],
+    nonce: u128,
+) -> Result<()> {
+    let rfq = &ctx.accounts.rfq;
+    let current_slot = Clock::get()?.slot;
+    require!(rfq.status == RfqStatus::Open, ErrorCode::InvalidRfqStatus);
+    require!(!rfq.is_expired(current_slot), ErrorCode::RfqExpired);
+
+    let requester = &ctx.accounts.requester_margin;
+    let responder = &ctx.accounts.responder_margin;
+    let market = &ctx.accounts.market;
+    require!(
+        market.is_price_fresh(current_slot),
+        ErrorCode::StaleMarkPrice
+    );
+    require!(
+        requester.has_current_loss_index(market) && responder.has_current_loss_index(market),
+        ErrorCode::PendingSocializedLoss
+    );
+
+    // Enc<Mxe, RfqInput>, Enc<Shared, QuoteInput>, both Enc<Mxe, MarginBalances>,
+    // both Enc<Mxe, Position>, Enc<Mxe, OpenInterest>, the mark price and
+    // maintenance margin, then both state versions
+    let mut args = vec![
+        Argument::PlaintextU128(rfq.request_nonce),
+        Argument::EncryptedU64(rfq.encrypted_request[0]),
+        Argument::EncryptedU8(rfq.encrypted_request[1]),
+        Argument::EncryptedU64(rfq.encrypted_request[2]),
+        Argument::ArcisPubkey(pub_key),
+        Argument::PlaintextU128(nonce),
+        Argument::EncryptedU64(ciphertext_price),
+        Argument::PlaintextU128(requester.nonce),
+        Argument::EncryptedU64(requester.encrypted_collateral),
+        Argument::EncryptedU64(requester.encrypted_debt),
+        Argument::PlaintextU128(responder.nonce),
+        Argument::EncryptedU64(responder.encrypted_collateral),
+        Argument::EncryptedU64(responder.encrypted_debt),
+    ];
+    push_position_args(&mut args, requester);
+    push_position_args(&mut args, responder);
+    push_open_interest_args(&mut args, &ctx.accounts.protocol_fees);
+    args.push(Argument::PlaintextU64(market.mark_price));
+    args.push(Argument::PlaintextU64(market.maintenance_margin_bps as u64));
+    args.push(Argument::PlaintextU64(requester.state_version));
+    args.push(Argument::PlaintextU64(responder.state_version));
+
+    let rfq_key = ctx.accounts.rfq.key();
+    ctx.accounts.protocol_fees.acquire(rfq_key, current_slot)?;
+
+    let responder_margin = responder.key();
+    let rfq = &mut ctx.accounts.rfq;
+    rfq.responder_margin = responder_margin;
+    rfq.status = RfqStatus::Settling;
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![SettleBlockTradeCallback::callback_ix(&[
+            CallbackAccount {
+                pubkey: ctx.accounts.requester_margin.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.responder_margin.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.rfq.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.protocol_fees.key(),
+                is_writable: true,
+            },
+        ])],
+        1,
+    )?;
+
+    msg!("Block trade settlement queued to MPC cluster");
+    Ok(())
+}
+
+/// Callback after block trade settlement
+/// On success, writes the re-encrypted balances and positions of both
+/// counterparties and the re-encrypted open interest.
+/// Only the counterparties and the success bit are public.
+///
+/// If either account moved past the state the block was computed from, the
+/// result is dropped and the request reopens for another quote. If the
+/// protocol fee lock expired and was taken over meanwhile, the result is
+/// dropped and the request fails.
+#[arcium_callback(encrypted_ix = "settle_block_trade")]
+pub fn settle_block_trade_callback(
+    ctx: Context<SettleBlockTradeCallback>,
+    output: ComputationOutputs<SettleBlockTradeOutput>,
+) -> Result<()> {
+    let (
+        requester_balances,
+        responder_balances,
+        requester_position,
+        responder_position,
+        open_interest,
+        success,
+        requester_state_version,
+        responder_state_version,
+    ) = match output {
+        ComputationOutputs::Success(SettleBlockTradeOutput {
+            field_0:
+                SettleBlockTradeOutputStruct0 {
+                    field_0: requester_balances,
+                    field_1: responder_balances,
+                    field_2: requester_position,
+                    field_3: responder_position,
+                    field_4: open_interest,
+                    field_5: success,
+                    field_6: requester_state_version,
+                    field_7: responder_state_version,
+                },
+        }) => (
+            requester_balances,
+            responder_balances,
+            requester_position,
+            responder_position,
+            open_interest,
+            success,
+            requester_state_version,
+            responder_state_version,
+        ),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let rfq = &mut ctx.accounts.rfq;
+    require!(
+        rfq.status == RfqStatus::Settling,
+        ErrorCode::InvalidRfqStatus
+    );
+    let requester = &mut ctx.accounts.requester_margin;
+    let responder = &mut ctx.accounts.responder_margin;
+    let protocol_fees = &mut ctx.accounts.protocol_fees;
+    let holds_lock = protocol_fees.release(&rfq.key());
+
+    if requester_state_version != requester.state_version
+        || responder_state_version != responder.state_version
+    {
+        rfq.status = RfqStatus::Open;
+        msg!("Margin account changed while the block trade was in flight, result dropped");
+        return Ok(());
+    }
+
+    if !holds_lock {
+        rfq.status = RfqStatus::Failed;
+        msg!("Fee lock expired, block trade result dropped");
+        return Ok(());
+    }
+
+    rfq.status = if success {
+        RfqStatus::Settled
+    } else {
+        RfqStatus::Failed
+    };
+
+    // Nothing changes inside MPC on failure, so nothing is written back
+    if success {
+        requester.encrypted_collateral = requester_balances.ciphertexts[0];
+        requester.encrypted_debt = requester_balances.ciphertexts[1];
+        requester.nonce = requester_balances.nonce;
+        requester.encrypted_position = requester_position.ciphertexts;
+        requester.position_nonce = requester_position.nonce;
+        requester.mark_state_changed();
+
+        responder.encrypted_collateral = responder_balances.ciphertexts[0];
+        responder.encrypted_debt = responder_balances.ciphertexts[1];
+        responder.nonce = responder_balances.nonce;
+        responder.encrypted_position = responder_position.ciphertexts;
+        responder.position_nonce = responder_position.nonce;
+        responder.mark_state_changed();
+    }
+
+    if success {
+        protocol_fees.encrypted_open_interest = open_interest.ciphertexts;
+        protocol_fees.open_interest_nonce = open_interest.nonce;
+    }
+
+    emit!(BlockTradeSettled {
+        rfq: rfq.key(),
+        requester: requester.owner,
+        responder: responder.owner,
+        success,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Block trade settlement computation completed");
+    Ok(())
+}
+
+// ========== ACCOUNT STRUCTS ==========
+
+/// Initialize request_quote computation definition
+#[init_computation_definition_accounts("request_quote", payer)]
+#[derive(Accounts)]
+pub struct InitRequestQuoteCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Post a quote request
+#[queue_computation_accounts("request_quote", requester)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RequestQuote<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = requester,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REQUEST_QUOTE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Requester's margin account
+    #[account(
+        seeds = [MarginAccount::SEED_PREFIX, requester.key().as_ref()],
+        bump = requester_margin.bump,
+    )]
+    pub requester_margin: Account<'info, MarginAccount>,
+
+    /// Quote request account
+    #[account(
+        init,
+        payer = requester,
+        seeds = [
+            Rfq::SEED_PREFIX,
+            requester_margin.key().as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+        space = Rfq::SPACE,
+    )]
+    pub rfq: Account<'info, Rfq>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Request quote callback
+#[callback_accounts("request_quote")]
+#[derive(Accounts)]
+pub struct RequestQuoteCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REQUEST_QUOTE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Quote request being stored
+    #[account(mut)]
+    pub rfq: Account<'info, Rfq>,
+}
+
+/// Cancel a quote request
+#[derive(Accounts)]
+pub struct CancelQuoteRequest<'info> {
+    pub requester: Signer<'info>,
+
+    #[account(mut, has_one = requester @ ErrorCode::UnauthorizedRequester)]
+    pub rfq: Account<'info, Rfq>,
+}
+
+/// Initialize settle_block_trade computation definition
+#[init_computation_definition_accounts("settle_block_trade", payer)]
+#[derive(Accounts)]
+pub struct InitSettleBlockTradeCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Submit a quote and settle the block trade
+#[queue_computation_accounts("settle_block_trade", responder)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitQuote<'info> {
+    /// Counterparty named in the request
+    #[account(mut)]
+    pub responder: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = responder,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SETTLE_BLOCK_TRADE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Quote request being answered
+    #[account(
+        mut,
+        has_one = responder @ ErrorCode::UnauthorizedResponder,
+        has_one = requester_margin @ ErrorCode::RfqAccountMismatch,
+    )]
+    pub rfq: Box<Account<'info, Rfq>>,
+
+    /// Requester's margin account
+    #[account(
+        mut,
+        seeds = [MarginAccount::SEED_PREFIX, requester_margin.owner.as_ref()],
+        bump = requester_margin.bump
+    )]
+    pub requester_margin: Box<Account<'info, MarginAccount>>,
+
+    /// Responder's margin account
+    #[account(
+        mut,
+        seeds = [MarginAccount::SEED_PREFIX, responder.key().as_ref()],
+        bump = responder_margin.bump
+    )]
+    pub responder_margin: Box<Account<'info, MarginAccount>>,
+
+    /// Market providing the mark price, maintenance margin and loss indexes
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// Protocol fee account holding the encrypted open interest
+    #[account(
+        mut,
+        seeds = [ProtocolFees::SEED_PREFIX],
+        bump = protocol_fees.bump,
+    )]
+    pub protocol_fees: Box<Account<'info, ProtocolFees>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Settle block trade callback
+#[callback_accounts("settle_block_trade")]
+#[derive(Accounts)]
+pub struct SettleBlockTradeCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SETTLE_BLOCK_TRADE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Requester's margin account
+    #[account(mut)]
+    pub requester_margin: Account<'info, MarginAccount>,
+
+    /// Responder's margin account
+    #[account(mut)]
+    pub responder_margin: Account<'info, MarginAccount>,
+
+    /// Quote request being settled
+    #[account(mut)]
+    pub rfq: Account<'info, Rfq>,
+
+    /// Protocol fee account holding the encrypted open interest
+    #[account(mut)]
+    pub protocol_fees: Account<'info, ProtocolFees>,
+}
+
+// ========== EVENTS ==========
+
+/// Emitted when a quote request is stored. Size, side and limit stay encrypted.
+#[event]
+pub struct QuoteRequested {
+    pub rfq: Pubkey,
+    pub requester: Pubkey,
+    pub responder: Pubkey,
+    pub expiry_slot: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a block trade settles. Price and size stay encrypted.
+#[event]
+pub struct BlockTradeSettled {
+    pub rfq: Pubkey,
+    pub requester: Pubkey,
+    pub responder: Pubkey,
+    pub success: bool,
+    pub timestamp: i64,
+}
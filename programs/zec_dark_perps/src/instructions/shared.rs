@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+use crate::state::order::Order;
+use crate::ID;
+
+// Helper function expected by arcium_callback macro
+// This validates that callback instructions are properly formatted
+pub(crate) fn validate_callback_ixs(_account_info: &AccountInfo, _pubkey: &Pubkey) -> Result<()> {
+    // Validation is handled by Arcium program
+    Ok(())
+}
+
+/// Deserialize a program account passed as a remaining account
+pub(crate) fn load_account<T: AccountDeserialize>(info: &AccountInfo) -> Result<T> {
+    require_keys_eq!(*info.owner, ID, ErrorCode::InvalidBatchAccount);
+    let data = info.try_borrow_data()?;
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Serialize a program account back into a remaining account
+pub(crate) fn store_account<T: AccountSerialize>(info: &AccountInfo, account: &T) -> Result<()> {
+    let mut data = info.try_borrow_mut_data()?;
+    account.try_serialize(&mut &mut data[..])
+}
+
+/// Whether a remaining account holds an account of the type with this discriminator
+pub(crate) fn has_discriminator(info: &AccountInfo, discriminator: &[u8]) -> bool {
+    info.try_borrow_data()
+        .map(|data| data.starts_with(discriminator))
+        .unwrap_or(false)
+}
+
+/// Deserialize an order passed as a remaining account
+pub(crate) fn load_order(info: &AccountInfo) -> Result<Order> {
+    require_keys_eq!(*info.owner, ID, ErrorCode::InvalidOrderAccount);
+    load_account(info)
+}
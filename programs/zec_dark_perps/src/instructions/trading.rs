@@ -2,13 +2,19 @@ use anchor_lang::prelude::*;
 
 use crate::error::ErrorCode;
 use crate::state::margin_account::MarginAccount;
+use crate::state::order::{Order, OrderStatus};
+use crate::state::program_config::ProgramConfig;
 
 /// Settle a matched trade between buyer and seller.
-/// Called by the off-chain matching engine after finding a match.
+/// Called by an authorized matching engine after finding a match.
 ///
-/// **PRIVACY:** Balance updates happen via Arcium MPC (see margin_arcium::queue_settle_trade)
+/// The trade must reference one live order from each side, both created and
+/// signed by the respective margin account owners (see `submit_order`).
+///
+/// **PRIVACY:** Price and size passed here are public and emitted in `TradeExecuted`.
+/// For private settlement use matching::queue_match_orders followed by
+/// margin_arcium::queue_settle_trade, which keep the fill encrypted end to end.
 /// This function only validates and emits the trade event.
-/// The actual encrypted balance settlement is done separately via MPC.
 ///
 /// For MVP:
 /// - Price and size are in fixed-point (multiply by 1e6)
@@ -20,6 +26,17 @@ pub fn settle_trade(
 ) -> Result<()> {
     require!(price > 0, ErrorCode::InvalidAmount);
     require!(size > 0, ErrorCode::InvalidAmount);
+    validate_order_pair(
+        &ctx.accounts.buy_order,
+        &ctx.accounts.buyer_margin,
+        &ctx.accounts.sell_order,
+        &ctx.accounts.seller_margin,
+    )?;
+    require_keys_neq!(
+        ctx.accounts.buyer_margin.key(),
+        ctx.accounts.seller_margin.key(),
+        ErrorCode::SelfTrade
+    );
 
     // Calculate trade value: (price * size) / 1e6
     // Using u128 to prevent overflow
@@ -29,20 +46,52 @@ pub fn settle_trade(
         .checked_div(1_000_000)
         .ok_or(ErrorCode::MathOverflow)? as u64;
 
-    // NOTE: Encrypted balance updates must be done via margin_arcium::queue_settle_trade
-    // This keeps trade validation and encrypted accounting as separate steps
+    // NOTE: This path does not touch encrypted balances
+    // Encrypted settlement goes through match_orders + queue_settle_trade
 
     // Emit event for off-chain tracking
     emit!(TradeExecuted {
+        matcher: ctx.accounts.authority.key(),
         buyer: ctx.accounts.buyer_margin.owner,
         seller: ctx.accounts.seller_margin.owner,
+        buy_order: ctx.accounts.buy_order.key(),
+        sell_order: ctx.accounts.sell_order.key(),
         price,
         size,
         trade_value,
         timestamp: Clock::get()?.unix_timestamp,
     });
 
-    msg!("Trade validated. Call queue_settle_trade to update encrypted balances.");
+    msg!("Trade validated and reported.");
+    Ok(())
+}
+
+/// Check that a settlement is bound to one live order per counterparty.
+///
+/// Both orders may belong to the same margin account: private matching
+/// resolves that case with self-trade prevention inside MPC.
+///
+/// Orders can only be created by the margin account owner signing
+/// `submit_order` (or `place_trigger_order`), so a matching order proves
+/// the trader authorized trading on that account.
+pub fn validate_order_pair(
+    buy_order: &Order,
+    buyer_margin: &Account<MarginAccount>,
+    sell_order: &Order,
+    seller_margin: &Account<MarginAccount>,
+) -> Result<()> {
+    for (order, margin) in [(buy_order, buyer_margin), (sell_order, seller_margin)] {
+        require_keys_eq!(
+            order.margin_account,
+            margin.key(),
+            ErrorCode::OrderAccountMismatch
+        );
+        require_keys_eq!(order.owner, margin.owner, ErrorCode::OrderAccountMismatch);
+        require!(
+            order.status == OrderStatus::Open,
+            ErrorCode::InvalidOrderStatus
+        );
+    }
     Ok(())
 }
 
@@ -52,6 +101,14 @@ pub struct SettleTrade<'info> {
     /// The matching engine authority (off-chain service)
     pub authority: Signer<'info>,
 
+    /// Program config holding the authorized matchers
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+        constraint = config.is_matcher(&authority.key()) @ ErrorCode::UnauthorizedMatcher,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
     /// Buyer's margin account
     #[account(
         mut,
@@ -67,13 +124,22 @@ pub struct SettleTrade<'info> {
         bump = seller_margin.bump
     )]
     pub seller_margin: Account<'info, MarginAccount>,
+
+    /// Buyer's live order being filled
+    pub buy_order: Account<'info, Order>,
+
+    /// Seller's live order being filled
+    pub sell_order: Account<'info, Order>,
 }
 
 /// Event emitted when a trade is executed
 #[event]
 pub struct TradeExecuted {
+    pub matcher: Pubkey,
     pub buyer: Pubkey,
     pub seller: Pubkey,
+    pub buy_order: Pubkey,
+    pub sell_order: Pubkey,
     pub price: u64,
     pub size: u64,
     pub trade_value: u64,
@@ -1,7 +1,16 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program::{self, Transfer};
 use arcium_anchor::prelude::*;
-use arcium_client::idl::arcium::types::{CircuitSource, OffChainCircuitSource};
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
 
+use crate::error::ErrorCode;
+use crate::instructions::shared::validate_callback_ixs;
+use crate::state::margin_account::MarginAccount;
+use crate::state::market::Market;
+use crate::state::order::{Order, OrderKind, OrderRejectReason, OrderStatus};
+use crate::state::order_commitment::OrderCommitment;
+use crate::state::program_config::ProgramConfig;
 use crate::state::SignerAccount;
 use crate::ID;
 use crate::ID as ID_CONST;
@@ -10,13 +19,6 @@ use crate::ID as ID_CONST;
 // This is calculated from the instruction name using comp_def_offset()
 const COMP_DEF_OFFSET_SUBMIT_ORDER: u32 = comp_def_offset("submit_order");
 
-// Helper function expected by arcium_callback macro
-// This validates that callback instructions are properly formatted
-fn validate_callback_ixs(_account_info: &AccountInfo, _pubkey: &Pubkey) -> Result<()> {
-    // Validation is handled by Arcium program
-    Ok(())
-}
-
 /// Initialize the computation definition for order submission
 /// This registers the Arcium MPC computation on-chain
 /// Only needs to be called once after program deployment
@@ -37,24 +39,69 @@ pub fn init_submit_order_comp_def(ctx: Context<InitSubmitOrderCompDef>) -> Resul
 /// Submit an encrypted order to the dark pool
 /// Orders are encrypted client-side and processed in MPC
 /// This ensures privacy - no one can see order details until matched
+///
+/// The order account is created here, signed by the margin account owner,
+/// so settlements can later be bound to orders both traders authorized
+///
+/// Iceberg orders pass a non-zero encrypted display size: only that slice
+/// is matched at a time, and it is refreshed inside MPC after each fill
+///
+/// The encrypted sequence number must equal the margin account's next
+/// sequence, so resubmitting a captured ciphertext is rejected by MPC
+///
+/// The encrypted self-trade prevention mode decides what happens when the
+/// order would cross another order from the same margin account
+///
+/// With `is_market` set, the encrypted price is the worst acceptable price:
+/// the order only takes liquidity and its remainder is never rested
+#[allow(clippy::too_many_arguments)]
 pub fn submit_order(
     ctx: Context<SubmitOrder>,
     computation_offset: u64,
+    is_market: bool,
     ciphertext_size: [u8; 32],
     ciphertext_price: [u8; 32],
     ciphertext_side: [u8; 32],
+    ciphertext_display_size: [u8; 32],
+    ciphertext_sequence: [u8; 32],
+    ciphertext_stp_mode: [u8; 32],
     pub_key: [u8; 32],
     nonce: u128,
 ) -> Result<()> {
-    // Build arguments for encrypted instruction
-    // Order: ArcisPubkey, nonce, then encrypted fields
-    let args = vec![
-        Argument::ArcisPubkey(pub_key),
-        Argument::PlaintextU128(nonce),
-        Argument::EncryptedU64(ciphertext_size),
-        Argument::EncryptedU64(ciphertext_price),
-        Argument::EncryptedU8(ciphertext_side),
-    ];
+    let market = &ctx.accounts.market;
+    require!(
+        market.is_price_fresh(Clock::get()?.slot),
+        ErrorCode::StaleMarkPrice
+    );
+
+    let owner = ctx.accounts.owner.key();
+    let margin_account = ctx.accounts.margin_account.key();
+    let sequence = ctx.accounts.margin_account.next_order_sequence()?;
+    ctx.accounts.order.initialize(
+        owner,
+        margin_account,
+        computation_offset,
+        sequence,
+        order_kind(is_market),
+        ctx.bumps.order,
+    )?;
+
+    let args = submit_order_args(
+        market,
+        &margin_account,
+        sequence,
+        is_market,
+        &[
+            ciphertext_size,
+            ciphertext_price,
+            ciphertext_side,
+            ciphertext_display_size,
+            ciphertext_sequence,
+            ciphertext_stp_mode,
+        ],
+        pub_key,
+        nonce,
+    );
 
     // Set the bump for the sign_pda_account
     ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
@@ -65,7 +112,10 @@ pub fn submit_order(
         computation_offset,
         args,
         None, // No callback server needed (output fits in transaction)
-        vec![SubmitOrderCallback::callback_ix(&[])],
+        vec![SubmitOrderCallback::callback_ix(&[CallbackAccount {
+            pubkey: ctx.accounts.order.key(),
+            is_writable: true,
+        }])],
         1, // One transaction for callback
     )?;
 
@@ -73,21 +123,250 @@ pub fn submit_order(
     Ok(())
 }
 
+/// Commit to an encrypted order without revealing it (commit-reveal mode)
+/// `commitment` is `order_commitment_hash` of the ciphertexts that will be
+/// passed to `reveal_order` in a later slot. The order sequence is reserved
+/// now, so the encrypted sequence must be the margin account's next one,
+/// and the commitment bond is locked until the reveal.
+pub fn commit_order(
+    ctx: Context<CommitOrder>,
+    _commitment_id: u64,
+    commitment: [u8; 32],
+) -> Result<()> {
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.system_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.owner.to_account_info(),
+            to: ctx.accounts.order_commitment.to_account_info(),
+        },
+    );
+    system_program::transfer(cpi_ctx, OrderCommitment::COMMITMENT_BOND_LAMPORTS)?;
+
+    let sequence = ctx.accounts.margin_account.next_order_sequence()?;
+    let order_commitment = &mut ctx.accounts.order_commitment;
+    order_commitment.owner = ctx.accounts.owner.key();
+    order_commitment.margin_account = ctx.accounts.margin_account.key();
+    order_commitment.commitment = commitment;
+    order_commitment.committed_slot = Clock::get()?.slot;
+    order_commitment.sequence = sequence;
+    order_commitment.bump = ctx.bumps.order_commitment;
+
+    emit!(OrderCommittedEvent {
+        order_commitment: order_commitment.key(),
+        owner: order_commitment.owner,
+        committed_slot: order_commitment.committed_slot,
+    });
+
+    msg!("Order commitment posted");
+    Ok(())
+}
+
+/// Reveal a committed order and submit it to the MPC cluster
+/// Runs the same `submit_order` circuit with the sequence reserved at
+/// commit. The order takes the commitment slot as its priority, so matching
+/// orders by slot follows commitment order; a market order's matching window
+/// still starts at the reveal. Revealing refunds the commitment bond.
+#[allow(clippy::too_many_arguments)]
+pub fn reveal_order(
+    ctx: Context<RevealOrder>,
+    computation_offset: u64,
+    is_market: bool,
+    ciphertext_size: [u8; 32],
+    ciphertext_price: [u8; 32],
+    ciphertext_side: [u8; 32],
+    ciphertext_display_size: [u8; 32],
+    ciphertext_sequence: [u8; 32],
+    ciphertext_stp_mode: [u8; 32],
+    pub_key: [u8; 32],
+    nonce: u128,
+) -> Result<()> {
+    let current_slot = Clock::get()?.slot;
+    let market = &ctx.accounts.market;
+    require!(
+        market.is_price_fresh(current_slot),
+        ErrorCode::StaleMarkPrice
+    );
+
+    let ciphertexts = [
+        ciphertext_size,
+        ciphertext_price,
+        ciphertext_side,
+        ciphertext_display_size,
+        ciphertext_sequence,
+        ciphertext_stp_mode,
+    ];
+    let order_commitment = &ctx.accounts.order_commitment;
+    order_commitment.check_reveal_window(current_slot)?;
+    require!(
+        order_commitment_hash(is_market, &ciphertexts, &pub_key, nonce)
+            == order_commitment.commitment,
+        ErrorCode::InvalidCommitment
+    );
+    let committed_slot = order_commitment.committed_slot;
+    let sequence = order_commitment.sequence;
+
+    let owner = ctx.accounts.owner.key();
+    let margin_account = ctx.accounts.margin_account.key();
+    let order = &mut ctx.accounts.order;
+    order.initialize(
+        owner,
+        margin_account,
+        computation_offset,
+        sequence,
+        order_kind(is_market),
+        ctx.bumps.order,
+    )?;
+    order.created_slot = committed_slot;
+
+    let args = submit_order_args(
+        market,
+        &margin_account,
+        sequence,
+        is_market,
+        &ciphertexts,
+        pub_key,
+        nonce,
+    );
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![SubmitOrderCallback::callback_ix(&[CallbackAccount {
+            pubkey: ctx.accounts.order.key(),
+            is_writable: true,
+        }])],
+        1,
+    )?;
+
+    msg!("Committed order revealed and submitted to MPC cluster");
+    Ok(())
+}
+
+/// Withdraw an order commitment that will not be revealed
+/// The owner can withdraw it at any time; once the reveal window has closed
+/// anyone can. Either way the bond and rent go to the config authority.
+pub fn cancel_order_commitment(ctx: Context<CancelOrderCommitment>) -> Result<()> {
+    let order_commitment = &ctx.accounts.order_commitment;
+    require!(
+        ctx.accounts.signer.key() == order_commitment.owner
+            || order_commitment.is_expired(Clock::get()?.slot),
+        ErrorCode::CommitmentStillRevealable
+    );
+
+    msg!("Order commitment cancelled, bond forfeited");
+    Ok(())
+}
+
+/// Hash committed to by `commit_order`: the order type flag, the order
+/// ciphertexts in circuit field order, the encryption pubkey and the nonce
+pub fn order_commitment_hash(
+    is_market: bool,
+    ciphertexts: &[[u8; 32]; 6],
+    pub_key: &[u8; 32],
+    nonce: u128,
+) -> [u8; 32] {
+    let flag = [is_market as u8];
+    let nonce_bytes = nonce.to_le_bytes();
+    let mut parts: Vec<&[u8]> = Vec::with_capacity(ciphertexts.len() + 3);
+    parts.push(&flag);
+    parts.extend(ciphertexts.iter().map(|c| c.as_slice()));
+    parts.push(pub_key);
+    parts.push(&nonce_bytes);
+    hashv(&parts).to_bytes()
+}
+
+fn order_kind(is_market: bool) -> OrderKind {
+    if is_market {
+        OrderKind::Market
+    } else {
+        OrderKind::Limit
+    }
+}
+
+/// Build arguments for the submit_order circuit
+/// Order: ArcisPubkey, nonce, then encrypted fields (size, price, side,
+/// display_size, sequence, stp_mode), then public market params, then the
+/// expected sequence number, the owner id used for self-trade prevention
+/// and the order type
+fn submit_order_args(
+    market: &Market,
+    margin_account: &Pubkey,
+    sequence: u64,
+    is_market: bool,
+    ciphertexts: &[[u8; 32]; 6],
+    pub_key: [u8; 32],
+    nonce: u128,
+) -> Vec<Argument> {
+    vec![
+        Argument::ArcisPubkey(pub_key),
+        Argument::PlaintextU128(nonce),
+        Argument::EncryptedU64(ciphertexts[0]),
+        Argument::EncryptedU64(ciphertexts[1]),
+        Argument::EncryptedU8(ciphertexts[2]),
+        Argument::EncryptedU64(ciphertexts[3]),
+        Argument::EncryptedU64(ciphertexts[4]),
+        Argument::EncryptedU8(ciphertexts[5]),
+        Argument::PlaintextU64(market.mark_price),
+        Argument::PlaintextU64(market.tick_size),
+        Argument::PlaintextU64(market.lot_size),
+        Argument::PlaintextU64(market.price_band_bps as u64),
+        Argument::PlaintextU64(sequence),
+        Argument::PlaintextU128(Order::stp_owner_id(margin_account)),
+        Argument::PlaintextBool(is_market),
+    ]
+}
+
 /// Callback function called by Arcium MPC cluster after order processing
-/// Receives the encrypted order confirmation
+/// Receives the encrypted order confirmation and the revealed validation status
+/// Accepted orders are stored MXE-encrypted on the order account and become live
 #[arcium_callback(encrypted_ix = "submit_order")]
 pub fn submit_order_callback(
     ctx: Context<SubmitOrderCallback>,
     output: ComputationOutputs<SubmitOrderOutput>,
 ) -> Result<()> {
-    let encrypted_order = match output {
-        ComputationOutputs::Success(SubmitOrderOutput { field_0 }) => field_0,
-        _ => return Err(crate::error::ErrorCode::AbortedComputation.into()),
+    let (encrypted_order, stored_order, status) = match output {
+        ComputationOutputs::Success(SubmitOrderOutput {
+            field_0:
+                SubmitOrderOutputStruct0 {
+                    field_0: encrypted_order,
+                    field_1: stored_order,
+                    field_2: status,
+                },
+        }) => (encrypted_order, stored_order, status),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
     };
 
+    let order = &mut ctx.accounts.order;
+    require!(
+        order.status == OrderStatus::Pending,
+        ErrorCode::InvalidOrderStatus
+    );
+
+    // Order failed validation inside MPC: only the reason is made public
+    if let Some(reason) = OrderRejectReason::from_status(status) {
+        order.status = OrderStatus::Rejected;
+        emit!(OrderRejectedEvent {
+            order: order.key(),
+            reason,
+            nonce: encrypted_order.nonce.to_le_bytes(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        msg!("Order rejected by MPC validation: {:?}", reason);
+        return Ok(());
+    }
+
+    order.encrypted_order = stored_order.ciphertexts;
+    order.order_nonce = stored_order.nonce;
+    order.status = OrderStatus::Open;
+
     // Emit event with encrypted order confirmation
     // Off-chain matching engine can process this
     emit!(OrderSubmittedEvent {
+        order: order.key(),
         encrypted_data: encrypted_order.ciphertexts[0],
         nonce: encrypted_order.nonce.to_le_bytes(),
         timestamp: Clock::get()?.unix_timestamp,
@@ -119,17 +398,18 @@ pub struct InitSubmitOrderCompDef<'info> {
 }
 
 /// Accounts for submitting an encrypted order
-#[queue_computation_accounts("submit_order", payer)]
+#[queue_computation_accounts("submit_order", owner)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64)]
 pub struct SubmitOrder<'info> {
+    /// Margin account owner placing (and signing) the order
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub owner: Signer<'info>,
 
     #[account(
         init_if_needed,
         space = 9,
-        payer = payer,
+        payer = owner,
         seeds = [&SIGN_PDA_SEED],
         bump,
         address = derive_sign_pda!(),
@@ -166,10 +446,182 @@ pub struct SubmitOrder<'info> {
     #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
     pub clock_account: Account<'info, ClockAccount>,
 
+    /// Market providing mark price, tick size, lot size and price band
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Margin account the order trades against; its order sequence is advanced
+    #[account(
+        mut,
+        seeds = [MarginAccount::SEED_PREFIX, owner.key().as_ref()],
+        bump = margin_account.bump,
+    )]
+    pub margin_account: Account<'info, MarginAccount>,
+
+    /// Order account holding the MXE-encrypted order
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            Order::SEED_PREFIX,
+            margin_account.key().as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+        space = Order::SPACE,
+    )]
+    pub order: Account<'info, Order>,
+
     pub system_program: Program<'info, System>,
     pub arcium_program: Program<'info, Arcium>,
 }
 
+/// Accounts for posting an order commitment
+#[derive(Accounts)]
+#[instruction(commitment_id: u64)]
+pub struct CommitOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Margin account the committed order will trade against; its order
+    /// sequence is advanced
+    #[account(
+        mut,
+        seeds = [MarginAccount::SEED_PREFIX, owner.key().as_ref()],
+        bump = margin_account.bump,
+    )]
+    pub margin_account: Account<'info, MarginAccount>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            OrderCommitment::SEED_PREFIX,
+            margin_account.key().as_ref(),
+            &commitment_id.to_le_bytes(),
+        ],
+        bump,
+        space = OrderCommitment::SPACE,
+    )]
+    pub order_commitment: Account<'info, OrderCommitment>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for revealing a committed order
+#[queue_computation_accounts("submit_order", owner)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RevealOrder<'info> {
+    /// Margin account owner who posted the commitment
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = owner,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Market providing mark price, tick size, lot size and price band
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// Margin account the order trades against; its sequence was reserved at commit
+    #[account(
+        seeds = [MarginAccount::SEED_PREFIX, owner.key().as_ref()],
+        bump = margin_account.bump,
+    )]
+    pub margin_account: Box<Account<'info, MarginAccount>>,
+
+    /// Commitment being revealed, closed back to the owner with its bond
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ErrorCode::InvalidCommitment,
+        has_one = margin_account @ ErrorCode::InvalidCommitment,
+    )]
+    pub order_commitment: Box<Account<'info, OrderCommitment>>,
+
+    /// Order account holding the MXE-encrypted order
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            Order::SEED_PREFIX,
+            margin_account.key().as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+        space = Order::SPACE,
+    )]
+    pub order: Box<Account<'info, Order>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Accounts for withdrawing an order commitment
+#[derive(Accounts)]
+pub struct CancelOrderCommitment<'info> {
+    /// The commitment owner, or anyone once the reveal window has closed
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [ProgramConfig::SEED_PREFIX],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    /// Config authority receiving the forfeited bond and rent
+    #[account(mut, address = config.authority @ ErrorCode::UnauthorizedConfigAuthority)]
+    /// CHECK: only receives lamports, address checked against the config
+    pub authority: UncheckedAccount<'info>,
+
+    #[account(mut, close = authority)]
+    pub order_commitment: Account<'info, OrderCommitment>,
+}
+
 /// Accounts for order submission callback
 #[callback_accounts("submit_order")]
 #[derive(Accounts)]
@@ -182,12 +634,80 @@ pub struct SubmitOrderCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Order being confirmed
+    #[account(mut)]
+    pub order: Account<'info, Order>,
 }
 
 /// Event emitted when an order is submitted and encrypted
 #[event]
 pub struct OrderSubmittedEvent {
+    pub order: Pubkey,
     pub encrypted_data: [u8; 32],
     pub nonce: [u8; 16],
     pub timestamp: i64,
 }
+
+/// Event emitted when an order fails validation inside MPC
+#[event]
+pub struct OrderRejectedEvent {
+    pub order: Pubkey,
+    pub reason: OrderRejectReason,
+    pub nonce: [u8; 16],
+    pub timestamp: i64,
+}
+
+/// Event emitted when an order commitment is posted; the order itself stays hidden
+#[event]
+pub struct OrderCommittedEvent {
+    pub order_commitment: Pubkey,
+    pub owner: Pubkey,
+    pub committed_slot: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ciphertexts() -> [[u8; 32]; 6] {
+        let mut ciphertexts = [[0u8; 32]; 6];
+        for (i, ciphertext) in ciphertexts.iter_mut().enumerate() {
+            *ciphertext = [i as u8 + 1; 32];
+        }
+        ciphertexts
+    }
+
+    #[test]
+    fn commitment_hash_is_deterministic() {
+        let ciphertexts = ciphertexts();
+        assert_eq!(
+            order_commitment_hash(false, &ciphertexts, &[7; 32], 42),
+            order_commitment_hash(false, &ciphertexts, &[7; 32], 42)
+        );
+    }
+
+    #[test]
+    fn commitment_hash_binds_every_input() {
+        let ciphertexts = ciphertexts();
+        let hash = order_commitment_hash(false, &ciphertexts, &[7; 32], 42);
+
+        assert_ne!(
+            order_commitment_hash(true, &ciphertexts, &[7; 32], 42),
+            hash
+        );
+        assert_ne!(
+            order_commitment_hash(false, &ciphertexts, &[8; 32], 42),
+            hash
+        );
+        assert_ne!(
+            order_commitment_hash(false, &ciphertexts, &[7; 32], 43),
+            hash
+        );
+        for i in 0..ciphertexts.len() {
+            let mut changed = ciphertexts;
+            changed[i][0] ^= 1;
+            assert_ne!(order_commitment_hash(false, &changed, &[7; 32], 42), hash);
+        }
+    }
+}
@@ -0,0 +1,563 @@
+use anchor_lang::prelude::*;
+use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
+
+use crate::error::ErrorCode;
+use crate::instructions::order_groups::{
+    oco_batch_len, oco_leg_allowed, push_oco_accounts, resolve_oco_group,
+};
+use crate::instructions::shared::{load_order, store_account, validate_callback_ixs};
+use crate::instructions::trading_arcium::OrderRejectedEvent;
+use crate::state::margin_account::MarginAccount;
+use crate::state::market::Market;
+use crate::state::order::{Order, OrderKind, OrderRejectReason, OrderStatus};
+use crate::state::SignerAccount;
+use crate::ID;
+use crate::ID as ID_CONST;
+
+// Computation definition offsets for trigger order encrypted instructions
+const COMP_DEF_OFFSET_PLACE_TRIGGER_ORDER: u32 = comp_def_offset("place_trigger_order");
+const COMP_DEF_OFFSET_EVALUATE_TRIGGERS: u32 = comp_def_offset("evaluate_triggers");
+
+/// Number of trigger orders evaluated per crank (must match the circuit)
+pub const TRIGGER_BATCH_SIZE: usize = 8;
+
+// ========== PLACE TRIGGER ORDER ==========
+
+/// Initialize computation definition for placing trigger orders
+pub fn init_place_trigger_order_comp_def(ctx: Context<InitPlaceTriggerOrderCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/place_trigger_order.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Place trigger order computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Place an encrypted stop-loss / take-profit order
+/// The trigger price and direction are encrypted client-side and
+/// re-encrypted for the MXE, so no one can see where the stop sits
+///
+/// Like limit orders, the ciphertext carries the margin account's next
+/// sequence number so it cannot be replayed
+#[allow(clippy::too_many_arguments)]
+pub fn place_trigger_order(
+    ctx: Context<PlaceTriggerOrder>,
+    computation_offset: u64,
+    ciphertext_size: [u8; 32],
+    ciphertext_price: [u8; 32],
+    ciphertext_side: [u8; 32],
+    ciphertext_trigger_price: [u8; 32],
+    ciphertext_direction: [u8; 32],
+    ciphertext_sequence: [u8; 32],
+    pub_key: [u8; 32],
+    nonce: u128,
+) -> Result<()> {
+    let owner = ctx.accounts.owner.key();
+    let margin_account = ctx.accounts.margin_account.key();
+    let sequence = ctx.accounts.margin_account.next_order_sequence()?;
+    ctx.accounts.order.initialize(
+        owner,
+        margin_account,
+        computation_offset,
+        sequence,
+        OrderKind::Trigger,
+        ctx.bumps.order,
+    )?;
+
+    // Enc<Shared, TriggerOrderInput>: pubkey, nonce, then struct fields in order,
+    // then the expected sequence number and the owner id used for self-trade prevention
+    let args = vec![
+        Argument::ArcisPubkey(pub_key),
+        Argument::PlaintextU128(nonce),
+        Argument::EncryptedU64(ciphertext_size),
+        Argument::EncryptedU64(ciphertext_price),
+        Argument::EncryptedU8(ciphertext_side),
+        Argument::EncryptedU64(ciphertext_trigger_price),
+        Argument::EncryptedU8(ciphertext_direction),
+        Argument::EncryptedU64(ciphertext_sequence),
+        Argument::PlaintextU64(sequence),
+        Argument::PlaintextU128(Order::stp_owner_id(&margin_account)),
+    ];
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![PlaceTriggerOrderCallback::callback_ix(&[CallbackAccount {
+            pubkey: ctx.accounts.order.key(),
+            is_writable: true,
+        }])],
+        1,
+    )?;
+
+    msg!("Trigger order queued to MPC cluster");
+    Ok(())
+}
+
+/// Callback after trigger order placement
+/// Stores the MXE-encrypted order and trigger on the order account,
+/// or rejects the order if its sequence number was stale
+#[arcium_callback(encrypted_ix = "place_trigger_order")]
+pub fn place_trigger_order_callback(
+    ctx: Context<PlaceTriggerOrderCallback>,
+    output: ComputationOutputs<PlaceTriggerOrderOutput>,
+) -> Result<()> {
+    let (encrypted_order, encrypted_trigger, sequence_ok) = match output {
+        ComputationOutputs::Success(PlaceTriggerOrderOutput {
+            field_0:
+                PlaceTriggerOrderOutputStruct0 {
+                    field_0: encrypted_order,
+                    field_1: encrypted_trigger,
+                    field_2: sequence_ok,
+                },
+        }) => (encrypted_order, encrypted_trigger, sequence_ok),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let order = &mut ctx.accounts.order;
+    require!(
+        order.status == OrderStatus::Pending,
+        ErrorCode::InvalidOrderStatus
+    );
+
+    if !sequence_ok {
+        order.status = OrderStatus::Rejected;
+        emit!(OrderRejectedEvent {
+            order: order.key(),
+            reason: OrderRejectReason::StaleSequence,
+            nonce: encrypted_order.nonce.to_le_bytes(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        msg!("Trigger order rejected: stale sequence number");
+        return Ok(());
+    }
+
+    order.encrypted_order = encrypted_order.ciphertexts;
+    order.order_nonce = encrypted_order.nonce;
+    order.encrypted_trigger = encrypted_trigger.ciphertexts;
+    order.trigger_nonce = encrypted_trigger.nonce;
+    order.status = OrderStatus::Untriggered;
+
+    emit!(TriggerOrderPlaced {
+        order: order.key(),
+        owner: order.owner,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Trigger order stored encrypted on-chain");
+    Ok(())
+}
+
+// ========== CANCEL TRIGGER ORDER ==========
+
+/// Cancel a trigger order that has not fired yet
+/// A crank already in flight for it skips the order when its callback lands.
+/// If the order is an OCO leg, the group stays active and its other leg
+/// keeps trading on its own.
+pub fn cancel_trigger_order(ctx: Context<CancelTriggerOrder>) -> Result<()> {
+    let order = &mut ctx.accounts.order;
+    require!(
+        order.kind == OrderKind::Trigger && order.status == OrderStatus::Untriggered,
+        ErrorCode::InvalidOrderStatus
+    );
+    order.status = OrderStatus::Cancelled;
+
+    emit!(TriggerOrderCancelled {
+        order: order.key(),
+        owner: order.owner,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Trigger order cancelled");
+    Ok(())
+}
+
+// ========== EVALUATE TRIGGERS (CRANK) ==========
+
+/// Initialize computation definition for trigger evaluation
+pub fn init_evaluate_triggers_comp_def(ctx: Context<InitEvaluateTriggersCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/evaluate_triggers.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Evaluate triggers computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Permissionless crank: evaluate up to TRIGGER_BATCH_SIZE trigger orders
+/// against the current mark price.
+///
+/// Untriggered order accounts are passed as remaining accounts, followed by
+/// the OCO group and sibling accounts of any grouped orders.
+/// The MPC cluster reveals only which of them fired.
+pub fn crank_triggers(ctx: Context<CrankTriggers>, computation_offset: u64) -> Result<()> {
+    let accounts = ctx.remaining_accounts;
+    let orders = &accounts[..oco_batch_len(accounts)];
+    require!(
+        !orders.is_empty() && orders.len() <= TRIGGER_BATCH_SIZE,
+        ErrorCode::InvalidBatchSize
+    );
+
+    let market = &ctx.accounts.market;
+    require!(
+        market.is_price_fresh(Clock::get()?.slot),
+        ErrorCode::StaleMarkPrice
+    );
+
+    // [Enc<Mxe, TriggerData>; TRIGGER_BATCH_SIZE]: nonce + fields for each slot,
+    // unused slots are padded and masked out by `active_mask`
+    let mut args = Vec::with_capacity(TRIGGER_BATCH_SIZE * 3 + 2);
+    let mut callback_accounts = Vec::with_capacity(accounts.len());
+    let mut active_mask: u8 = 0;
+
+    for slot in 0..TRIGGER_BATCH_SIZE {
+        match orders.get(slot) {
+            Some(info) => {
+                let order = load_order(info)?;
+                require!(
+                    order.kind == OrderKind::Trigger && order.status == OrderStatus::Untriggered,
+                    ErrorCode::InvalidOrderStatus
+                );
+
+                args.push(Argument::PlaintextU128(order.trigger_nonce));
+                args.push(Argument::EncryptedU64(order.encrypted_trigger[0]));
+                args.push(Argument::EncryptedU8(order.encrypted_trigger[1]));
+
+                callback_accounts.push(CallbackAccount {
+                    pubkey: info.key(),
+                    is_writable: true,
+                });
+                active_mask |= 1 << slot;
+            }
+            None => {
+                args.push(Argument::PlaintextU128(0));
+                args.push(Argument::EncryptedU64([0u8; 32]));
+                args.push(Argument::EncryptedU8([0u8; 32]));
+            }
+        }
+    }
+    args.push(Argument::PlaintextU8(active_mask));
+    args.push(Argument::PlaintextU64(market.mark_price));
+
+    // Group and sibling accounts go after the batch so the callback can
+    // cancel the other leg when a grouped order fires
+    for info in orders {
+        let order = load_order(info)?;
+        push_oco_accounts(info.key(), &order, accounts, &mut callback_accounts)?;
+    }
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![EvaluateTriggersCallback::callback_ix(&callback_accounts)],
+        1,
+    )?;
+
+    msg!("Trigger evaluation queued for {} orders", orders.len());
+    Ok(())
+}
+
+/// Callback after trigger evaluation
+/// Orders whose bit is set in the revealed mask become live (Open).
+/// A fired OCO leg cancels its sibling.
+#[arcium_callback(encrypted_ix = "evaluate_triggers")]
+pub fn evaluate_triggers_callback(
+    ctx: Context<EvaluateTriggersCallback>,
+    output: ComputationOutputs<EvaluateTriggersOutput>,
+) -> Result<()> {
+    let fired_mask = match output {
+        ComputationOutputs::Success(EvaluateTriggersOutput { field_0 }) => field_0,
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    let accounts = ctx.remaining_accounts;
+    let orders = &accounts[..oco_batch_len(accounts)];
+
+    for (slot, info) in orders.iter().enumerate() {
+        if fired_mask & (1 << slot) == 0 {
+            continue;
+        }
+
+        let mut order = load_order(info)?;
+        // The order may have been cancelled while the computation was in flight
+        // or its OCO sibling may have executed first
+        if order.status != OrderStatus::Untriggered
+            || !oco_leg_allowed(info.key(), &order, accounts)?
+        {
+            continue;
+        }
+        order.status = OrderStatus::Open;
+        store_account(info, &order)?;
+        resolve_oco_group(info.key(), &order, accounts)?;
+
+        emit!(OrderTriggered {
+            order: info.key(),
+            owner: order.owner,
+            timestamp,
+        });
+    }
+
+    msg!(
+        "Trigger evaluation completed, fired mask: {:#010b}",
+        fired_mask
+    );
+    Ok(())
+}
+
+// ========== ACCOUNT STRUCTS ==========
+
+/// Initialize place_trigger_order computation definition
+#[init_computation_definition_accounts("place_trigger_order", payer)]
+#[derive(Accounts)]
+pub struct InitPlaceTriggerOrderCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Place a trigger order
+#[queue_computation_accounts("place_trigger_order", owner)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PlaceTriggerOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = owner,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLACE_TRIGGER_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Margin account the order trades against; its order sequence is advanced
+    #[account(
+        mut,
+        seeds = [MarginAccount::SEED_PREFIX, owner.key().as_ref()],
+        bump = margin_account.bump,
+    )]
+    pub margin_account: Account<'info, MarginAccount>,
+
+    /// Order account holding the encrypted order and trigger
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            Order::SEED_PREFIX,
+            margin_account.key().as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+        space = Order::SPACE,
+    )]
+    pub order: Account<'info, Order>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Place trigger order callback
+#[callback_accounts("place_trigger_order")]
+#[derive(Accounts)]
+pub struct PlaceTriggerOrderCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLACE_TRIGGER_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Order being placed
+    #[account(mut)]
+    pub order: Account<'info, Order>,
+}
+
+/// Cancel an untriggered trigger order
+#[derive(Accounts)]
+pub struct CancelTriggerOrder<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut, has_one = owner @ ErrorCode::UnauthorizedOrderOwner)]
+    pub order: Account<'info, Order>,
+}
+
+/// Initialize evaluate_triggers computation definition
+#[init_computation_definition_accounts("evaluate_triggers", payer)]
+#[derive(Accounts)]
+pub struct InitEvaluateTriggersCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Crank trigger evaluation (permissionless)
+/// Remaining accounts: up to TRIGGER_BATCH_SIZE untriggered order accounts
+#[queue_computation_accounts("evaluate_triggers", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CrankTriggers<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_EVALUATE_TRIGGERS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Market providing the mark price
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Evaluate triggers callback
+/// Remaining accounts: the order accounts passed to `crank_triggers`, in the same order
+#[callback_accounts("evaluate_triggers")]
+#[derive(Accounts)]
+pub struct EvaluateTriggersCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_EVALUATE_TRIGGERS))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+// ========== EVENTS ==========
+
+#[event]
+pub struct TriggerOrderPlaced {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when the owner cancels a trigger order before it fires
+#[event]
+pub struct TriggerOrderCancelled {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when a trigger order fires and becomes a live order
+#[event]
+pub struct OrderTriggered {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,644 @@
+use anchor_lang::prelude::*;
+use arcium_anchor::prelude::*;
+use arcium_client::idl::arcium::types::{CallbackAccount, CircuitSource, OffChainCircuitSource};
+
+use crate::error::ErrorCode;
+use crate::instructions::matching::push_order_args;
+use crate::instructions::shared::validate_callback_ixs;
+use crate::state::margin_account::MarginAccount;
+use crate::state::market::Market;
+use crate::state::order::{Order, OrderKind, OrderStatus};
+use crate::state::twap_order::{TwapOrder, TwapStatus};
+use crate::state::SignerAccount;
+use crate::ID;
+use crate::ID as ID_CONST;
+
+// Computation definition offsets for TWAP encrypted instructions
+const COMP_DEF_OFFSET_PLACE_TWAP_ORDER: u32 = comp_def_offset("place_twap_order");
+const COMP_DEF_OFFSET_RELEASE_TWAP_SLICE: u32 = comp_def_offset("release_twap_slice");
+
+// ========== PLACE TWAP ORDER ==========
+
+/// Initialize computation definition for placing TWAP orders
+pub fn init_place_twap_order_comp_def(ctx: Context<InitPlaceTwapOrderCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/place_twap_order.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Place TWAP order computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Place an encrypted TWAP order
+/// Total size, limit price, side, slice count and interval are encrypted
+/// client-side. The parent never enters the book; its slices are released
+/// into a single child order by `crank_twap`.
+///
+/// Like limit orders, the ciphertext carries the margin account's next
+/// sequence number so it cannot be replayed
+#[allow(clippy::too_many_arguments)]
+pub fn place_twap_order(
+    ctx: Context<PlaceTwapOrder>,
+    computation_offset: u64,
+    ciphertext_total_size: [u8; 32],
+    ciphertext_price: [u8; 32],
+    ciphertext_side: [u8; 32],
+    ciphertext_slices: [u8; 32],
+    ciphertext_interval_slots: [u8; 32],
+    ciphertext_sequence: [u8; 32],
+    ciphertext_stp_mode: [u8; 32],
+    pub_key: [u8; 32],
+    nonce: u128,
+) -> Result<()> {
+    let owner = ctx.accounts.owner.key();
+    let margin_account = ctx.accounts.margin_account.key();
+    let sequence = ctx.accounts.margin_account.next_order_sequence()?;
+    ctx.accounts.child_order.initialize(
+        owner,
+        margin_account,
+        computation_offset,
+        sequence,
+        OrderKind::Twap,
+        ctx.bumps.child_order,
+    )?;
+
+    let child_order = ctx.accounts.child_order.key();
+    let twap = &mut ctx.accounts.twap;
+    twap.owner = owner;
+    twap.margin_account = margin_account;
+    twap.child_order = child_order;
+    twap.order_id = computation_offset;
+    twap.status = TwapStatus::Pending;
+    twap.encrypted_twap = [[0u8; 32]; 4];
+    twap.twap_nonce = 0;
+    twap.slices_released = 0;
+    twap.last_crank_slot = 0;
+    twap.bump = ctx.bumps.twap;
+
+    let market = &ctx.accounts.market;
+
+    // Enc<Shared, TwapInput>: pubkey, nonce, then struct fields in order,
+    // then the public market params, expected sequence, owner id and slot
+    let args = vec![
+        Argument::ArcisPubkey(pub_key),
+        Argument::PlaintextU128(nonce),
+        Argument::EncryptedU64(ciphertext_total_size),
+        Argument::EncryptedU64(ciphertext_price),
+        Argument::EncryptedU8(ciphertext_side),
+        Argument::EncryptedU64(ciphertext_slices),
+        Argument::EncryptedU64(ciphertext_interval_slots),
+        Argument::EncryptedU64(ciphertext_sequence),
+        Argument::EncryptedU8(ciphertext_stp_mode),
+        Argument::PlaintextU64(market.tick_size),
+        Argument::PlaintextU64(market.lot_size),
+        Argument::PlaintextU64(sequence),
+        Argument::PlaintextU128(Order::stp_owner_id(&margin_account)),
+        Argument::PlaintextU64(Clock::get()?.slot),
+    ];
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![PlaceTwapOrderCallback::callback_ix(&[
+            CallbackAccount {
+                pubkey: ctx.accounts.twap.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.child_order.key(),
+                is_writable: true,
+            },
+        ])],
+        1,
+    )?;
+
+    msg!("TWAP order queued to MPC cluster");
+    Ok(())
+}
+
+/// Callback after TWAP placement
+/// Stores the MXE-encrypted parent and the empty child order, or rejects
+/// both if the TWAP failed validation
+#[arcium_callback(encrypted_ix = "place_twap_order")]
+pub fn place_twap_order_callback(
+    ctx: Context<PlaceTwapOrderCallback>,
+    output: ComputationOutputs<PlaceTwapOrderOutput>,
+) -> Result<()> {
+    let (encrypted_twap, encrypted_child, valid) = match output {
+        ComputationOutputs::Success(PlaceTwapOrderOutput {
+            field_0:
+                PlaceTwapOrderOutputStruct0 {
+                    field_0: encrypted_twap,
+                    field_1: encrypted_child,
+                    field_2: valid,
+                },
+        }) => (encrypted_twap, encrypted_child, valid),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let twap = &mut ctx.accounts.twap;
+    require!(
+        twap.status == TwapStatus::Pending,
+        ErrorCode::InvalidTwapStatus
+    );
+    let child = &mut ctx.accounts.child_order;
+    require_keys_eq!(twap.child_order, child.key(), ErrorCode::TwapChildMismatch);
+
+    if !valid {
+        twap.status = TwapStatus::Rejected;
+        child.status = OrderStatus::Rejected;
+        msg!("TWAP order rejected");
+        return Ok(());
+    }
+
+    twap.encrypted_twap = encrypted_twap.ciphertexts;
+    twap.twap_nonce = encrypted_twap.nonce;
+    twap.status = TwapStatus::Active;
+
+    // The child stays Pending (out of the book) until the first slice is released
+    child.encrypted_order = encrypted_child.ciphertexts;
+    child.order_nonce = encrypted_child.nonce;
+
+    emit!(TwapOrderPlaced {
+        twap: twap.key(),
+        child_order: child.key(),
+        owner: twap.owner,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("TWAP order stored encrypted on-chain");
+    Ok(())
+}
+
+/// Cancel a TWAP order and the unfilled remainder of its child order
+pub fn cancel_twap_order(ctx: Context<CancelTwapOrder>) -> Result<()> {
+    let twap = &mut ctx.accounts.twap;
+    require!(
+        twap.status == TwapStatus::Active || twap.status == TwapStatus::Completed,
+        ErrorCode::InvalidTwapStatus
+    );
+    twap.status = TwapStatus::Cancelled;
+
+    let child = &mut ctx.accounts.child_order;
+    if child.status != OrderStatus::Filled {
+        child.status = OrderStatus::Cancelled;
+    }
+
+    emit!(TwapOrderCancelled {
+        twap: twap.key(),
+        child_order: child.key(),
+        slices_released: twap.slices_released,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("TWAP order cancelled");
+    Ok(())
+}
+
+// ========== RELEASE TWAP SLICE (CRANK) ==========
+
+/// Initialize computation definition for TWAP slice release
+pub fn init_release_twap_slice_comp_def(ctx: Context<InitReleaseTwapSliceCompDef>) -> Result<()> {
+    init_comp_def(
+        ctx.accounts,
+        0,
+        Some(CircuitSource::OffChain(OffChainCircuitSource {
+            source: "https://ffzjucisiaierxyonwlx.supabase.co/storage/v1/object/public/arcium-circuits/release_twap_slice.arcis".to_string(),
+            hash: [0; 32],
+        })),
+        None,
+    )?;
+    msg!("Release TWAP slice computation definition initialized with circuit");
+    Ok(())
+}
+
+/// Ask the MPC cluster to release the next slice of a TWAP order
+/// Permissionless crank: the interval is encrypted, so the circuit decides
+/// whether a slice is due and only reveals whether one was released.
+/// The child order is held by the TWAP until the callback lands, so it
+/// cannot be cranked while a match against it is in flight, nor matched
+/// while a slice is being released. Cranks are rate-limited per TWAP so
+/// repeated calls cannot keep the child out of the book.
+pub fn crank_twap(ctx: Context<CrankTwap>, computation_offset: u64) -> Result<()> {
+    let twap = &ctx.accounts.twap;
+    require!(
+        twap.status == TwapStatus::Active,
+        ErrorCode::InvalidTwapStatus
+    );
+    let child = &ctx.accounts.child_order;
+    require!(
+        matches!(
+            child.status,
+            OrderStatus::Pending | OrderStatus::Open | OrderStatus::Filled
+        ),
+        ErrorCode::InvalidOrderStatus
+    );
+
+    // Enc<Mxe, TwapData>, Enc<Mxe, OrderData>, then the current slot and lot size
+    let mut args = vec![Argument::PlaintextU128(twap.twap_nonce)];
+    args.extend(
+        twap.encrypted_twap
+            .iter()
+            .map(|ct| Argument::EncryptedU64(*ct)),
+    );
+    push_order_args(&mut args, child);
+    let current_slot = Clock::get()?.slot;
+    args.push(Argument::PlaintextU64(current_slot));
+    args.push(Argument::PlaintextU64(ctx.accounts.market.lot_size));
+
+    ctx.accounts.twap.record_crank(current_slot)?;
+    let twap_key = ctx.accounts.twap.key();
+    ctx.accounts.child_order.lock(twap_key, current_slot)?;
+
+    ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+    queue_computation(
+        ctx.accounts,
+        computation_offset,
+        args,
+        None,
+        vec![ReleaseTwapSliceCallback::callback_ix(&[
+            CallbackAccount {
+                pubkey: ctx.accounts.twap.key(),
+                is_writable: true,
+            },
+            CallbackAccount {
+                pubkey: ctx.accounts.child_order.key(),
+                is_writable: true,
+            },
+        ])],
+        1,
+    )?;
+
+    msg!("TWAP slice release queued to MPC cluster");
+    Ok(())
+}
+
+/// Callback after a TWAP crank
+/// When a slice was released, the topped-up child order is stored and
+/// (re)opened in the book with a fresh time priority. The result is dropped
+/// if the TWAP lost its hold on the child or the child was cancelled.
+#[arcium_callback(encrypted_ix = "release_twap_slice")]
+pub fn release_twap_slice_callback(
+    ctx: Context<ReleaseTwapSliceCallback>,
+    output: ComputationOutputs<ReleaseTwapSliceOutput>,
+) -> Result<()> {
+    let (encrypted_twap, encrypted_child, released, completed) = match output {
+        ComputationOutputs::Success(ReleaseTwapSliceOutput {
+            field_0:
+                ReleaseTwapSliceOutputStruct0 {
+                    field_0: encrypted_twap,
+                    field_1: encrypted_child,
+                    field_2: released,
+                    field_3: completed,
+                },
+        }) => (encrypted_twap, encrypted_child, released, completed),
+        _ => return Err(ErrorCode::AbortedComputation.into()),
+    };
+
+    let twap = &mut ctx.accounts.twap;
+    require!(
+        twap.status == TwapStatus::Active,
+        ErrorCode::InvalidTwapStatus
+    );
+    let child = &mut ctx.accounts.child_order;
+    require_keys_eq!(twap.child_order, child.key(), ErrorCode::TwapChildMismatch);
+
+    if !child.unlock(&twap.key()) || child.status == OrderStatus::Cancelled {
+        msg!("Child order changed while the release was in flight, result discarded");
+        return Ok(());
+    }
+
+    twap.encrypted_twap = encrypted_twap.ciphertexts;
+    twap.twap_nonce = encrypted_twap.nonce;
+
+    if !released {
+        msg!("No TWAP slice due yet");
+        return Ok(());
+    }
+
+    child.encrypted_order = encrypted_child.ciphertexts;
+    child.order_nonce = encrypted_child.nonce;
+    child.status = OrderStatus::Open;
+    child.created_slot = Clock::get()?.slot;
+    child.submitted_slot = child.created_slot;
+
+    twap.slices_released = twap
+        .slices_released
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if completed {
+        twap.status = TwapStatus::Completed;
+    }
+
+    emit!(TwapSliceReleased {
+        twap: twap.key(),
+        child_order: child.key(),
+        slices_released: twap.slices_released,
+        completed,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("TWAP slice released into the book");
+    Ok(())
+}
+
+// ========== ACCOUNT STRUCTS ==========
+
+/// Initialize place_twap_order computation definition
+#[init_computation_definition_accounts("place_twap_order", payer)]
+#[derive(Accounts)]
+pub struct InitPlaceTwapOrderCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Place a TWAP order
+#[queue_computation_accounts("place_twap_order", owner)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PlaceTwapOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = owner,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLACE_TWAP_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Market providing tick size and lot size
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// Margin account the child order trades against; its order sequence is advanced
+    #[account(
+        mut,
+        seeds = [MarginAccount::SEED_PREFIX, owner.key().as_ref()],
+        bump = margin_account.bump,
+    )]
+    pub margin_account: Box<Account<'info, MarginAccount>>,
+
+    /// TWAP parent holding the encrypted schedule
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            TwapOrder::SEED_PREFIX,
+            margin_account.key().as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+        space = TwapOrder::SPACE,
+    )]
+    pub twap: Box<Account<'info, TwapOrder>>,
+
+    /// Child order receiving the released slices
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            Order::SEED_PREFIX,
+            margin_account.key().as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        bump,
+        space = Order::SPACE,
+    )]
+    pub child_order: Box<Account<'info, Order>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Place TWAP order callback
+#[callback_accounts("place_twap_order")]
+#[derive(Accounts)]
+pub struct PlaceTwapOrderCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLACE_TWAP_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// TWAP parent being placed
+    #[account(mut)]
+    pub twap: Account<'info, TwapOrder>,
+
+    /// Child order of the TWAP
+    #[account(mut)]
+    pub child_order: Account<'info, Order>,
+}
+
+/// Cancel a TWAP order
+#[derive(Accounts)]
+pub struct CancelTwapOrder<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ ErrorCode::UnauthorizedTwapOwner,
+        has_one = child_order @ ErrorCode::TwapChildMismatch,
+    )]
+    pub twap: Account<'info, TwapOrder>,
+
+    #[account(mut)]
+    pub child_order: Account<'info, Order>,
+}
+
+/// Initialize release_twap_slice computation definition
+#[init_computation_definition_accounts("release_twap_slice", payer)]
+#[derive(Accounts)]
+pub struct InitReleaseTwapSliceCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Computation definition account, checked by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Crank a TWAP order (permissionless)
+#[queue_computation_accounts("release_twap_slice", cranker)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct CrankTwap<'info> {
+    /// Anyone may crank; pays the computation fee
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = cranker,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by arcium program
+    pub computation_account: UncheckedAccount<'info>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RELEASE_TWAP_SLICE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account, crate::error::ErrorCode::ClusterNotSet)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+
+    /// Market providing the lot size slices are rounded to
+    #[account(
+        seeds = [Market::SEED_PREFIX],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// TWAP parent being cranked, records the crank slot
+    #[account(mut, has_one = child_order @ ErrorCode::TwapChildMismatch)]
+    pub twap: Box<Account<'info, TwapOrder>>,
+
+    /// Child order receiving the slice, held until the callback lands
+    #[account(mut)]
+    pub child_order: Box<Account<'info, Order>>,
+
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+/// Release TWAP slice callback
+#[callback_accounts("release_twap_slice")]
+#[derive(Accounts)]
+pub struct ReleaseTwapSliceCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_RELEASE_TWAP_SLICE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// TWAP parent being cranked
+    #[account(mut)]
+    pub twap: Account<'info, TwapOrder>,
+
+    /// Child order receiving the slice
+    #[account(mut)]
+    pub child_order: Account<'info, Order>,
+}
+
+// ========== EVENTS ==========
+
+/// Emitted when a TWAP order is stored. Total size, slices and interval stay encrypted.
+#[event]
+pub struct TwapOrderPlaced {
+    pub twap: Pubkey,
+    pub child_order: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when a slice is released into the child order. Slice size stays encrypted.
+#[event]
+pub struct TwapSliceReleased {
+    pub twap: Pubkey,
+    pub child_order: Pubkey,
+    pub slices_released: u64,
+    pub completed: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when a TWAP order is cancelled by its owner
+#[event]
+pub struct TwapOrderCancelled {
+    pub twap: Pubkey,
+    pub child_order: Pubkey,
+    pub slices_released: u64,
+    pub timestamp: i64,
+}
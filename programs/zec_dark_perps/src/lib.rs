@@ -25,13 +25,88 @@ pub mod zec_dark_perps {
         instructions::margin::withdraw_collateral(ctx, amount)
     }
 
+    pub fn set_viewing_key(ctx: Context<SetViewingKey>, viewing_key: [u8; 32]) -> Result<()> {
+        instructions::margin::set_viewing_key(ctx, viewing_key)
+    }
+
     // Trading instructions
     pub fn settle_trade(ctx: Context<SettleTrade>, price: u64, size: u64) -> Result<()> {
         instructions::trading::settle_trade(ctx, price, size)
     }
 
-    pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
-        instructions::liquidation::liquidate(ctx)
+    // Liquidation instructions
+    pub fn init_insurance_fund(ctx: Context<InitInsuranceFund>) -> Result<()> {
+        instructions::insurance::init_insurance_fund(ctx)
+    }
+
+    pub fn init_liquidate_position_comp_def(
+        ctx: Context<InitLiquidatePositionCompDef>,
+    ) -> Result<()> {
+        instructions::liquidation::init_liquidate_position_comp_def(ctx)
+    }
+
+    pub fn liquidate(ctx: Context<Liquidate>, computation_offset: u64) -> Result<()> {
+        instructions::liquidation::liquidate(ctx, computation_offset)
+    }
+
+    pub fn liquidate_position_callback(
+        ctx: Context<LiquidatePositionCallback>,
+        output: ComputationOutputs<LiquidatePositionOutput>,
+    ) -> Result<()> {
+        instructions::liquidation::liquidate_position_callback(ctx, output)
+    }
+
+    pub fn init_apply_loss_index_comp_def(ctx: Context<InitApplyLossIndexCompDef>) -> Result<()> {
+        instructions::liquidation::init_apply_loss_index_comp_def(ctx)
+    }
+
+    pub fn apply_socialized_loss(
+        ctx: Context<ApplySocializedLoss>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        instructions::liquidation::apply_socialized_loss(ctx, computation_offset)
+    }
+
+    pub fn apply_loss_index_callback(
+        ctx: Context<ApplyLossIndexCallback>,
+        output: ComputationOutputs<ApplyLossIndexOutput>,
+    ) -> Result<()> {
+        instructions::liquidation::apply_loss_index_callback(ctx, output)
+    }
+
+    // Auto-deleveraging instructions
+    pub fn init_rank_adl_comp_def(ctx: Context<InitRankAdlCompDef>) -> Result<()> {
+        instructions::adl::init_rank_adl_comp_def(ctx)
+    }
+
+    pub fn queue_adl_ranking(ctx: Context<QueueAdlRanking>, computation_offset: u64) -> Result<()> {
+        instructions::adl::queue_adl_ranking(ctx, computation_offset)
+    }
+
+    pub fn rank_adl_callback(
+        ctx: Context<RankAdlCallback>,
+        output: ComputationOutputs<RankAdlOutput>,
+    ) -> Result<()> {
+        instructions::adl::rank_adl_callback(ctx, output)
+    }
+
+    pub fn init_auto_deleverage_comp_def(ctx: Context<InitAutoDeleverageCompDef>) -> Result<()> {
+        instructions::adl::init_auto_deleverage_comp_def(ctx)
+    }
+
+    pub fn auto_deleverage(
+        ctx: Context<AutoDeleverage>,
+        computation_offset: u64,
+        count: u8,
+    ) -> Result<()> {
+        instructions::adl::auto_deleverage(ctx, computation_offset, count)
+    }
+
+    pub fn auto_deleverage_callback(
+        ctx: Context<AutoDeleverageCallback>,
+        output: ComputationOutputs<AutoDeleverageOutput>,
+    ) -> Result<()> {
+        instructions::adl::auto_deleverage_callback(ctx, output)
     }
 
     // Arcium MPC health check instructions
@@ -42,29 +117,33 @@ pub mod zec_dark_perps {
     pub fn queue_health_check(
         ctx: Context<QueueHealthCheck>,
         computation_offset: u64,
-        ciphertext_collateral: [u8; 32],
-        ciphertext_debt: [u8; 32],
-        ciphertext_price: [u8; 32],
-        pub_key: [u8; 32],
-        nonce: u128,
     ) -> Result<()> {
-        instructions::arcium::queue_health_check(
-            ctx,
-            computation_offset,
-            ciphertext_collateral,
-            ciphertext_debt,
-            ciphertext_price,
-            pub_key,
-            nonce,
-        )
+        instructions::arcium::queue_health_check(ctx, computation_offset)
+    }
+
+    pub fn check_health_callback(
+        ctx: Context<CheckHealthCallback>,
+        output: ComputationOutputs<CheckHealthOutput>,
+    ) -> Result<()> {
+        instructions::arcium::check_health_callback(ctx, output)
+    }
+
+    pub fn init_health_sweep_comp_def(ctx: Context<InitHealthSweepCompDef>) -> Result<()> {
+        instructions::arcium::init_health_sweep_comp_def(ctx)
+    }
+
+    pub fn queue_health_sweep(
+        ctx: Context<QueueHealthSweep>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        instructions::arcium::queue_health_sweep(ctx, computation_offset)
     }
 
-    pub fn health_check_callback(
-        ctx: Context<HealthCheckCallback>,
-        encrypted_result: [u8; 32],
-        nonce: [u8; 16],
+    pub fn check_health_batch_callback(
+        ctx: Context<CheckHealthBatchCallback>,
+        output: ComputationOutputs<CheckHealthBatchOutput>,
     ) -> Result<()> {
-        instructions::arcium::health_check_callback(ctx, encrypted_result, nonce)
+        instructions::arcium::check_health_batch_callback(ctx, output)
     }
 
     // Bridge instructions
@@ -98,26 +177,76 @@ pub mod zec_dark_perps {
         instructions::trading_arcium::init_submit_order_comp_def(ctx)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn submit_order(
         ctx: Context<SubmitOrder>,
         computation_offset: u64,
+        is_market: bool,
         ciphertext_size: [u8; 32],
         ciphertext_price: [u8; 32],
         ciphertext_side: [u8; 32],
+        ciphertext_display_size: [u8; 32],
+        ciphertext_sequence: [u8; 32],
+        ciphertext_stp_mode: [u8; 32],
         pub_key: [u8; 32],
         nonce: u128,
     ) -> Result<()> {
         instructions::trading_arcium::submit_order(
             ctx,
             computation_offset,
+            is_market,
             ciphertext_size,
             ciphertext_price,
             ciphertext_side,
+            ciphertext_display_size,
+            ciphertext_sequence,
+            ciphertext_stp_mode,
             pub_key,
             nonce,
         )
     }
 
+    pub fn commit_order(
+        ctx: Context<CommitOrder>,
+        commitment_id: u64,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        instructions::trading_arcium::commit_order(ctx, commitment_id, commitment)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn reveal_order(
+        ctx: Context<RevealOrder>,
+        computation_offset: u64,
+        is_market: bool,
+        ciphertext_size: [u8; 32],
+        ciphertext_price: [u8; 32],
+        ciphertext_side: [u8; 32],
+        ciphertext_display_size: [u8; 32],
+        ciphertext_sequence: [u8; 32],
+        ciphertext_stp_mode: [u8; 32],
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        instructions::trading_arcium::reveal_order(
+            ctx,
+            computation_offset,
+            is_market,
+            ciphertext_size,
+            ciphertext_price,
+            ciphertext_side,
+            ciphertext_display_size,
+            ciphertext_sequence,
+            ciphertext_stp_mode,
+            pub_key,
+            nonce,
+        )
+    }
+
+    pub fn cancel_order_commitment(ctx: Context<CancelOrderCommitment>) -> Result<()> {
+        instructions::trading_arcium::cancel_order_commitment(ctx)
+    }
+
     pub fn submit_order_callback(
         ctx: Context<SubmitOrderCallback>,
         output: ComputationOutputs<SubmitOrderOutput>,
@@ -171,9 +300,8 @@ pub mod zec_dark_perps {
     pub fn queue_settle_trade(
         ctx: Context<QueueSettleTrade>,
         computation_offset: u64,
-        trade_value: u64,
     ) -> Result<()> {
-        instructions::margin_arcium::queue_settle_trade(ctx, computation_offset, trade_value)
+        instructions::margin_arcium::queue_settle_trade(ctx, computation_offset)
     }
 
     pub fn settle_trade_callback(
@@ -182,4 +310,378 @@ pub mod zec_dark_perps {
     ) -> Result<()> {
         instructions::margin_arcium::settle_trade_callback(ctx, output)
     }
+
+    pub fn reset_settling_fill(ctx: Context<ResetSettlingFill>) -> Result<()> {
+        instructions::margin_arcium::reset_settling_fill(ctx)
+    }
+
+    // Market instructions
+    pub fn initialize_market(
+        ctx: Context<InitializeMarket>,
+        oracle: Pubkey,
+        tick_size: u64,
+        lot_size: u64,
+        price_band_bps: u16,
+    ) -> Result<()> {
+        instructions::market::initialize_market(ctx, oracle, tick_size, lot_size, price_band_bps)
+    }
+
+    pub fn update_market_params(
+        ctx: Context<UpdateMarketParams>,
+        tick_size: u64,
+        lot_size: u64,
+        price_band_bps: u16,
+    ) -> Result<()> {
+        instructions::market::update_market_params(ctx, tick_size, lot_size, price_band_bps)
+    }
+
+    pub fn update_market_fees(
+        ctx: Context<UpdateMarketParams>,
+        maker_fee_bps: i16,
+        taker_fee_bps: u16,
+    ) -> Result<()> {
+        instructions::market::update_market_fees(ctx, maker_fee_bps, taker_fee_bps)
+    }
+
+    pub fn update_risk_params(
+        ctx: Context<UpdateMarketParams>,
+        maintenance_margin_bps: u16,
+        liquidation_penalty_bps: u16,
+        insurance_fund_share_bps: u16,
+        health_check_max_age_slots: u64,
+    ) -> Result<()> {
+        instructions::market::update_risk_params(
+            ctx,
+            maintenance_margin_bps,
+            liquidation_penalty_bps,
+            insurance_fund_share_bps,
+            health_check_max_age_slots,
+        )
+    }
+
+    pub fn update_mark_price(ctx: Context<UpdateMarkPrice>, mark_price: u64) -> Result<()> {
+        instructions::market::update_mark_price(ctx, mark_price)
+    }
+
+    // Encrypted stop-loss / take-profit trigger orders
+    pub fn init_place_trigger_order_comp_def(
+        ctx: Context<InitPlaceTriggerOrderCompDef>,
+    ) -> Result<()> {
+        instructions::trigger_orders::init_place_trigger_order_comp_def(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn place_trigger_order(
+        ctx: Context<PlaceTriggerOrder>,
+        computation_offset: u64,
+        ciphertext_size: [u8; 32],
+        ciphertext_price: [u8; 32],
+        ciphertext_side: [u8; 32],
+        ciphertext_trigger_price: [u8; 32],
+        ciphertext_direction: [u8; 32],
+        ciphertext_sequence: [u8; 32],
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        instructions::trigger_orders::place_trigger_order(
+            ctx,
+            computation_offset,
+            ciphertext_size,
+            ciphertext_price,
+            ciphertext_side,
+            ciphertext_trigger_price,
+            ciphertext_direction,
+            ciphertext_sequence,
+            pub_key,
+            nonce,
+        )
+    }
+
+    pub fn place_trigger_order_callback(
+        ctx: Context<PlaceTriggerOrderCallback>,
+        output: ComputationOutputs<PlaceTriggerOrderOutput>,
+    ) -> Result<()> {
+        instructions::trigger_orders::place_trigger_order_callback(ctx, output)
+    }
+
+    pub fn cancel_trigger_order(ctx: Context<CancelTriggerOrder>) -> Result<()> {
+        instructions::trigger_orders::cancel_trigger_order(ctx)
+    }
+
+    pub fn init_evaluate_triggers_comp_def(
+        ctx: Context<InitEvaluateTriggersCompDef>,
+    ) -> Result<()> {
+        instructions::trigger_orders::init_evaluate_triggers_comp_def(ctx)
+    }
+
+    pub fn crank_triggers(ctx: Context<CrankTriggers>, computation_offset: u64) -> Result<()> {
+        instructions::trigger_orders::crank_triggers(ctx, computation_offset)
+    }
+
+    pub fn evaluate_triggers_callback(
+        ctx: Context<EvaluateTriggersCallback>,
+        output: ComputationOutputs<EvaluateTriggersOutput>,
+    ) -> Result<()> {
+        instructions::trigger_orders::evaluate_triggers_callback(ctx, output)
+    }
+
+    // Program config / matcher registry
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        instructions::config::initialize_config(ctx)
+    }
+
+    pub fn add_matcher(ctx: Context<UpdateMatchers>, matcher: Pubkey) -> Result<()> {
+        instructions::config::add_matcher(ctx, matcher)
+    }
+
+    pub fn remove_matcher(ctx: Context<UpdateMatchers>, matcher: Pubkey) -> Result<()> {
+        instructions::config::remove_matcher(ctx, matcher)
+    }
+
+    pub fn set_callback_server(ctx: Context<UpdateConfig>, url: String) -> Result<()> {
+        instructions::config::set_callback_server(ctx, url)
+    }
+
+    // Encrypted order matching
+    pub fn init_match_orders_comp_def(ctx: Context<InitMatchOrdersCompDef>) -> Result<()> {
+        instructions::matching::init_match_orders_comp_def(ctx)
+    }
+
+    pub fn queue_match_orders(
+        ctx: Context<QueueMatchOrders>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        instructions::matching::queue_match_orders(ctx, computation_offset)
+    }
+
+    pub fn match_orders_callback(
+        ctx: Context<MatchOrdersCallback>,
+        output: ComputationOutputs<MatchOrdersOutput>,
+    ) -> Result<()> {
+        instructions::matching::match_orders_callback(ctx, output)
+    }
+
+    pub fn expire_market_order(ctx: Context<ExpireMarketOrder>) -> Result<()> {
+        instructions::matching::expire_market_order(ctx)
+    }
+
+    pub fn init_return_fill_comp_def(ctx: Context<InitReturnFillCompDef>) -> Result<()> {
+        instructions::matching::init_return_fill_comp_def(ctx)
+    }
+
+    pub fn queue_return_fill(ctx: Context<QueueReturnFill>, computation_offset: u64) -> Result<()> {
+        instructions::matching::queue_return_fill(ctx, computation_offset)
+    }
+
+    pub fn return_fill_callback(
+        ctx: Context<ReturnFillCallback>,
+        output: ComputationOutputs<ReturnFillOutput>,
+    ) -> Result<()> {
+        instructions::matching::return_fill_callback(ctx, output)
+    }
+
+    // One-cancels-other order groups
+    pub fn create_oco_group(ctx: Context<CreateOcoGroup>, group_id: u64) -> Result<()> {
+        instructions::order_groups::create_oco_group(ctx, group_id)
+    }
+
+    pub fn cancel_oco_group(ctx: Context<CancelOcoGroup>) -> Result<()> {
+        instructions::order_groups::cancel_oco_group(ctx)
+    }
+
+    // Public book snapshot
+    pub fn init_book_snapshot(ctx: Context<InitBookSnapshot>, interval_slots: u64) -> Result<()> {
+        instructions::book_snapshot::init_book_snapshot(ctx, interval_slots)
+    }
+
+    pub fn init_aggregate_book_comp_def(ctx: Context<InitAggregateBookCompDef>) -> Result<()> {
+        instructions::book_snapshot::init_aggregate_book_comp_def(ctx)
+    }
+
+    pub fn queue_aggregate_book(
+        ctx: Context<QueueAggregateBook>,
+        computation_offset: u64,
+        reset: bool,
+        finalize: bool,
+    ) -> Result<()> {
+        instructions::book_snapshot::queue_aggregate_book(ctx, computation_offset, reset, finalize)
+    }
+
+    pub fn aggregate_book_callback(
+        ctx: Context<AggregateBookCallback>,
+        output: ComputationOutputs<AggregateBookOutput>,
+    ) -> Result<()> {
+        instructions::book_snapshot::aggregate_book_callback(ctx, output)
+    }
+
+    // Private RFQ / block trades
+    pub fn init_request_quote_comp_def(ctx: Context<InitRequestQuoteCompDef>) -> Result<()> {
+        instructions::rfq::init_request_quote_comp_def(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn request_quote(
+        ctx: Context<RequestQuote>,
+        computation_offset: u64,
+        responder: Pubkey,
+        ttl_slots: u64,
+        ciphertext_size: [u8; 32],
+        ciphertext_side: [u8; 32],
+        ciphertext_limit_price: [u8; 32],
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        instructions::rfq::request_quote(
+            ctx,
+            computation_offset,
+            responder,
+            ttl_slots,
+            ciphertext_size,
+            ciphertext_side,
+            ciphertext_limit_price,
+            pub_key,
+            nonce,
+        )
+    }
+
+    pub fn request_quote_callback(
+        ctx: Context<RequestQuoteCallback>,
+        output: ComputationOutputs<RequestQuoteOutput>,
+    ) -> Result<()> {
+        instructions::rfq::request_quote_callback(ctx, output)
+    }
+
+    pub fn cancel_quote_request(ctx: Context<CancelQuoteRequest>) -> Result<()> {
+        instructions::rfq::cancel_quote_request(ctx)
+    }
+
+    pub fn init_settle_block_trade_comp_def(
+        ctx: Context<InitSettleBlockTradeCompDef>,
+    ) -> Result<()> {
+        instructions::rfq::init_settle_block_trade_comp_def(ctx)
+    }
+
+    pub fn submit_quote(
+        ctx: Context<SubmitQuote>,
+        computation_offset: u64,
+        ciphertext_price: [u8; 32],
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        instructions::rfq::submit_quote(ctx, computation_offset, ciphertext_price, pub_key, nonce)
+    }
+
+    pub fn settle_block_trade_callback(
+        ctx: Context<SettleBlockTradeCallback>,
+        output: ComputationOutputs<SettleBlockTradeOutput>,
+    ) -> Result<()> {
+        instructions::rfq::settle_block_trade_callback(ctx, output)
+    }
+
+    // Batched settlement
+    pub fn init_settle_batch_comp_def(ctx: Context<InitSettleBatchCompDef>) -> Result<()> {
+        instructions::batch_settlement::init_settle_batch_comp_def(ctx)
+    }
+
+    pub fn queue_settle_batch(
+        ctx: Context<QueueSettleBatch>,
+        computation_offset: u64,
+        fill_count: u8,
+    ) -> Result<()> {
+        instructions::batch_settlement::queue_settle_batch(ctx, computation_offset, fill_count)
+    }
+
+    pub fn settle_batch_callback(
+        ctx: Context<SettleBatchCallback>,
+        output: ComputationOutputs<SettleBatchOutput>,
+    ) -> Result<()> {
+        instructions::batch_settlement::settle_batch_callback(ctx, output)
+    }
+
+    // TWAP orders
+    pub fn init_place_twap_order_comp_def(ctx: Context<InitPlaceTwapOrderCompDef>) -> Result<()> {
+        instructions::twap::init_place_twap_order_comp_def(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn place_twap_order(
+        ctx: Context<PlaceTwapOrder>,
+        computation_offset: u64,
+        ciphertext_total_size: [u8; 32],
+        ciphertext_price: [u8; 32],
+        ciphertext_side: [u8; 32],
+        ciphertext_slices: [u8; 32],
+        ciphertext_interval_slots: [u8; 32],
+        ciphertext_sequence: [u8; 32],
+        ciphertext_stp_mode: [u8; 32],
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        instructions::twap::place_twap_order(
+            ctx,
+            computation_offset,
+            ciphertext_total_size,
+            ciphertext_price,
+            ciphertext_side,
+            ciphertext_slices,
+            ciphertext_interval_slots,
+            ciphertext_sequence,
+            ciphertext_stp_mode,
+            pub_key,
+            nonce,
+        )
+    }
+
+    pub fn place_twap_order_callback(
+        ctx: Context<PlaceTwapOrderCallback>,
+        output: ComputationOutputs<PlaceTwapOrderOutput>,
+    ) -> Result<()> {
+        instructions::twap::place_twap_order_callback(ctx, output)
+    }
+
+    pub fn cancel_twap_order(ctx: Context<CancelTwapOrder>) -> Result<()> {
+        instructions::twap::cancel_twap_order(ctx)
+    }
+
+    pub fn init_release_twap_slice_comp_def(
+        ctx: Context<InitReleaseTwapSliceCompDef>,
+    ) -> Result<()> {
+        instructions::twap::init_release_twap_slice_comp_def(ctx)
+    }
+
+    pub fn crank_twap(ctx: Context<CrankTwap>, computation_offset: u64) -> Result<()> {
+        instructions::twap::crank_twap(ctx, computation_offset)
+    }
+
+    pub fn release_twap_slice_callback(
+        ctx: Context<ReleaseTwapSliceCallback>,
+        output: ComputationOutputs<ReleaseTwapSliceOutput>,
+    ) -> Result<()> {
+        instructions::twap::release_twap_slice_callback(ctx, output)
+    }
+
+    // Protocol fees
+    pub fn init_protocol_fees(ctx: Context<InitProtocolFees>) -> Result<()> {
+        instructions::fees::init_protocol_fees(ctx)
+    }
+
+    pub fn init_reveal_protocol_fees_comp_def(
+        ctx: Context<InitRevealProtocolFeesCompDef>,
+    ) -> Result<()> {
+        instructions::fees::init_reveal_protocol_fees_comp_def(ctx)
+    }
+
+    pub fn sweep_protocol_fees(
+        ctx: Context<SweepProtocolFees>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        instructions::fees::sweep_protocol_fees(ctx, computation_offset)
+    }
+
+    pub fn reveal_protocol_fees_callback(
+        ctx: Context<RevealProtocolFeesCallback>,
+        output: ComputationOutputs<RevealProtocolFeesOutput>,
+    ) -> Result<()> {
+        instructions::fees::reveal_protocol_fees_callback(ctx, output)
+    }
 }
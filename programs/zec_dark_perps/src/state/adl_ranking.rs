@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+/// Auto-deleveraging ranking for one bankrupt margin account.
+///
+/// Address: seeds = [b"adl_ranking", margin_account], bump stored in `bump`.
+///
+/// Holds the candidates a `rank_adl` computation was queued with and, once
+/// its callback lands, the revealed order they are deleveraged in. The
+/// ranking only holds while the bankrupt account and every candidate are
+/// still at the state versions it was computed from, and is consumed by
+/// `auto_deleverage`.
+#[account]
+pub struct AdlRanking {
+    /// The bankrupt margin account
+    pub margin_account: Pubkey,
+
+    /// Candidate margin accounts, in the slot order they were ranked in
+    pub candidates: [Pubkey; 8],
+
+    /// Number of candidate slots in use
+    pub candidate_count: u8,
+
+    /// Computation offset of the ranking the candidates were queued with
+    pub computation_offset: u64,
+
+    /// Revealed ranking: entry k is the slot of the k-th ranked candidate
+    pub order: [u8; 8],
+
+    /// Whether `order` holds the result for the current candidates
+    pub is_ranked: bool,
+
+    /// `state_version` of the bankrupt account the ranking was computed from
+    pub bankrupt_state_version: u64,
+
+    /// `state_version` of each candidate the ranking was computed from
+    pub state_versions: [u64; 8],
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl AdlRanking {
+    pub const SEED_PREFIX: &'static [u8] = b"adl_ranking";
+
+    /// Space calculation for account
+    /// discriminator(8) + margin_account(32) + candidates(256)
+    /// + candidate_count(1) + computation_offset(8) + order(8) + is_ranked(1)
+    /// + bankrupt_state_version(8) + state_versions(64) + bump(1) = 387 bytes
+    pub const SPACE: usize = 8 + 32 + 256 + 1 + 8 + 8 + 1 + 8 + 64 + 1;
+
+    /// Slot of the k-th ranked candidate, if it holds a candidate
+    pub fn ranked_slot(&self, rank: usize) -> Option<usize> {
+        let slot = *self.order.get(rank)? as usize;
+        (slot < self.candidate_count as usize).then_some(slot)
+    }
+}
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+
+/// Public depth of the encrypted book, aggregated inside MPC.
+///
+/// Address: seeds = [b"book_snapshot"], bump stored in `bump`.
+///
+/// A snapshot is built by feeding every open order through `aggregate_book`
+/// in batches, in strictly increasing order-address order so no order is
+/// counted twice. Per-level totals stay encrypted while building; only the
+/// size bucket of each $1 price level is published when the build finalizes.
+#[account]
+pub struct BookSnapshot {
+    /// Minimum slots between two published snapshots
+    pub interval_slots: u64,
+
+    /// A snapshot is being built
+    pub building: bool,
+
+    /// A batch computation is in flight; the next one must wait for its callback
+    pub in_flight: bool,
+
+    /// Computation offset of the batch in flight. A reset supersedes it, and
+    /// callbacks from any other computation are ignored.
+    pub in_flight_offset: u64,
+
+    /// The batch in flight is the last one of the build
+    pub finalizing: bool,
+
+    /// Encrypted running totals: bids per level, then asks per level
+    pub encrypted_depth: [[u8; 32]; BookSnapshot::DEPTH_ENCRYPTED_FIELDS],
+
+    /// Nonce for `encrypted_depth`
+    pub depth_nonce: u128,
+
+    /// Dollar price of level 0 for the snapshot being built
+    pub build_base_level: u64,
+
+    /// Highest order address aggregated in the current build
+    pub last_order: Pubkey,
+
+    /// Orders aggregated in the current build
+    pub build_order_count: u32,
+
+    /// Slot at which the current build started
+    pub build_started_slot: u64,
+
+    /// Published bid size bucket per level (0 = empty, 1 = '0-1', 2 = '1-10',
+    /// 3 = '10-100', 4 = '100+')
+    pub bid_buckets: [u8; BookSnapshot::DEPTH_LEVELS],
+
+    /// Published ask size bucket per level
+    pub ask_buckets: [u8; BookSnapshot::DEPTH_LEVELS],
+
+    /// Dollar price of level 0 in the published snapshot
+    pub base_level: u64,
+
+    /// Orders aggregated into the published snapshot
+    pub order_count: u32,
+
+    /// Slot at which the published snapshot's build started (0 if none yet)
+    pub published_slot: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl BookSnapshot {
+    pub const SEED_PREFIX: &'static [u8] = b"book_snapshot";
+
+    /// Price levels per snapshot, centered on the mark price (must match the circuit)
+    pub const DEPTH_LEVELS: usize = 16;
+
+    /// Encrypted running totals: one bid and one ask total per level
+    pub const DEPTH_ENCRYPTED_FIELDS: usize = 32;
+
+    /// Width of a price level: $1 in 1e6 fixed-point
+    pub const PRICE_UNIT: u64 = 1_000_000;
+
+    /// Space calculation for account
+    /// discriminator(8) + interval_slots(8) + building(1) + in_flight(1)
+    /// + in_flight_offset(8) + finalizing(1) + encrypted_depth(1024) + depth_nonce(16)
+    /// + build_base_level(8) + last_order(32) + build_order_count(4)
+    /// + build_started_slot(8) + bid_buckets(16) + ask_buckets(16) + base_level(8)
+    /// + order_count(4) + published_slot(8) + bump(1) = 1172 bytes
+    pub const SPACE: usize =
+        8 + 8 + 1 + 1 + 8 + 1 + 1024 + 16 + 8 + 32 + 4 + 8 + 16 + 16 + 8 + 4 + 8 + 1;
+
+    /// Whether a new build may start at `current_slot`
+    pub fn is_due(&self, current_slot: u64) -> bool {
+        self.published_slot == 0
+            || current_slot.saturating_sub(self.published_slot) >= self.interval_slots
+    }
+
+    /// Dollar price of level 0 for a snapshot centered on `mark_price`
+    pub fn base_level_for(mark_price: u64) -> u64 {
+        let mark_level = (mark_price + Self::PRICE_UNIT / 2) / Self::PRICE_UNIT;
+        mark_level.saturating_sub(Self::DEPTH_LEVELS as u64 / 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_level_centers_on_mark_price() {
+        let half = BookSnapshot::DEPTH_LEVELS as u64 / 2;
+
+        // $100.40 rounds to level 100
+        assert_eq!(BookSnapshot::base_level_for(100_400_000), 100 - half);
+        // $100.50 rounds up to level 101
+        assert_eq!(BookSnapshot::base_level_for(100_500_000), 101 - half);
+    }
+
+    #[test]
+    fn base_level_saturates_at_zero() {
+        assert_eq!(BookSnapshot::base_level_for(0), 0);
+        assert_eq!(BookSnapshot::base_level_for(3_000_000), 0);
+    }
+}
@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+
+/// Lifecycle of a fill produced by the matching circuit
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FillStatus {
+    /// Match queued, waiting for the MPC callback
+    Pending,
+    /// Orders crossed, encrypted fill ready for settlement
+    Matched,
+    /// Orders did not cross, nothing to settle
+    NoMatch,
+    /// Settlement queued, waiting for the MPC callback
+    Settling,
+    /// Balances updated
+    Settled,
+    /// Settlement rejected inside MPC (e.g. insufficient collateral)
+    Failed,
+    /// Settlement failed and the fill size went back to both orders
+    Returned,
+}
+
+/// PDA holding the encrypted result of matching two orders.
+///
+/// Address: seeds = [b"fill", match computation_offset (le bytes)], bump stored in `bump`.
+///
+/// **PRIVACY:** Fill price and size are stored as `Enc<Mxe, Fill>` ciphertexts
+/// and only ever consumed inside MPC by `settle_trade`.
+#[account]
+pub struct Fill {
+    /// Buy order that was matched
+    pub buy_order: Pubkey,
+
+    /// Sell order that was matched
+    pub sell_order: Pubkey,
+
+    /// Buyer's margin account
+    pub buyer_margin: Pubkey,
+
+    /// Seller's margin account
+    pub seller_margin: Pubkey,
+
+    pub status: FillStatus,
+
+    /// Encrypted fill fields: price, size
+    pub encrypted_fill: [[u8; 32]; 2],
+
+    /// Nonce for `encrypted_fill`
+    pub fill_nonce: u128,
+
+    /// Slot at which the match was queued
+    pub created_slot: u64,
+
+    /// Whether the buy order was the maker (resting) side; decides which
+    /// counterparty pays the taker fee
+    pub buyer_is_maker: bool,
+
+    /// Computation account of the settlement in flight, which also holds the
+    /// protocol fee lock for it (default pubkey if none)
+    pub settling_computation: Pubkey,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl Fill {
+    pub const SEED_PREFIX: &'static [u8] = b"fill";
+
+    /// Space calculation for account
+    /// discriminator(8) + buy_order(32) + sell_order(32) + buyer_margin(32) + seller_margin(32)
+    /// + status(1) + encrypted_fill(64) + fill_nonce(16) + created_slot(8) + buyer_is_maker(1)
+    /// + settling_computation(32) + bump(1) = 259 bytes
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 1 + 64 + 16 + 8 + 1 + 32 + 1;
+
+    /// Hand the fill to the settlement queued under `computation`
+    pub fn start_settling(&mut self, computation: Pubkey) {
+        self.status = FillStatus::Settling;
+        self.settling_computation = computation;
+    }
+
+    /// Whether `computation` is the settlement the fill is waiting on.
+    /// A settlement that was reset (and possibly queued again) must not
+    /// apply its result.
+    pub fn is_settling_under(&self, computation: &Pubkey) -> bool {
+        self.status == FillStatus::Settling && self.settling_computation == *computation
+    }
+
+    /// Leave the settling state with `status`
+    pub fn finish_settling(&mut self, status: FillStatus) {
+        self.status = status;
+        self.settling_computation = Pubkey::default();
+    }
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+/// Protocol insurance fund.
+///
+/// Address: seeds = [b"insurance_fund"], bump stored in `bump`.
+///
+/// Holds wZEC in `vault`, an ATA owned by this PDA. The fund receives its
+/// share of every liquidation penalty (`Market::insurance_fund_share_bps`)
+/// and covers the bad debt of bankrupt accounts first, before any loss is
+/// socialized. Its balance is public.
+///
+/// Covering bad debt is an accounting entry: the written-off debt was owed to
+/// the winning side, whose encrypted balances already hold the credit, so the
+/// tokens stay in the vault backing it and `total_paid_out` is booked against
+/// the fund. Only the uncommitted rest can cover further bad debt.
+#[account]
+pub struct InsuranceFund {
+    /// Token account holding the fund (ATA owned by this PDA)
+    pub vault: Pubkey,
+
+    /// Cumulative liquidation penalties paid into the fund
+    pub total_received: u64,
+
+    /// Cumulative bad debt covered by the fund, still held in `vault`
+    pub total_paid_out: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl InsuranceFund {
+    pub const SEED_PREFIX: &'static [u8] = b"insurance_fund";
+
+    /// Space calculation for account
+    /// discriminator(8) + vault(32) + total_received(8) + total_paid_out(8)
+    /// + bump(1) = 57 bytes
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1;
+
+    /// Part of the vault balance not yet committed to covered bad debt
+    pub fn available(&self, vault_amount: u64) -> u64 {
+        vault_amount.saturating_sub(self.total_paid_out)
+    }
+}
@@ -1,17 +1,25 @@
 use anchor_lang::prelude::*;
 
+use crate::error::ErrorCode;
+use crate::state::market::Market;
+
 /// PDA that tracks a user's margin information.
 ///
 /// Address: seeds = [b"margin", owner_pubkey], bump stored in `bump`.
 ///
 /// **PRIVACY:** All balance fields are encrypted using Arcium MPC.
 /// Only the MPC cluster can decrypt and perform operations on balances.
-/// Users can decrypt their own balances using viewing keys (to be implemented).
+/// Results computed for the owner (e.g. the margin ratio) are encrypted for
+/// the owner's registered viewing key.
 #[account]
 pub struct MarginAccount {
     /// The wallet that owns this margin account.
     pub owner: Pubkey,
 
+    /// x25519 public key the owner decrypts results with
+    /// (all zeros means no viewing key is registered)
+    pub viewing_key: [u8; 32],
+
     /// Encrypted collateral balance (ciphertext)
     /// Updated via Arcium MPC computations
     pub encrypted_collateral: [u8; 32],
@@ -24,10 +32,63 @@ pub struct MarginAccount {
     /// Used by MPC to decrypt and re-encrypt balances
     pub nonce: u128,
 
+    /// Encrypted net position (size, side, entry notional)
+    /// Updated via Arcium MPC computations when fills settle
+    pub encrypted_position: [[u8; 32]; 3],
+
+    /// Nonce for `encrypted_position`
+    pub position_nonce: u128,
+
     /// Flag indicating if this account is liquidatable (set by Arcium health check callback).
     /// This can remain public as it's a boolean flag set by MPC
     pub is_liquidatable: bool,
 
+    /// Slot at which the health check behind `is_liquidatable` was queued
+    pub health_check_slot: u64,
+
+    /// Computation offset of the health check behind `is_liquidatable`
+    pub health_check_offset: u64,
+
+    /// Version of the account's balances and position. Bumped by every
+    /// callback that changes them, so a health check can be tied to the
+    /// exact state it was computed from.
+    pub state_version: u64,
+
+    /// `state_version` the health check behind `is_liquidatable` was computed from
+    pub health_check_version: u64,
+
+    /// `Market::long_loss_index` last charged to this account
+    pub long_loss_snapshot: u64,
+
+    /// `Market::short_loss_index` last charged to this account
+    pub short_loss_snapshot: u64,
+
+    /// Set when a liquidation found the account bankrupt beyond what the
+    /// insurance fund covers; the opposite side must be deleveraged next
+    pub adl_pending: bool,
+
+    /// Set when an auto-deleveraging round ran against the account's
+    /// current state; what is left of its bad debt may then be socialized
+    pub adl_completed: bool,
+
+    /// Sequence number of the last order submitted from this account.
+    /// Every order must carry `order_sequence + 1` inside its ciphertext,
+    /// so a captured order bundle cannot be replayed.
+    pub order_sequence: u64,
+
+    /// Number of results encrypted for the owner's viewing key so far.
+    /// Each one takes the next value as its nonce, so no two results are
+    /// ever encrypted under the same key and nonce.
+    pub viewing_nonce: u64,
+
+    /// Tokens received into the vault but not yet credited to the encrypted
+    /// collateral. `queue_deposit` can only credit up to this amount.
+    pub pending_deposit: u64,
+
+    /// Tokens already debited from the encrypted collateral by a successful
+    /// `withdraw_collateral` computation, not yet transferred out of the vault
+    pub approved_withdrawal: u64,
+
     /// PDA bump seed.
     pub bump: u8,
 }
@@ -36,7 +97,192 @@ impl MarginAccount {
     pub const SEED_PREFIX: &'static [u8] = b"margin";
 
     /// Space calculation for account
-    /// discriminator(8) + owner(32) + encrypted_collateral(32) + encrypted_debt(32)
-    /// + nonce(16) + is_liquidatable(1) + bump(1) = 122 bytes
-    pub const SPACE: usize = 8 + 32 + 32 + 32 + 16 + 1 + 1;
+    /// discriminator(8) + owner(32) + viewing_key(32) + encrypted_collateral(32)
+    /// + encrypted_debt(32) + nonce(16) + encrypted_position(96) + position_nonce(16)
+    /// + is_liquidatable(1) + health_check_slot(8) + health_check_offset(8)
+    /// + state_version(8) + health_check_version(8) + long_loss_snapshot(8)
+    /// + short_loss_snapshot(8) + adl_pending(1) + adl_completed(1)
+    /// + order_sequence(8) + viewing_nonce(8) + pending_deposit(8)
+    /// + approved_withdrawal(8) + bump(1) = 348 bytes
+    pub const SPACE: usize = 8
+        + 32
+        + 32
+        + 32
+        + 32
+        + 16
+        + 96
+        + 16
+        + 1
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 1
+        + 1
+        + 8
+        + 8
+        + 8
+        + 8
+        + 1;
+
+    /// Consume the next order sequence number.
+    /// The number is used up even if the order is later rejected by MPC,
+    /// so every submission moves the sequence forward.
+    pub fn next_order_sequence(&mut self) -> Result<u64> {
+        self.order_sequence = self
+            .order_sequence
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(self.order_sequence)
+    }
+
+    /// Whether the owner has registered a viewing key.
+    /// Encrypting for the all-zero key yields an all-zero shared secret that
+    /// anyone can use, so nothing private may be encrypted for it.
+    pub fn has_viewing_key(&self) -> bool {
+        self.viewing_key != [0u8; 32]
+    }
+
+    /// Consume the next nonce for a result encrypted for the viewing key
+    pub fn next_viewing_nonce(&mut self) -> Result<u128> {
+        self.viewing_nonce = self
+            .viewing_nonce
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(self.viewing_nonce as u128)
+    }
+
+    /// Record a health check result computed from state read at `check_slot`.
+    /// Returns false (and records nothing) if a more recent result is already
+    /// recorded, since callbacks can land out of order, or if the balances or
+    /// position changed after the check was queued.
+    pub fn record_health_check(
+        &mut self,
+        is_liquidatable: bool,
+        check_slot: u64,
+        computation_offset: u64,
+        state_version: u64,
+    ) -> bool {
+        if check_slot < self.health_check_slot || state_version != self.state_version {
+            return false;
+        }
+        self.is_liquidatable = is_liquidatable;
+        self.health_check_slot = check_slot;
+        self.health_check_offset = computation_offset;
+        self.health_check_version = state_version;
+        true
+    }
+
+    /// Whether every socialized loss has been charged to this account.
+    /// The side is private, so both indexes must be current before the
+    /// account's balances or position are used.
+    pub fn has_current_loss_index(&self, market: &Market) -> bool {
+        self.long_loss_snapshot == market.long_loss_index
+            && self.short_loss_snapshot == market.short_loss_index
+    }
+
+    /// Mark the balances or position as changed.
+    /// Any recorded health check no longer describes the account, so the
+    /// liquidatable flag is cleared until a new check lands, and so are the
+    /// auto-deleveraging flags, which were set for the previous state.
+    pub fn mark_state_changed(&mut self) {
+        self.state_version = self.state_version.wrapping_add(1);
+        self.is_liquidatable = false;
+        self.adl_pending = false;
+        self.adl_completed = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn margin_account() -> MarginAccount {
+        MarginAccount {
+            owner: Pubkey::new_unique(),
+            viewing_key: [0; 32],
+            encrypted_collateral: [0; 32],
+            encrypted_debt: [0; 32],
+            nonce: 0,
+            encrypted_position: [[0; 32]; 3],
+            position_nonce: 0,
+            is_liquidatable: false,
+            health_check_slot: 0,
+            health_check_offset: 0,
+            state_version: 0,
+            health_check_version: 0,
+            long_loss_snapshot: 0,
+            short_loss_snapshot: 0,
+            adl_pending: false,
+            adl_completed: false,
+            order_sequence: 0,
+            viewing_nonce: 0,
+            pending_deposit: 0,
+            approved_withdrawal: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn record_health_check_stores_current_result() {
+        let mut margin = margin_account();
+        margin.state_version = 3;
+
+        assert!(margin.record_health_check(true, 100, 7, 3));
+        assert!(margin.is_liquidatable);
+        assert_eq!(margin.health_check_slot, 100);
+        assert_eq!(margin.health_check_offset, 7);
+        assert_eq!(margin.health_check_version, 3);
+
+        // A later check of the same state replaces it
+        assert!(margin.record_health_check(false, 100, 8, 3));
+        assert!(!margin.is_liquidatable);
+        assert_eq!(margin.health_check_offset, 8);
+    }
+
+    #[test]
+    fn record_health_check_ignores_older_slot() {
+        let mut margin = margin_account();
+        assert!(margin.record_health_check(false, 100, 1, 0));
+
+        assert!(!margin.record_health_check(true, 99, 2, 0));
+        assert!(!margin.is_liquidatable);
+        assert_eq!(margin.health_check_slot, 100);
+        assert_eq!(margin.health_check_offset, 1);
+    }
+
+    #[test]
+    fn record_health_check_ignores_outdated_state() {
+        let mut margin = margin_account();
+        margin.mark_state_changed();
+
+        assert!(!margin.record_health_check(true, 100, 1, 0));
+        assert!(!margin.is_liquidatable);
+        assert_eq!(margin.health_check_slot, 0);
+    }
+
+    #[test]
+    fn mark_state_changed_clears_flags() {
+        let mut margin = margin_account();
+        assert!(margin.record_health_check(true, 100, 1, 0));
+        margin.adl_pending = true;
+        margin.adl_completed = true;
+
+        margin.mark_state_changed();
+        assert_eq!(margin.state_version, 1);
+        assert!(!margin.is_liquidatable);
+        assert!(!margin.adl_pending);
+        assert!(!margin.adl_completed);
+    }
+
+    #[test]
+    fn has_viewing_key_rejects_zero_key() {
+        let mut margin = margin_account();
+        assert!(!margin.has_viewing_key());
+
+        margin.viewing_key[0] = 9;
+        assert!(margin.has_viewing_key());
+    }
 }
@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+
+/// Market configuration for the ZEC perpetual.
+///
+/// Address: seeds = [b"market"], bump stored in `bump`.
+///
+/// The mark price is public: it is pushed by the configured oracle and
+/// consumed as a plaintext input by MPC computations (e.g. trigger evaluation).
+#[account]
+pub struct Market {
+    /// Authority that can update market parameters.
+    pub authority: Pubkey,
+
+    /// Oracle key allowed to push mark price updates.
+    pub oracle: Pubkey,
+
+    /// Latest mark price (fixed-point, 1e6 = $1.00)
+    pub mark_price: u64,
+
+    /// Slot at which `mark_price` was last updated
+    pub mark_price_slot: u64,
+
+    /// Minimum price increment; order prices must be a multiple of this
+    pub tick_size: u64,
+
+    /// Minimum size increment; order sizes must be a multiple of this
+    pub lot_size: u64,
+
+    /// Maximum distance of an order price from the mark price, in basis points
+    pub price_band_bps: u16,
+
+    /// Fee charged to the maker of a fill, in basis points of the notional.
+    /// Negative values are rebates paid to the maker out of the taker fee.
+    pub maker_fee_bps: i16,
+
+    /// Fee charged to the taker of a fill, in basis points of the notional
+    pub taker_fee_bps: u16,
+
+    /// Minimum equity, in basis points of the position value at the mark
+    /// price, below which an account becomes liquidatable
+    pub maintenance_margin_bps: u16,
+
+    /// Penalty charged on the value of a position closed by liquidation,
+    /// in basis points. Always below `maintenance_margin_bps`, so closing
+    /// part of a position restores the margin.
+    pub liquidation_penalty_bps: u16,
+
+    /// Share of each liquidation penalty paid into the insurance fund, in
+    /// basis points; the rest is the liquidator's reward
+    pub insurance_fund_share_bps: u16,
+
+    /// Maximum age, in slots, of the health check behind a liquidation
+    pub health_check_max_age_slots: u64,
+
+    /// Cumulative bad debt socialized onto longs, per unit of size
+    /// (1e6 fixed-point). Charged to each account in MPC.
+    pub long_loss_index: u64,
+
+    /// Cumulative bad debt socialized onto shorts, per unit of size
+    pub short_loss_index: u64,
+
+    /// PDA bump seed.
+    pub bump: u8,
+}
+
+impl Market {
+    pub const SEED_PREFIX: &'static [u8] = b"market";
+
+    /// Maximum age of the mark price (in slots) before it is considered stale.
+    /// ~1 minute at 400ms slots.
+    pub const MAX_PRICE_AGE_SLOTS: u64 = 150;
+
+    /// Upper bound for `price_band_bps` (100%)
+    pub const MAX_PRICE_BAND_BPS: u16 = 10_000;
+
+    /// Upper bound for maker and taker fee rates (10%)
+    pub const MAX_FEE_BPS: u16 = 1_000;
+
+    /// Maintenance margin a new market starts with (5%)
+    pub const DEFAULT_MAINTENANCE_MARGIN_BPS: u16 = 500;
+
+    /// Upper bound for `maintenance_margin_bps` (100%)
+    pub const MAX_MAINTENANCE_MARGIN_BPS: u16 = 10_000;
+
+    /// Liquidation penalty a new market starts with (2.5%)
+    pub const DEFAULT_LIQUIDATION_PENALTY_BPS: u16 = 250;
+
+    /// Insurance fund share a new market starts with (50%)
+    pub const DEFAULT_INSURANCE_FUND_SHARE_BPS: u16 = 5_000;
+
+    /// Upper bound for `insurance_fund_share_bps` (100%)
+    pub const MAX_INSURANCE_FUND_SHARE_BPS: u16 = 10_000;
+
+    /// Health check age limit a new market starts with (~1 minute)
+    pub const DEFAULT_HEALTH_CHECK_MAX_AGE_SLOTS: u64 = 150;
+
+    /// Space calculation for account
+    /// discriminator(8) + authority(32) + oracle(32) + mark_price(8) + mark_price_slot(8)
+    /// + tick_size(8) + lot_size(8) + price_band_bps(2) + maker_fee_bps(2) + taker_fee_bps(2)
+    /// + maintenance_margin_bps(2) + liquidation_penalty_bps(2) + insurance_fund_share_bps(2)
+    /// + health_check_max_age_slots(8) + long_loss_index(8) + short_loss_index(8)
+    /// + bump(1) = 141 bytes
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 2 + 2 + 2 + 2 + 2 + 2 + 8 + 8 + 8 + 1;
+
+    /// Check that the mark price is set and recent enough to act on
+    pub fn is_price_fresh(&self, current_slot: u64) -> bool {
+        self.mark_price > 0
+            && current_slot.saturating_sub(self.mark_price_slot) <= Self::MAX_PRICE_AGE_SLOTS
+    }
+
+    /// Split a liquidation penalty into (liquidator reward, insurance fund share)
+    pub fn split_liquidation_penalty(&self, penalty: u64) -> (u64, u64) {
+        let fund_share = (penalty as u128 * self.insurance_fund_share_bps as u128
+            / Self::MAX_INSURANCE_FUND_SHARE_BPS as u128) as u64;
+        (penalty - fund_share, fund_share)
+    }
+
+    /// Fee rates as passed to the settlement circuits:
+    /// (taker_bps, maker_bps magnitude, whether the maker rate is a rebate)
+    pub fn fee_rates(&self) -> (u64, u64, bool) {
+        (
+            self.taker_fee_bps as u64,
+            self.maker_fee_bps.unsigned_abs() as u64,
+            self.maker_fee_bps < 0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market(insurance_fund_share_bps: u16) -> Market {
+        Market {
+            authority: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+            mark_price: 0,
+            mark_price_slot: 0,
+            tick_size: 1,
+            lot_size: 1,
+            price_band_bps: 0,
+            maker_fee_bps: 0,
+            taker_fee_bps: 0,
+            maintenance_margin_bps: Market::DEFAULT_MAINTENANCE_MARGIN_BPS,
+            liquidation_penalty_bps: Market::DEFAULT_LIQUIDATION_PENALTY_BPS,
+            insurance_fund_share_bps,
+            health_check_max_age_slots: Market::DEFAULT_HEALTH_CHECK_MAX_AGE_SLOTS,
+            long_loss_index: 0,
+            short_loss_index: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn split_liquidation_penalty_by_share() {
+        let half = market(Market::DEFAULT_INSURANCE_FUND_SHARE_BPS);
+        assert_eq!(half.split_liquidation_penalty(1_000), (500, 500));
+
+        let quarter = market(2_500);
+        assert_eq!(quarter.split_liquidation_penalty(1_000), (750, 250));
+    }
+
+    #[test]
+    fn split_liquidation_penalty_rounds_in_favor_of_liquidator() {
+        let third = market(3_333);
+        // 3 * 33.33% = 0.9999, floored to 0 for the fund
+        assert_eq!(third.split_liquidation_penalty(3), (3, 0));
+        assert_eq!(third.split_liquidation_penalty(10_000), (6_667, 3_333));
+    }
+
+    #[test]
+    fn split_liquidation_penalty_at_bounds() {
+        assert_eq!(market(0).split_liquidation_penalty(1_000), (1_000, 0));
+        assert_eq!(
+            market(Market::MAX_INSURANCE_FUND_SHARE_BPS).split_liquidation_penalty(1_000),
+            (0, 1_000)
+        );
+        assert_eq!(
+            market(Market::MAX_INSURANCE_FUND_SHARE_BPS).split_liquidation_penalty(u64::MAX),
+            (0, u64::MAX)
+        );
+    }
+}
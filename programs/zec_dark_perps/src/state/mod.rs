@@ -1,5 +1,17 @@
+pub mod adl_ranking;
+pub mod book_snapshot;
 pub mod bridge_config;
+pub mod fill;
+pub mod insurance_fund;
 pub mod margin_account;
+pub mod market;
+pub mod order;
+pub mod order_commitment;
+pub mod order_group;
+pub mod program_config;
+pub mod protocol_fees;
+pub mod rfq;
 pub mod signer_account;
+pub mod twap_order;
 
 pub use signer_account::SignerAccount;
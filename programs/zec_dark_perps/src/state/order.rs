@@ -0,0 +1,233 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+/// Kind of order stored on-chain
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OrderKind {
+    /// Plain limit order, live as soon as it is confirmed
+    Limit,
+    /// Stop-loss / take-profit order, live only once its encrypted trigger fires
+    Trigger,
+    /// Immediate order with an encrypted worst acceptable price; always the
+    /// taker, and any remainder is cancelled instead of resting
+    Market,
+    /// Child of a TWAP parent; trades like a limit order and is topped up
+    /// with a new slice on every release
+    Twap,
+}
+
+/// Lifecycle of an on-chain order
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OrderStatus {
+    /// Account created, waiting for the MPC callback
+    Pending,
+    /// Trigger order waiting for its trigger condition
+    Untriggered,
+    /// Live order, available for matching
+    Open,
+    /// Fully filled
+    Filled,
+    /// Cancelled by the owner or by the protocol
+    Cancelled,
+    /// Failed validation inside MPC
+    Rejected,
+}
+
+/// Reason an order was rejected by the `submit_order` circuit.
+///
+/// See `from_status` for the mapping from circuit status codes (0 = accepted).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OrderRejectReason {
+    /// Size is zero
+    InvalidSize,
+    /// Side is not 0 (short) or 1 (long)
+    InvalidSide,
+    /// Price is not a multiple of the market tick size
+    OffTick,
+    /// Size is not a multiple of the market lot size
+    OffLot,
+    /// Price is too far from the mark price
+    OutsidePriceBand,
+    /// Iceberg display size is larger than the order or off the lot size
+    InvalidDisplaySize,
+    /// Encrypted sequence number is not the next one for the margin account
+    /// (replayed or out-of-order submission)
+    StaleSequence,
+    /// Self-trade prevention mode is not one of the supported modes
+    InvalidStpMode,
+    /// Status code not known to this program version
+    Unknown,
+}
+
+impl OrderRejectReason {
+    /// Map a revealed circuit status code to a rejection reason.
+    /// Returns `None` when the order was accepted.
+    pub fn from_status(status: u8) -> Option<Self> {
+        match status {
+            0 => None,
+            1 => Some(Self::InvalidSize),
+            2 => Some(Self::InvalidSide),
+            3 => Some(Self::OffTick),
+            4 => Some(Self::OffLot),
+            5 => Some(Self::OutsidePriceBand),
+            6 => Some(Self::InvalidDisplaySize),
+            7 => Some(Self::StaleSequence),
+            8 => Some(Self::InvalidStpMode),
+            _ => Some(Self::Unknown),
+        }
+    }
+}
+
+/// PDA holding an encrypted order.
+///
+/// Address: seeds = [b"order", margin_account, order_id (le bytes)], bump stored in `bump`.
+///
+/// **PRIVACY:** Order details are stored as `Enc<Mxe, _>` ciphertexts.
+/// Only the kind and status are public.
+#[account]
+pub struct Order {
+    /// The wallet that owns this order
+    pub owner: Pubkey,
+
+    /// Margin account the order trades against
+    pub margin_account: Pubkey,
+
+    /// Order identifier (computation offset used to create the order)
+    pub order_id: u64,
+
+    /// Per-margin-account sequence number the order was submitted with
+    pub sequence: u64,
+
+    pub kind: OrderKind,
+
+    pub status: OrderStatus,
+
+    /// Encrypted order fields: size, price, side, display_size, visible_size,
+    /// stp_mode, owner_id
+    /// `display_size` > 0 marks an iceberg order; only `visible_size` is ever matched
+    pub encrypted_order: [[u8; 32]; 7],
+
+    /// Nonce for `encrypted_order`
+    pub order_nonce: u128,
+
+    /// Encrypted trigger fields: trigger_price, direction (zero for limit orders)
+    pub encrypted_trigger: [[u8; 32]; 2],
+
+    /// Nonce for `encrypted_trigger`
+    pub trigger_nonce: u128,
+
+    /// Slot at which the order was created (the commitment slot for orders
+    /// submitted through commit-reveal); used for price-time priority
+    pub created_slot: u64,
+
+    /// Slot at which the order was submitted (the reveal slot for orders
+    /// submitted through commit-reveal); starts a market order's matching window
+    pub submitted_slot: u64,
+
+    /// One-cancels-other group this order belongs to (default pubkey if none)
+    pub oco_group: Pubkey,
+
+    /// Account whose computation is rewriting `encrypted_order` (the fill of
+    /// a match, or the TWAP parent of a slice release); default pubkey if none
+    pub in_flight: Pubkey,
+
+    /// Slot at which `in_flight` was taken
+    pub in_flight_slot: u64,
+
+    /// PDA bump seed.
+    pub bump: u8,
+}
+
+impl Order {
+    pub const SEED_PREFIX: &'static [u8] = b"order";
+
+    /// Slots a market order stays matchable before its remainder can be cancelled
+    pub const MARKET_ORDER_TTL_SLOTS: u64 = 150;
+
+    /// Slots after which an in-flight computation whose callback never
+    /// arrived no longer holds the order
+    pub const IN_FLIGHT_TIMEOUT_SLOTS: u64 = 150;
+
+    /// Space calculation for account
+    /// discriminator(8) + owner(32) + margin_account(32) + order_id(8) + sequence(8)
+    /// + kind(1) + status(1) + encrypted_order(224) + order_nonce(16) + encrypted_trigger(64)
+    /// + trigger_nonce(16) + created_slot(8) + submitted_slot(8) + oco_group(32)
+    /// + in_flight(32) + in_flight_slot(8) + bump(1) = 499 bytes
+    pub const SPACE: usize =
+        8 + 32 + 32 + 8 + 8 + 1 + 1 + 224 + 16 + 64 + 16 + 8 + 8 + 32 + 32 + 8 + 1;
+
+    /// Owner identifier sealed into the encrypted order for self-trade prevention:
+    /// the first 16 bytes of the margin account address
+    pub fn stp_owner_id(margin_account: &Pubkey) -> u128 {
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&margin_account.as_ref()[..16]);
+        u128::from_le_bytes(bytes)
+    }
+
+    /// Whether a market order is past its matching window at `current_slot`
+    pub fn is_market_expired(&self, current_slot: u64) -> bool {
+        self.kind == OrderKind::Market
+            && current_slot.saturating_sub(self.submitted_slot) > Self::MARKET_ORDER_TTL_SLOTS
+    }
+
+    /// Mark the order as held by `holder`'s computation.
+    /// Each computation rewrites `encrypted_order` from the ciphertext it was
+    /// queued with, so only one may be in flight at a time.
+    pub fn lock(&mut self, holder: Pubkey, current_slot: u64) -> Result<()> {
+        require!(
+            self.in_flight == Pubkey::default()
+                || current_slot.saturating_sub(self.in_flight_slot) > Self::IN_FLIGHT_TIMEOUT_SLOTS,
+            ErrorCode::OrderInFlight
+        );
+        self.in_flight = holder;
+        self.in_flight_slot = current_slot;
+        Ok(())
+    }
+
+    /// Release the order if `holder` still holds it. Returns false if the
+    /// hold expired and was taken over, in which case the caller's result
+    /// must be dropped.
+    pub fn unlock(&mut self, holder: &Pubkey) -> bool {
+        if self.in_flight != *holder {
+            return false;
+        }
+        self.in_flight = Pubkey::default();
+        true
+    }
+
+    /// Whether the order is a leg of a one-cancels-other group
+    pub fn has_oco_group(&self) -> bool {
+        self.oco_group != Pubkey::default()
+    }
+
+    /// Initialize a freshly created order account in `Pending` status.
+    /// Encrypted fields are filled in by the MPC callback.
+    pub fn initialize(
+        &mut self,
+        owner: Pubkey,
+        margin_account: Pubkey,
+        order_id: u64,
+        sequence: u64,
+        kind: OrderKind,
+        bump: u8,
+    ) -> Result<()> {
+        self.owner = owner;
+        self.margin_account = margin_account;
+        self.order_id = order_id;
+        self.sequence = sequence;
+        self.kind = kind;
+        self.status = OrderStatus::Pending;
+        self.encrypted_order = [[0u8; 32]; 7];
+        self.order_nonce = 0;
+        self.encrypted_trigger = [[0u8; 32]; 2];
+        self.trigger_nonce = 0;
+        self.created_slot = Clock::get()?.slot;
+        self.submitted_slot = self.created_slot;
+        self.oco_group = Pubkey::default();
+        self.in_flight = Pubkey::default();
+        self.in_flight_slot = 0;
+        self.bump = bump;
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+/// PDA holding a hash commitment to an encrypted order (commit-reveal mode).
+///
+/// Address: seeds = [b"order_commitment", margin_account, commitment_id (le bytes)],
+/// bump stored in `bump`.
+///
+/// The commitment is the hash of the order ciphertexts, encryption pubkey and
+/// nonce (see `order_commitment_hash`). When the order is revealed in a later
+/// slot it takes the commitment slot as its priority, so reordering reveals
+/// based on their contents gains nothing.
+///
+/// Priority from the commitment slot is an option on the market until the
+/// reveal, so the window is kept short, the order sequence is reserved at
+/// commit, and the commitment holds a bond (`COMMITMENT_BOND_LAMPORTS`) that
+/// is only refunded by revealing. A commitment that is withdrawn or left to
+/// expire forfeits its bond and rent to the config authority.
+#[account]
+pub struct OrderCommitment {
+    /// The wallet that posted the commitment
+    pub owner: Pubkey,
+
+    /// Margin account the committed order will trade against
+    pub margin_account: Pubkey,
+
+    /// Hash of the encrypted order
+    pub commitment: [u8; 32],
+
+    /// Slot at which the commitment was posted
+    pub committed_slot: u64,
+
+    /// Order sequence reserved for the committed order
+    pub sequence: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl OrderCommitment {
+    pub const SEED_PREFIX: &'static [u8] = b"order_commitment";
+
+    /// Slots after the commitment within which the order must be revealed
+    pub const MAX_REVEAL_DELAY_SLOTS: u64 = 16;
+
+    /// Lamports locked in the commitment on top of its rent
+    pub const COMMITMENT_BOND_LAMPORTS: u64 = 10_000_000;
+
+    /// Space calculation for account
+    /// discriminator(8) + owner(32) + margin_account(32) + commitment(32)
+    /// + committed_slot(8) + sequence(8) + bump(1) = 121 bytes
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 1;
+
+    /// Whether the reveal window has closed at `current_slot`
+    pub fn is_expired(&self, current_slot: u64) -> bool {
+        current_slot.saturating_sub(self.committed_slot) > Self::MAX_REVEAL_DELAY_SLOTS
+    }
+
+    /// Check that the order can be revealed at `current_slot`:
+    /// strictly after the commitment slot and before the window closes
+    pub fn check_reveal_window(&self, current_slot: u64) -> Result<()> {
+        require!(
+            current_slot > self.committed_slot,
+            ErrorCode::RevealTooEarly
+        );
+        require!(!self.is_expired(current_slot), ErrorCode::CommitmentExpired);
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+
+/// Lifecycle of a one-cancels-other order group
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OrderGroupStatus {
+    /// Neither leg has executed yet
+    Active,
+    /// One leg executed (filled or triggered) and the other was cancelled
+    Resolved,
+}
+
+/// PDA linking two orders with one-cancels-other semantics, e.g. a
+/// take-profit and a stop-loss bracketing a position.
+///
+/// Address: seeds = [b"order_group", margin_account, group_id (le bytes)],
+/// bump stored in `bump`.
+///
+/// The first leg to receive a fill from `match_orders` or to fire in
+/// `evaluate_triggers` resolves the group and cancels the other leg.
+#[account]
+pub struct OrderGroup {
+    /// The wallet that owns both legs
+    pub owner: Pubkey,
+
+    /// Margin account both legs trade against
+    pub margin_account: Pubkey,
+
+    /// The two linked orders
+    pub legs: [Pubkey; 2],
+
+    pub status: OrderGroupStatus,
+
+    /// Leg that resolved the group (default pubkey while active)
+    pub resolved_by: Pubkey,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl OrderGroup {
+    pub const SEED_PREFIX: &'static [u8] = b"order_group";
+
+    /// Space calculation for account
+    /// discriminator(8) + owner(32) + margin_account(32) + legs(64) + status(1)
+    /// + resolved_by(32) + bump(1) = 170 bytes
+    pub const SPACE: usize = 8 + 32 + 32 + 64 + 1 + 32 + 1;
+
+    /// The other leg of the group, or `None` if `leg` is not part of it
+    pub fn sibling_of(&self, leg: &Pubkey) -> Option<Pubkey> {
+        if *leg == self.legs[0] {
+            Some(self.legs[1])
+        } else if *leg == self.legs[1] {
+            Some(self.legs[0])
+        } else {
+            None
+        }
+    }
+
+    /// Whether `leg` may still execute: the group is active, or `leg` is
+    /// the one that already resolved it (further partial fills)
+    pub fn allows(&self, leg: &Pubkey) -> bool {
+        match self.status {
+            OrderGroupStatus::Active => true,
+            OrderGroupStatus::Resolved => self.resolved_by == *leg,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order_group() -> OrderGroup {
+        OrderGroup {
+            owner: Pubkey::new_unique(),
+            margin_account: Pubkey::new_unique(),
+            legs: [Pubkey::new_unique(), Pubkey::new_unique()],
+            status: OrderGroupStatus::Active,
+            resolved_by: Pubkey::default(),
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn active_group_allows_both_legs() {
+        let group = order_group();
+        assert!(group.allows(&group.legs[0]));
+        assert!(group.allows(&group.legs[1]));
+    }
+
+    #[test]
+    fn resolved_group_allows_only_resolving_leg() {
+        let mut group = order_group();
+        group.status = OrderGroupStatus::Resolved;
+        group.resolved_by = group.legs[1];
+
+        assert!(group.allows(&group.legs[1]));
+        assert!(!group.allows(&group.legs[0]));
+        assert!(!group.allows(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn sibling_of_returns_other_leg() {
+        let group = order_group();
+        assert_eq!(group.sibling_of(&group.legs[0]), Some(group.legs[1]));
+        assert_eq!(group.sibling_of(&group.legs[1]), Some(group.legs[0]));
+        assert_eq!(group.sibling_of(&Pubkey::new_unique()), None);
+    }
+}
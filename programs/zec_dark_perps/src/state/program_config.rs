@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+/// Program-wide configuration holding the authorized matching engines.
+///
+/// Address: seeds = [b"config"], bump stored in `bump`
+#[account]
+pub struct ProgramConfig {
+    /// Authority that can add/remove matchers
+    pub authority: Pubkey,
+
+    /// Matching engine keys allowed to settle trades
+    /// Only the first `matcher_count` entries are valid
+    pub matchers: [Pubkey; ProgramConfig::MAX_MATCHERS],
+
+    /// Number of registered matchers
+    pub matcher_count: u8,
+
+    /// Callback server receiving MPC outputs too large for a single
+    /// callback transaction (empty = not configured)
+    pub callback_server_url: String,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl ProgramConfig {
+    pub const SEED_PREFIX: &'static [u8] = b"config";
+
+    /// Maximum number of matching engines that can be registered
+    pub const MAX_MATCHERS: usize = 8;
+
+    /// Maximum length of the callback server URL
+    pub const MAX_CALLBACK_URL_LEN: usize = 128;
+
+    /// Computation output bytes that fit in one callback transaction
+    /// next to the callback accounts
+    pub const CALLBACK_TX_OUTPUT_BYTES: usize = 512;
+
+    /// Space calculation for account
+    /// discriminator(8) + authority(32) + matchers(32 * 8) + matcher_count(1)
+    /// + callback_server_url(4 + 128) + bump(1) = 430 bytes
+    pub const SPACE: usize =
+        8 + 32 + 32 * Self::MAX_MATCHERS + 1 + 4 + Self::MAX_CALLBACK_URL_LEN + 1;
+
+    /// Registered matchers
+    pub fn active_matchers(&self) -> &[Pubkey] {
+        &self.matchers[..self.matcher_count as usize]
+    }
+
+    /// Check if a pubkey is an authorized matching engine
+    pub fn is_matcher(&self, pubkey: &Pubkey) -> bool {
+        self.active_matchers().contains(pubkey)
+    }
+
+    /// How a computation output of `output_bytes` is delivered:
+    /// inline in one callback transaction, or through the callback server
+    /// split over as many transactions as needed.
+    /// Returns the callback URL and number of callback transactions for `queue_computation`.
+    pub fn callback_route(&self, output_bytes: usize) -> Result<(Option<String>, u8)> {
+        if output_bytes <= Self::CALLBACK_TX_OUTPUT_BYTES {
+            return Ok((None, 1));
+        }
+        require!(
+            !self.callback_server_url.is_empty(),
+            ErrorCode::CallbackServerNotSet
+        );
+        let txs = output_bytes.div_ceil(Self::CALLBACK_TX_OUTPUT_BYTES);
+        Ok((
+            Some(self.callback_server_url.clone()),
+            u8::try_from(txs).map_err(|_| ErrorCode::MathOverflow)?,
+        ))
+    }
+}
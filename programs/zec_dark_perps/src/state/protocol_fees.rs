@@ -0,0 +1,181 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+/// Encrypted balance of trading fees collected by the protocol.
+///
+/// Address: seeds = [b"protocol_fees"], bump stored in `bump`.
+///
+/// **PRIVACY:** The cumulative fee total is stored as an `Enc<Mxe, ProtocolFees>`
+/// ciphertext and updated by every settlement. It is only revealed, as a
+/// cumulative total, when the config authority sweeps.
+///
+/// The account also holds the encrypted open interest per side
+/// (`Enc<Mxe, OpenInterest>`), which every settlement and liquidation
+/// updates alongside the fees. It is never revealed; it sizes the loss per
+/// unit when bad debt is socialized.
+///
+/// Settlements read and rewrite the same ciphertexts, so only one settlement
+/// may be in flight at a time: each one takes the lock at queue time and
+/// releases it in its callback. A lock whose callback never arrives expires
+/// after `LOCK_TIMEOUT_SLOTS`; a callback that finds its lock taken over
+/// drops its whole result.
+///
+/// This serializes every settlement, liquidation and auto-deleveraging on
+/// the market: at most one computation touching the fee balance or the open
+/// interest is in flight, so throughput is one MPC round trip per queue.
+/// Matching engines should prefer `queue_settle_batch`, which settles up to
+/// `SETTLE_BATCH_FILLS` fills under a single lock.
+#[account]
+pub struct ProtocolFees {
+    /// Encrypted cumulative fees collected (all zeros represents encrypted zero)
+    pub encrypted_fees: [u8; 32],
+
+    /// Nonce for `encrypted_fees`
+    pub fees_nonce: u128,
+
+    /// Encrypted open interest (long, short), in size units
+    /// (all zeros represents encrypted zero)
+    pub encrypted_open_interest: [[u8; 32]; 2],
+
+    /// Nonce for `encrypted_open_interest`
+    pub open_interest_nonce: u128,
+
+    /// Cumulative total revealed by the last sweep
+    pub total_swept: u64,
+
+    /// Slot of the last sweep
+    pub last_sweep_slot: u64,
+
+    /// Settlement currently allowed to update the fee balance: the RFQ, or the
+    /// computation account of a trade or batch settlement, liquidation or
+    /// auto-deleveraging round, since those can be queued again for the same
+    /// fill or margin account
+    pub lock_holder: Pubkey,
+
+    /// Slot at which the lock was taken
+    pub lock_slot: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl ProtocolFees {
+    pub const SEED_PREFIX: &'static [u8] = b"protocol_fees";
+
+    /// Slots after which an unreleased settlement lock can be taken over
+    pub const LOCK_TIMEOUT_SLOTS: u64 = 150;
+
+    /// Space calculation for account
+    /// discriminator(8) + encrypted_fees(32) + fees_nonce(16)
+    /// + encrypted_open_interest(64) + open_interest_nonce(16) + total_swept(8)
+    /// + last_sweep_slot(8) + lock_holder(32) + lock_slot(8) + bump(1) = 193 bytes
+    pub const SPACE: usize = 8 + 32 + 16 + 64 + 16 + 8 + 8 + 32 + 8 + 1;
+
+    /// Take the settlement lock for `holder`
+    pub fn acquire(&mut self, holder: Pubkey, current_slot: u64) -> Result<()> {
+        require!(
+            self.lock_holder == Pubkey::default()
+                || current_slot.saturating_sub(self.lock_slot) > Self::LOCK_TIMEOUT_SLOTS,
+            ErrorCode::FeeBalanceBusy
+        );
+        self.lock_holder = holder;
+        self.lock_slot = current_slot;
+        Ok(())
+    }
+
+    /// Whether `holder` owns a lock that has not expired at `current_slot`
+    pub fn is_held_by(&self, holder: &Pubkey, current_slot: u64) -> bool {
+        self.lock_holder == *holder
+            && current_slot.saturating_sub(self.lock_slot) <= Self::LOCK_TIMEOUT_SLOTS
+    }
+
+    /// Release the lock if `holder` still owns it. Returns false if the lock
+    /// expired and was taken over, in which case the caller's fee update
+    /// must be dropped.
+    pub fn release(&mut self, holder: &Pubkey) -> bool {
+        if self.lock_holder != *holder {
+            return false;
+        }
+        self.lock_holder = Pubkey::default();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protocol_fees() -> ProtocolFees {
+        ProtocolFees {
+            encrypted_fees: [0; 32],
+            fees_nonce: 0,
+            encrypted_open_interest: [[0; 32]; 2],
+            open_interest_nonce: 0,
+            total_swept: 0,
+            last_sweep_slot: 0,
+            lock_holder: Pubkey::default(),
+            lock_slot: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn acquire_takes_free_lock() {
+        let mut fees = protocol_fees();
+        let holder = Pubkey::new_unique();
+
+        fees.acquire(holder, 10).unwrap();
+        assert_eq!(fees.lock_holder, holder);
+        assert_eq!(fees.lock_slot, 10);
+    }
+
+    #[test]
+    fn acquire_rejects_held_lock() {
+        let mut fees = protocol_fees();
+        let holder = Pubkey::new_unique();
+        fees.acquire(holder, 10).unwrap();
+
+        let result = fees.acquire(Pubkey::new_unique(), 10 + ProtocolFees::LOCK_TIMEOUT_SLOTS);
+        assert_eq!(result.unwrap_err(), ErrorCode::FeeBalanceBusy.into());
+        assert_eq!(fees.lock_holder, holder);
+    }
+
+    #[test]
+    fn acquire_takes_over_expired_lock() {
+        let mut fees = protocol_fees();
+        let holder = Pubkey::new_unique();
+        fees.acquire(holder, 10).unwrap();
+
+        let next = Pubkey::new_unique();
+        fees.acquire(next, 11 + ProtocolFees::LOCK_TIMEOUT_SLOTS)
+            .unwrap();
+        assert_eq!(fees.lock_holder, next);
+
+        // The first holder's callback must drop its result
+        assert!(!fees.release(&holder));
+        assert_eq!(fees.lock_holder, next);
+    }
+
+    #[test]
+    fn is_held_by_expires_with_lock() {
+        let mut fees = protocol_fees();
+        let holder = Pubkey::new_unique();
+        fees.acquire(holder, 10).unwrap();
+
+        assert!(fees.is_held_by(&holder, 10 + ProtocolFees::LOCK_TIMEOUT_SLOTS));
+        assert!(!fees.is_held_by(&holder, 11 + ProtocolFees::LOCK_TIMEOUT_SLOTS));
+        assert!(!fees.is_held_by(&Pubkey::new_unique(), 10));
+    }
+
+    #[test]
+    fn release_frees_lock_for_holder() {
+        let mut fees = protocol_fees();
+        let holder = Pubkey::new_unique();
+        fees.acquire(holder, 10).unwrap();
+
+        assert!(fees.release(&holder));
+        assert_eq!(fees.lock_holder, Pubkey::default());
+        fees.acquire(Pubkey::new_unique(), 11).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+/// Lifecycle of a private quote request
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RfqStatus {
+    /// Request queued, waiting for the MPC callback
+    Pending,
+    /// Request stored encrypted, waiting for the responder's quote
+    Open,
+    /// Request failed validation inside MPC
+    Rejected,
+    /// Quote submitted, settlement queued
+    Settling,
+    /// Block trade settled into both margin accounts
+    Settled,
+    /// Quote outside the limit or insufficient collateral
+    Failed,
+    /// Cancelled by the requester
+    Cancelled,
+}
+
+/// PDA holding a private request for quote between two counterparties.
+///
+/// Address: seeds = [b"rfq", requester_margin, computation_offset (le bytes)],
+/// bump stored in `bump`.
+///
+/// **PRIVACY:** Size, side and limit price are stored as `Enc<Mxe, RfqInput>`
+/// ciphertexts. The responder's quote is never stored; it is consumed
+/// directly by the `settle_block_trade` circuit.
+#[account]
+pub struct Rfq {
+    /// Wallet that requested the quote
+    pub requester: Pubkey,
+
+    /// Requester's margin account
+    pub requester_margin: Pubkey,
+
+    /// Only wallet allowed to answer this request
+    pub responder: Pubkey,
+
+    /// Responder's margin account, set when the quote is submitted
+    pub responder_margin: Pubkey,
+
+    pub status: RfqStatus,
+
+    /// Encrypted request fields: size, side, limit_price
+    pub encrypted_request: [[u8; 32]; 3],
+
+    /// Nonce for `encrypted_request`
+    pub request_nonce: u128,
+
+    /// Slot at which the request was created
+    pub created_slot: u64,
+
+    /// Last slot at which a quote is accepted
+    pub expiry_slot: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl Rfq {
+    pub const SEED_PREFIX: &'static [u8] = b"rfq";
+
+    /// Space calculation for account
+    /// discriminator(8) + requester(32) + requester_margin(32) + responder(32)
+    /// + responder_margin(32) + status(1) + encrypted_request(96) + request_nonce(16)
+    /// + created_slot(8) + expiry_slot(8) + bump(1) = 266 bytes
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 1 + 96 + 16 + 8 + 8 + 1;
+
+    /// Whether a quote can still be submitted at `current_slot`
+    pub fn is_expired(&self, current_slot: u64) -> bool {
+        current_slot > self.expiry_slot
+    }
+}
@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+/// Lifecycle of a TWAP parent order
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TwapStatus {
+    /// Account created, waiting for the MPC callback
+    Pending,
+    /// Releasing slices into its child order on every crank
+    Active,
+    /// Every slice has been released
+    Completed,
+    /// Failed validation inside MPC
+    Rejected,
+    /// Cancelled by the owner
+    Cancelled,
+}
+
+/// PDA holding an encrypted TWAP parent order.
+///
+/// Address: seeds = [b"twap", margin_account, order_id (le bytes)], bump stored in `bump`.
+///
+/// **PRIVACY:** Remaining size, slice count and interval are stored as
+/// `Enc<Mxe, TwapData>` ciphertexts. Slices are released into a single child
+/// `Order` (same order_id) that trades in the book like a limit order.
+#[account]
+pub struct TwapOrder {
+    /// The wallet that owns this TWAP order
+    pub owner: Pubkey,
+
+    /// Margin account the child order trades against
+    pub margin_account: Pubkey,
+
+    /// Child order receiving the released slices
+    pub child_order: Pubkey,
+
+    /// Order identifier (computation offset used to create the order)
+    pub order_id: u64,
+
+    pub status: TwapStatus,
+
+    /// Encrypted parent fields: remaining_size, slices_left, interval_slots, next_release_slot
+    pub encrypted_twap: [[u8; 32]; 4],
+
+    /// Nonce for `encrypted_twap`
+    pub twap_nonce: u128,
+
+    /// Number of slices released so far
+    pub slices_released: u64,
+
+    /// Slot of the last accepted crank
+    pub last_crank_slot: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl TwapOrder {
+    pub const SEED_PREFIX: &'static [u8] = b"twap";
+
+    /// Minimum slots between two cranks of the same TWAP. Every crank holds
+    /// the child order until its callback lands, so back-to-back cranks would
+    /// keep the child out of matching.
+    pub const MIN_CRANK_INTERVAL_SLOTS: u64 = 20;
+
+    /// Space calculation for account
+    /// discriminator(8) + owner(32) + margin_account(32) + child_order(32) + order_id(8)
+    /// + status(1) + encrypted_twap(128) + twap_nonce(16) + slices_released(8)
+    /// + last_crank_slot(8) + bump(1) = 274 bytes
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 8 + 1 + 128 + 16 + 8 + 8 + 1;
+
+    /// Record a crank at `current_slot`, rejecting it if the previous crank
+    /// was less than `MIN_CRANK_INTERVAL_SLOTS` ago
+    pub fn record_crank(&mut self, current_slot: u64) -> Result<()> {
+        require!(
+            self.last_crank_slot == 0
+                || current_slot.saturating_sub(self.last_crank_slot)
+                    >= Self::MIN_CRANK_INTERVAL_SLOTS,
+            ErrorCode::TwapCrankTooSoon
+        );
+        self.last_crank_slot = current_slot;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn twap_order() -> TwapOrder {
+        TwapOrder {
+            owner: Pubkey::new_unique(),
+            margin_account: Pubkey::new_unique(),
+            child_order: Pubkey::new_unique(),
+            order_id: 1,
+            status: TwapStatus::Active,
+            encrypted_twap: [[0u8; 32]; 4],
+            twap_nonce: 0,
+            slices_released: 0,
+            last_crank_slot: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn first_crank_is_accepted() {
+        let mut twap = twap_order();
+        twap.record_crank(5).unwrap();
+        assert_eq!(twap.last_crank_slot, 5);
+    }
+
+    #[test]
+    fn crank_within_interval_is_rejected() {
+        let mut twap = twap_order();
+        twap.record_crank(100).unwrap();
+
+        let result = twap.record_crank(100 + TwapOrder::MIN_CRANK_INTERVAL_SLOTS - 1);
+        assert_eq!(result.unwrap_err(), ErrorCode::TwapCrankTooSoon.into());
+        assert_eq!(twap.last_crank_slot, 100);
+
+        twap.record_crank(100 + TwapOrder::MIN_CRANK_INTERVAL_SLOTS)
+            .unwrap();
+    }
+}
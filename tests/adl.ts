@@ -0,0 +1,82 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import {
+  Protocol,
+  Trader,
+  arciumAccounts,
+  createTrader,
+  expectError,
+  newComputationOffset,
+  pda,
+  refreshMarkPrice,
+  setupProtocol,
+} from "./helpers";
+
+describe("auto-deleveraging", () => {
+  const collateral = 1_000_000_000;
+
+  let p: Protocol;
+  let bankrupt: Trader;
+  let candidate: Trader;
+
+  before(async () => {
+    p = await setupProtocol();
+    bankrupt = await createTrader(p, collateral);
+    candidate = await createTrader(p, collateral);
+  });
+
+  async function queueAdlRanking(
+    margin: PublicKey,
+    candidates: PublicKey[],
+    payer: Keypair = p.wallet.payer,
+  ) {
+    await refreshMarkPrice(p);
+    const computationOffset: BN = newComputationOffset();
+    await p.program.methods
+      .queueAdlRanking(computationOffset)
+      .accountsPartial({
+        payer: payer.publicKey,
+        config: p.config,
+        market: p.market,
+        marginAccount: margin,
+        adlRanking: pda(
+          p.program,
+          Buffer.from("adl_ranking"),
+          margin.toBuffer(),
+        ),
+        ...arciumAccounts(p.program, "rank_adl", computationOffset),
+      })
+      .remainingAccounts(
+        candidates.map((pubkey) => ({
+          pubkey,
+          isSigner: false,
+          isWritable: false,
+        })),
+      )
+      .signers([payer])
+      .rpc();
+  }
+
+  it("rejects a ranking queued by an unregistered matcher", async () => {
+    const outsider = Keypair.generate();
+    await p.provider.connection.confirmTransaction(
+      await p.provider.connection.requestAirdrop(
+        outsider.publicKey,
+        LAMPORTS_PER_SOL,
+      ),
+      "confirmed",
+    );
+
+    await expectError(
+      queueAdlRanking(bankrupt.margin, [candidate.margin], outsider),
+      "UnauthorizedMatcher",
+    );
+  });
+
+  it("rejects ranking an account with no pending ADL", async () => {
+    await expectError(
+      queueAdlRanking(bankrupt.margin, [candidate.margin]),
+      "AdlNotPending",
+    );
+  });
+});
@@ -0,0 +1,528 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import {
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createAccount,
+  createMint,
+  getAssociatedTokenAddressSync,
+  mintTo,
+} from "@solana/spl-token";
+import {
+  RescueCipher,
+  awaitComputationFinalization,
+  deserializeLE,
+  getArciumEnv,
+  getClusterAccAddress,
+  getCompDefAccAddress,
+  getCompDefAccOffset,
+  getComputationAccAddress,
+  getExecutingPoolAccAddress,
+  getMempoolAccAddress,
+  getMXEAccAddress,
+  getMXEPublicKey,
+} from "@arcium-hq/client";
+import { x25519 } from "@noble/curves/ed25519";
+import { randomBytes } from "crypto";
+import { expect } from "chai";
+import { ZecDarkPerps } from "../target/types/zec_dark_perps";
+
+// Shared fixtures for the MPC flows. Run against `arcium localnet`, which
+// deploys the program as upgradeable with the provider wallet as upgrade
+// authority, so the wallet can initialize the config and the market.
+
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+  "BPFLoaderUpgradeab1e11111111111111111111111",
+);
+
+/** Fixed-point scale shared by prices and sizes (1e6) */
+export const PRICE_SCALE = 1_000_000;
+
+/** Mark price the market starts at: $50.00 */
+export const MARK_PRICE = 50 * PRICE_SCALE;
+
+export const TICK_SIZE = 10_000;
+export const LOT_SIZE = 1_000;
+export const PRICE_BAND_BPS = 1_000;
+
+/** Sides as encoded in the order ciphertext */
+export const BUY = 1;
+export const SELL = 0;
+
+/** Circuits the MPC flow tests go through */
+const COMP_DEFS: [string, string][] = [
+  ["deposit_collateral", "initDepositCompDef"],
+  ["withdraw_collateral", "initWithdrawCompDef"],
+  ["submit_order", "initSubmitOrderCompDef"],
+  ["match_orders", "initMatchOrdersCompDef"],
+  ["return_fill", "initReturnFillCompDef"],
+  ["settle_trade", "initSettleCompDef"],
+  ["settle_batch", "initSettleBatchCompDef"],
+  ["check_health", "initHealthCheckCompDef"],
+  ["liquidate_position", "initLiquidatePositionCompDef"],
+  ["rank_adl", "initRankAdlCompDef"],
+];
+
+export type Protocol = {
+  program: Program<ZecDarkPerps>;
+  provider: anchor.AnchorProvider;
+  wallet: anchor.Wallet;
+  mint: PublicKey;
+  config: PublicKey;
+  market: PublicKey;
+  protocolFees: PublicKey;
+  insuranceFund: PublicKey;
+  insuranceVault: PublicKey;
+};
+
+export type Trader = {
+  owner: Keypair;
+  tokenAccount: PublicKey;
+  margin: PublicKey;
+  vault: PublicKey;
+};
+
+export function pda(program: Program<ZecDarkPerps>, ...seeds: Buffer[]) {
+  return PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+}
+
+export function newComputationOffset(): BN {
+  return new BN(randomBytes(8), "hex");
+}
+
+/** Accounts every queue_computation instruction takes */
+export function arciumAccounts(
+  program: Program<ZecDarkPerps>,
+  circuit: string,
+  computationOffset: BN,
+) {
+  const compDefOffset = Buffer.from(
+    getCompDefAccOffset(circuit),
+  ).readUInt32LE();
+  return {
+    mxeAccount: getMXEAccAddress(program.programId),
+    mempoolAccount: getMempoolAccAddress(program.programId),
+    executingPool: getExecutingPoolAccAddress(program.programId),
+    computationAccount: getComputationAccAddress(
+      program.programId,
+      computationOffset,
+    ),
+    compDefAccount: getCompDefAccAddress(program.programId, compDefOffset),
+    clusterAccount: getClusterAccAddress(getArciumEnv().arciumClusterOffset),
+  };
+}
+
+export async function awaitComputation(
+  provider: anchor.AnchorProvider,
+  program: Program<ZecDarkPerps>,
+  computationOffset: BN,
+) {
+  return awaitComputationFinalization(
+    provider,
+    computationOffset,
+    program.programId,
+    "confirmed",
+  );
+}
+
+/** Expect `promise` to fail with the program error `code` */
+export async function expectError(promise: Promise<unknown>, code: string) {
+  let error: unknown;
+  try {
+    await promise;
+  } catch (err) {
+    error = err;
+  }
+  expect(error, `expected ${code}`).to.be.instanceOf(anchor.AnchorError);
+  expect((error as anchor.AnchorError).error.errorCode.code).to.equal(code);
+}
+
+let protocol: Promise<Protocol> | undefined;
+
+/**
+ * Config, market, fee and insurance accounts plus the computation
+ * definitions, created once per test run and shared by every test file.
+ * The provider wallet is the config authority, the only matcher and the
+ * market oracle.
+ */
+export function setupProtocol(): Promise<Protocol> {
+  if (!protocol) {
+    protocol = initProtocol();
+  }
+  return protocol;
+}
+
+async function initProtocol(): Promise<Protocol> {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.ZecDarkPerps as Program<ZecDarkPerps>;
+  const wallet = provider.wallet as anchor.Wallet;
+
+  const config = pda(program, Buffer.from("config"));
+  const market = pda(program, Buffer.from("market"));
+  const protocolFees = pda(program, Buffer.from("protocol_fees"));
+  const insuranceFund = pda(program, Buffer.from("insurance_fund"));
+  const programData = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    BPF_LOADER_UPGRADEABLE_ID,
+  )[0];
+
+  const mint = await createMint(
+    provider.connection,
+    wallet.payer,
+    wallet.publicKey,
+    null,
+    8, // decimals
+  );
+  const insuranceVault = getAssociatedTokenAddressSync(
+    mint,
+    insuranceFund,
+    true,
+  );
+
+  await program.methods
+    .initializeConfig()
+    .accountsPartial({
+      authority: wallet.publicKey,
+      config,
+      program: program.programId,
+      programData,
+    })
+    .rpc();
+  await program.methods
+    .addMatcher(wallet.publicKey)
+    .accountsPartial({ authority: wallet.publicKey, config })
+    .rpc();
+
+  await program.methods
+    .initializeMarket(
+      wallet.publicKey,
+      new BN(TICK_SIZE),
+      new BN(LOT_SIZE),
+      PRICE_BAND_BPS,
+    )
+    .accountsPartial({
+      authority: wallet.publicKey,
+      market,
+      program: program.programId,
+      programData,
+    })
+    .rpc();
+
+  await program.methods
+    .initProtocolFees()
+    .accountsPartial({ authority: wallet.publicKey, config, protocolFees })
+    .rpc();
+  await program.methods
+    .initInsuranceFund()
+    .accountsPartial({
+      authority: wallet.publicKey,
+      config,
+      insuranceFund,
+      insuranceVault,
+      mint,
+    })
+    .rpc();
+
+  for (const [circuit, method] of COMP_DEFS) {
+    const compDefOffset = Buffer.from(
+      getCompDefAccOffset(circuit),
+    ).readUInt32LE();
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    await (program.methods as any)
+      [method]()
+      .accountsPartial({
+        payer: wallet.publicKey,
+        mxeAccount: getMXEAccAddress(program.programId),
+        compDefAccount: getCompDefAccAddress(program.programId, compDefOffset),
+      })
+      .rpc();
+  }
+
+  const shared = {
+    program,
+    provider,
+    wallet,
+    mint,
+    config,
+    market,
+    protocolFees,
+    insuranceFund,
+    insuranceVault,
+  };
+  await refreshMarkPrice(shared);
+  return shared;
+}
+
+/** Push the mark price again so it is fresh for the next instruction */
+export async function refreshMarkPrice(p: Protocol, price = MARK_PRICE) {
+  await p.program.methods
+    .updateMarkPrice(new BN(price))
+    .accountsPartial({ oracle: p.wallet.publicKey, market: p.market })
+    .rpc();
+}
+
+/**
+ * A funded trader: margin account initialized, `collateral` deposited into
+ * the vault and credited to the encrypted balance in MPC
+ */
+export async function createTrader(
+  p: Protocol,
+  collateral: number,
+): Promise<Trader> {
+  const { program, provider, wallet, mint } = p;
+  const owner = Keypair.generate();
+  await provider.connection.confirmTransaction(
+    await provider.connection.requestAirdrop(
+      owner.publicKey,
+      2 * LAMPORTS_PER_SOL,
+    ),
+    "confirmed",
+  );
+
+  const tokenAccount = await createAccount(
+    provider.connection,
+    wallet.payer,
+    mint,
+    owner.publicKey,
+  );
+  await mintTo(
+    provider.connection,
+    wallet.payer,
+    mint,
+    tokenAccount,
+    wallet.payer,
+    BigInt(collateral),
+  );
+
+  const margin = pda(
+    program,
+    Buffer.from("margin"),
+    owner.publicKey.toBuffer(),
+  );
+  const vault = getAssociatedTokenAddressSync(mint, margin, true);
+
+  await program.methods
+    .initializeMarginAccount()
+    .accountsPartial({
+      owner: owner.publicKey,
+      marginAccount: margin,
+      marginVault: vault,
+      mint,
+      systemProgram: SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+    })
+    .signers([owner])
+    .rpc();
+
+  await program.methods
+    .depositCollateral(new BN(collateral))
+    .accountsPartial({
+      owner: owner.publicKey,
+      marginAccount: margin,
+      ownerTokenAccount: tokenAccount,
+      marginVault: vault,
+      mint,
+    })
+    .signers([owner])
+    .rpc();
+
+  const computationOffset = newComputationOffset();
+  await program.methods
+    .queueDeposit(computationOffset, new BN(collateral))
+    .accountsPartial({
+      payer: wallet.publicKey,
+      marginAccount: margin,
+      ...arciumAccounts(program, "deposit_collateral", computationOffset),
+    })
+    .rpc();
+  await awaitComputation(provider, program, computationOffset);
+
+  return { owner, tokenAccount, margin, vault };
+}
+
+async function mxePublicKey(p: Protocol): Promise<Uint8Array> {
+  for (let attempt = 0; attempt < 10; attempt++) {
+    const key = await getMXEPublicKey(p.provider, p.program.programId);
+    if (key) {
+      return key;
+    }
+    await new Promise((resolve) => setTimeout(resolve, 500));
+  }
+  throw new Error("MXE public key not set");
+}
+
+/**
+ * Encrypt and submit an order for `trader`, wait for the MPC callback and
+ * return the order account
+ */
+export async function submitOrder(
+  p: Protocol,
+  trader: Trader,
+  order: { side: number; price: number; size: number; isMarket?: boolean },
+): Promise<PublicKey> {
+  const { program, provider } = p;
+  await refreshMarkPrice(p);
+
+  const margin = await program.account.marginAccount.fetch(trader.margin);
+  const sequence = margin.orderSequence.addn(1);
+
+  const privateKey = x25519.utils.randomSecretKey();
+  const publicKey = x25519.getPublicKey(privateKey);
+  const cipher = new RescueCipher(
+    x25519.getSharedSecret(privateKey, await mxePublicKey(p)),
+  );
+  const nonce = randomBytes(16);
+  // size, price, side, display_size, sequence, stp_mode (cancel newest)
+  const ciphertexts = cipher.encrypt(
+    [
+      BigInt(order.size),
+      BigInt(order.price),
+      BigInt(order.side),
+      BigInt(0),
+      BigInt(sequence.toString()),
+      BigInt(0),
+    ],
+    nonce,
+  );
+
+  const computationOffset = newComputationOffset();
+  const orderAccount = pda(
+    program,
+    Buffer.from("order"),
+    trader.margin.toBuffer(),
+    computationOffset.toArrayLike(Buffer, "le", 8),
+  );
+
+  await program.methods
+    .submitOrder(
+      computationOffset,
+      order.isMarket ?? false,
+      ciphertexts[0],
+      ciphertexts[1],
+      ciphertexts[2],
+      ciphertexts[3],
+      ciphertexts[4],
+      ciphertexts[5],
+      Array.from(publicKey),
+      new BN(deserializeLE(nonce).toString()),
+    )
+    .accountsPartial({
+      owner: trader.owner.publicKey,
+      market: p.market,
+      marginAccount: trader.margin,
+      order: orderAccount,
+      ...arciumAccounts(program, "submit_order", computationOffset),
+    })
+    .signers([trader.owner])
+    .rpc();
+  await awaitComputation(provider, program, computationOffset);
+
+  return orderAccount;
+}
+
+/** Queue a match of two orders and return the fill account, without waiting */
+export async function queueMatch(
+  p: Protocol,
+  buyer: Trader,
+  buyOrder: PublicKey,
+  seller: Trader,
+  sellOrder: PublicKey,
+  matcher: Keypair = p.wallet.payer,
+): Promise<{ fill: PublicKey; computationOffset: BN }> {
+  const { program } = p;
+  const computationOffset = newComputationOffset();
+  const fill = pda(
+    program,
+    Buffer.from("fill"),
+    computationOffset.toArrayLike(Buffer, "le", 8),
+  );
+
+  await program.methods
+    .queueMatchOrders(computationOffset)
+    .accountsPartial({
+      matcher: matcher.publicKey,
+      config: p.config,
+      buyerMargin: buyer.margin,
+      sellerMargin: seller.margin,
+      buyOrder,
+      sellOrder,
+      fill,
+      ...arciumAccounts(program, "match_orders", computationOffset),
+    })
+    .signers([matcher])
+    .rpc();
+
+  return { fill, computationOffset };
+}
+
+/** Match two orders and wait for the MPC callback */
+export async function matchOrders(
+  p: Protocol,
+  buyer: Trader,
+  buyOrder: PublicKey,
+  seller: Trader,
+  sellOrder: PublicKey,
+): Promise<PublicKey> {
+  const { fill, computationOffset } = await queueMatch(
+    p,
+    buyer,
+    buyOrder,
+    seller,
+    sellOrder,
+  );
+  await awaitComputation(p.provider, p.program, computationOffset);
+  return fill;
+}
+
+/** Settle one matched fill through settle_trade and wait for the callback */
+export async function settleFill(
+  p: Protocol,
+  buyer: Trader,
+  seller: Trader,
+  fill: PublicKey,
+) {
+  const { program, wallet } = p;
+  const computationOffset = newComputationOffset();
+
+  // The post-trade margin check runs at the mark price
+  await refreshMarkPrice(p);
+  await program.methods
+    .queueSettleTrade(computationOffset)
+    .accountsPartial({
+      payer: wallet.publicKey,
+      matcher: wallet.publicKey,
+      config: p.config,
+      buyerMargin: buyer.margin,
+      sellerMargin: seller.margin,
+      fill,
+      market: p.market,
+      protocolFees: p.protocolFees,
+      ...arciumAccounts(program, "settle_trade", computationOffset),
+    })
+    .rpc();
+  await awaitComputation(p.provider, program, computationOffset);
+}
+
+/** Open a position of `size` between a buyer and a seller at `price` */
+export async function openPosition(
+  p: Protocol,
+  buyer: Trader,
+  seller: Trader,
+  size: number,
+  price = MARK_PRICE,
+): Promise<PublicKey> {
+  const buyOrder = await submitOrder(p, buyer, { side: BUY, price, size });
+  const sellOrder = await submitOrder(p, seller, { side: SELL, price, size });
+  const fill = await matchOrders(p, buyer, buyOrder, seller, sellOrder);
+  await settleFill(p, buyer, seller, fill);
+  return fill;
+}
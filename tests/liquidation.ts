@@ -0,0 +1,151 @@
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { createAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  PRICE_SCALE,
+  Protocol,
+  Trader,
+  arciumAccounts,
+  awaitComputation,
+  createTrader,
+  expectError,
+  newComputationOffset,
+  openPosition,
+  refreshMarkPrice,
+  setupProtocol,
+} from "./helpers";
+
+describe("liquidation", () => {
+  const size = PRICE_SCALE; // 1 ZEC, $50 at the mark price
+
+  // Risk parameters a new market starts with
+  const maintenanceMarginBps = 500;
+  const liquidationPenaltyBps = 250;
+  const insuranceFundShareBps = 5_000;
+  const healthCheckMaxAgeSlots = 150;
+
+  let p: Protocol;
+  let long: Trader;
+  let short: Trader;
+  let liquidatorTokenAccount: PublicKey;
+
+  before(async () => {
+    p = await setupProtocol();
+    long = await createTrader(p, 1_000_000_000);
+    // $10 of collateral against a $50 short: healthy at a 5% maintenance
+    // margin, liquidatable at 50%
+    short = await createTrader(p, 10_000_000);
+    await openPosition(p, long, short, size);
+
+    liquidatorTokenAccount = await createAccount(
+      p.provider.connection,
+      p.wallet.payer,
+      p.mint,
+      p.wallet.publicKey,
+    );
+  });
+
+  after(async () => {
+    await setRiskParams(maintenanceMarginBps, healthCheckMaxAgeSlots);
+  });
+
+  async function setRiskParams(maintenanceBps: number, maxAgeSlots: number) {
+    await p.program.methods
+      .updateRiskParams(
+        maintenanceBps,
+        liquidationPenaltyBps,
+        insuranceFundShareBps,
+        new BN(maxAgeSlots),
+      )
+      .accountsPartial({ authority: p.wallet.publicKey, market: p.market })
+      .rpc();
+  }
+
+  async function checkHealth(trader: Trader) {
+    await refreshMarkPrice(p);
+    const computationOffset = newComputationOffset();
+    await p.program.methods
+      .queueHealthCheck(computationOffset)
+      .accountsPartial({
+        payer: p.wallet.publicKey,
+        marginAccount: trader.margin,
+        market: p.market,
+        ...arciumAccounts(p.program, "check_health", computationOffset),
+      })
+      .rpc();
+    await awaitComputation(p.provider, p.program, computationOffset);
+  }
+
+  async function liquidate(trader: Trader, computationOffset: BN) {
+    await refreshMarkPrice(p);
+    await p.program.methods
+      .liquidate(computationOffset)
+      .accountsPartial({
+        liquidator: p.wallet.publicKey,
+        marginAccount: trader.margin,
+        market: p.market,
+        marginVault: trader.vault,
+        liquidatorTokenAccount,
+        insuranceFund: p.insuranceFund,
+        insuranceVault: p.insuranceVault,
+        protocolFees: p.protocolFees,
+        ...arciumAccounts(p.program, "liquidate_position", computationOffset),
+      })
+      .rpc();
+  }
+
+  it("rejects liquidating a healthy account", async () => {
+    await checkHealth(short);
+
+    const margin = await p.program.account.marginAccount.fetch(short.margin);
+    expect(margin.isLiquidatable).to.be.false;
+    await expectError(
+      liquidate(short, newComputationOffset()),
+      "HealthyPosition",
+    );
+  });
+
+  it("flags the account once the maintenance margin is raised", async () => {
+    await setRiskParams(5_000, healthCheckMaxAgeSlots);
+    await checkHealth(short);
+
+    const margin = await p.program.account.marginAccount.fetch(short.margin);
+    expect(margin.isLiquidatable).to.be.true;
+    expect(margin.healthCheckVersion.eq(margin.stateVersion)).to.be.true;
+  });
+
+  it("rejects a liquidation based on an outdated health check", async () => {
+    await setRiskParams(5_000, 1);
+    // Let the health check age past the limit
+    await new Promise((resolve) => setTimeout(resolve, 2_000));
+
+    await expectError(
+      liquidate(short, newComputationOffset()),
+      "StaleHealthCheck",
+    );
+  });
+
+  it("liquidates the account and invalidates its health check", async () => {
+    await setRiskParams(5_000, healthCheckMaxAgeSlots);
+    await checkHealth(short);
+    const before = await p.program.account.marginAccount.fetch(short.margin);
+    expect(before.isLiquidatable).to.be.true;
+
+    const computationOffset = newComputationOffset();
+    await liquidate(short, computationOffset);
+    await awaitComputation(p.provider, p.program, computationOffset);
+
+    const after = await p.program.account.marginAccount.fetch(short.margin);
+    expect(after.stateVersion.toNumber()).to.equal(
+      before.stateVersion.toNumber() + 1,
+    );
+    expect(after.isLiquidatable).to.be.false;
+
+    // A new check is needed before the account can be liquidated again
+    await expectError(
+      liquidate(short, newComputationOffset()),
+      "HealthyPosition",
+    );
+  });
+});
@@ -8,8 +8,18 @@ import {
   createMint,
   createAccount,
   mintTo,
+  getAccount,
 } from "@solana/spl-token";
+import { expect } from "chai";
 import { ZecDarkPerps } from "../target/types/zec_dark_perps";
+import {
+  arciumAccounts,
+  awaitComputation,
+  createTrader,
+  newComputationOffset,
+  refreshMarkPrice,
+  setupProtocol,
+} from "./helpers";
 
 describe("margin account", () => {
   // Configure the client to use the local validator by default
@@ -90,7 +100,7 @@ describe("margin account", () => {
     console.log("margin after init:", margin);
   });
 
-  it("deposits and withdraws collateral", async () => {
+  it("holds withdrawals until MPC approves them", async () => {
     const amount = new anchor.BN(100_000_000); // 1 token (decimals=8)
 
     // Deposit
@@ -110,21 +120,77 @@ describe("margin account", () => {
     let margin = await program.account.marginAccount.fetch(marginPda);
     console.log("margin after deposit:", margin);
 
-    // Withdraw
-    await program.methods
-      .withdrawCollateral(amount)
-      .accounts({
-        owner: wallet.publicKey,
-        marginAccount: marginPda,
-        marginVault: marginVaultAta,
-        ownerTokenAccount: userTokenAccount,
-        mint,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    // Nothing has been approved by a queued withdrawal yet
+    try {
+      await program.methods
+        .withdrawCollateral(amount)
+        .accounts({
+          owner: wallet.publicKey,
+          marginAccount: marginPda,
+          marginVault: marginVaultAta,
+          ownerTokenAccount: userTokenAccount,
+          mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      expect.fail("withdrawal without approval succeeded");
+    } catch (err) {
+      expect(err).to.be.instanceOf(anchor.AnchorError);
+      expect((err as anchor.AnchorError).error.errorCode.code).to.equal(
+        "InsufficientCollateral",
+      );
+    }
+
+    margin = await program.account.marginAccount.fetch(marginPda);
+    expect(margin.approvedWithdrawal.toNumber()).to.equal(0);
+    expect(margin.pendingDeposit.toNumber()).to.equal(amount.toNumber());
+  });
+
+  it("pays out a withdrawal once MPC approves it", async () => {
+    const p = await setupProtocol();
+    const collateral = 1_000_000_000;
+    const amount = 400_000_000;
+    const trader = await createTrader(p, collateral);
+
+    await refreshMarkPrice(p);
+    const computationOffset = newComputationOffset();
+    await p.program.methods
+      .queueWithdraw(computationOffset, new anchor.BN(amount))
+      .accountsPartial({
+        payer: trader.owner.publicKey,
+        marginAccount: trader.margin,
+        market: p.market,
+        ...arciumAccounts(
+          p.program,
+          "withdraw_collateral",
+          computationOffset,
+        ),
+      })
+      .signers([trader.owner])
+      .rpc();
+    await awaitComputation(p.provider, p.program, computationOffset);
+
+    let margin = await p.program.account.marginAccount.fetch(trader.margin);
+    expect(margin.approvedWithdrawal.toNumber()).to.equal(amount);
+
+    await p.program.methods
+      .withdrawCollateral(new anchor.BN(amount))
+      .accountsPartial({
+        owner: trader.owner.publicKey,
+        marginAccount: trader.margin,
+        marginVault: trader.vault,
+        ownerTokenAccount: trader.tokenAccount,
+        mint: p.mint,
       })
+      .signers([trader.owner])
       .rpc();
 
-    margin = await program.account.marginAccount.fetch(marginPda);
-    console.log("margin after withdraw:", margin);
+    margin = await p.program.account.marginAccount.fetch(trader.margin);
+    expect(margin.approvedWithdrawal.toNumber()).to.equal(0);
+    const tokens = await getAccount(p.provider.connection, trader.tokenAccount);
+    expect(Number(tokens.amount)).to.equal(amount);
+    const vault = await getAccount(p.provider.connection, trader.vault);
+    expect(Number(vault.amount)).to.equal(collateral - amount);
   });
 });
@@ -0,0 +1,160 @@
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  BUY,
+  MARK_PRICE,
+  PRICE_SCALE,
+  Protocol,
+  SELL,
+  Trader,
+  awaitComputation,
+  createTrader,
+  expectError,
+  matchOrders,
+  queueMatch,
+  setupProtocol,
+  submitOrder,
+} from "./helpers";
+
+describe("order matching", () => {
+  const collateral = 1_000_000_000;
+  const size = PRICE_SCALE; // 1 ZEC
+
+  let p: Protocol;
+  let buyer: Trader;
+  let seller: Trader;
+
+  before(async () => {
+    p = await setupProtocol();
+    buyer = await createTrader(p, collateral);
+    seller = await createTrader(p, collateral);
+  });
+
+  it("rejects a match queued by an unregistered matcher", async () => {
+    const buyOrder = await submitOrder(p, buyer, {
+      side: BUY,
+      price: MARK_PRICE,
+      size,
+    });
+    const sellOrder = await submitOrder(p, seller, {
+      side: SELL,
+      price: MARK_PRICE,
+      size,
+    });
+
+    const outsider = Keypair.generate();
+    await p.provider.connection.confirmTransaction(
+      await p.provider.connection.requestAirdrop(
+        outsider.publicKey,
+        LAMPORTS_PER_SOL,
+      ),
+      "confirmed",
+    );
+
+    await expectError(
+      queueMatch(p, buyer, buyOrder, seller, sellOrder, outsider),
+      "UnauthorizedMatcher",
+    );
+  });
+
+  it("matches crossing orders into an encrypted fill", async () => {
+    const buyOrder = await submitOrder(p, buyer, {
+      side: BUY,
+      price: MARK_PRICE,
+      size,
+    });
+    const sellOrder = await submitOrder(p, seller, {
+      side: SELL,
+      price: MARK_PRICE,
+      size,
+    });
+
+    const fill = await matchOrders(p, buyer, buyOrder, seller, sellOrder);
+
+    const fillAccount = await p.program.account.fill.fetch(fill);
+    expect(fillAccount.status).to.deep.equal({ matched: {} });
+    expect(fillAccount.buyerMargin.equals(buyer.margin)).to.be.true;
+    expect(fillAccount.sellerMargin.equals(seller.margin)).to.be.true;
+
+    for (const order of [buyOrder, sellOrder]) {
+      const orderAccount = await p.program.account.order.fetch(order);
+      expect(orderAccount.status).to.deep.equal({ filled: {} });
+      expect(orderAccount.inFlight.equals(PublicKey.default)).to.be.true;
+    }
+  });
+
+  it("leaves orders that do not cross open", async () => {
+    const buyOrder = await submitOrder(p, buyer, {
+      side: BUY,
+      price: MARK_PRICE - PRICE_SCALE,
+      size,
+    });
+    const sellOrder = await submitOrder(p, seller, {
+      side: SELL,
+      price: MARK_PRICE + PRICE_SCALE,
+      size,
+    });
+
+    const fill = await matchOrders(p, buyer, buyOrder, seller, sellOrder);
+
+    const fillAccount = await p.program.account.fill.fetch(fill);
+    expect(fillAccount.status).to.deep.equal({ noMatch: {} });
+    for (const order of [buyOrder, sellOrder]) {
+      const orderAccount = await p.program.account.order.fetch(order);
+      expect(orderAccount.status).to.deep.equal({ open: {} });
+    }
+  });
+
+  it("keeps a market order open when the sell does not cross", async () => {
+    const buyOrder = await submitOrder(p, buyer, {
+      side: BUY,
+      price: MARK_PRICE - PRICE_SCALE,
+      size,
+      isMarket: true,
+    });
+    const sellOrder = await submitOrder(p, seller, {
+      side: SELL,
+      price: MARK_PRICE + PRICE_SCALE,
+      size,
+    });
+
+    await matchOrders(p, buyer, buyOrder, seller, sellOrder);
+
+    const orderAccount = await p.program.account.order.fetch(buyOrder);
+    expect(orderAccount.status).to.deep.equal({ open: {} });
+  });
+
+  it("rejects an order held by a match still in flight", async () => {
+    const buyOrder = await submitOrder(p, buyer, {
+      side: BUY,
+      price: MARK_PRICE,
+      size,
+    });
+    const sellOrder = await submitOrder(p, seller, {
+      side: SELL,
+      price: MARK_PRICE,
+      size,
+    });
+    const otherSell = await submitOrder(p, seller, {
+      side: SELL,
+      price: MARK_PRICE,
+      size,
+    });
+
+    const { computationOffset } = await queueMatch(
+      p,
+      buyer,
+      buyOrder,
+      seller,
+      sellOrder,
+    );
+    await expectError(
+      queueMatch(p, buyer, buyOrder, seller, otherSell),
+      "OrderInFlight",
+    );
+    await awaitComputation(p.provider, p.program, computationOffset);
+
+    const orderAccount = await p.program.account.order.fetch(buyOrder);
+    expect(orderAccount.inFlight.equals(PublicKey.default)).to.be.true;
+  });
+});
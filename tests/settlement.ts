@@ -0,0 +1,235 @@
+import { PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  BUY,
+  MARK_PRICE,
+  PRICE_SCALE,
+  Protocol,
+  SELL,
+  Trader,
+  arciumAccounts,
+  awaitComputation,
+  createTrader,
+  expectError,
+  matchOrders,
+  newComputationOffset,
+  refreshMarkPrice,
+  settleFill,
+  setupProtocol,
+  submitOrder,
+} from "./helpers";
+
+describe("trade settlement", () => {
+  const collateral = 1_000_000_000;
+  const size = PRICE_SCALE; // 1 ZEC
+
+  let p: Protocol;
+  let buyer: Trader;
+  let seller: Trader;
+
+  before(async () => {
+    p = await setupProtocol();
+    buyer = await createTrader(p, collateral);
+    seller = await createTrader(p, collateral);
+  });
+
+  async function matchedFill(): Promise<PublicKey> {
+    const buyOrder = await submitOrder(p, buyer, {
+      side: BUY,
+      price: MARK_PRICE,
+      size,
+    });
+    const sellOrder = await submitOrder(p, seller, {
+      side: SELL,
+      price: MARK_PRICE,
+      size,
+    });
+    return matchOrders(p, buyer, buyOrder, seller, sellOrder);
+  }
+
+  // $10 of collateral cannot back a $500 short at a 5% maintenance margin
+  async function undercollateralizedFill() {
+    const thinSeller = await createTrader(p, 10_000_000);
+    const buyOrder = await submitOrder(p, buyer, {
+      side: BUY,
+      price: MARK_PRICE,
+      size: 10 * size,
+    });
+    const sellOrder = await submitOrder(p, thinSeller, {
+      side: SELL,
+      price: MARK_PRICE,
+      size: 10 * size,
+    });
+    const fill = await matchOrders(p, buyer, buyOrder, thinSeller, sellOrder);
+    return { thinSeller, buyOrder, sellOrder, fill };
+  }
+
+  async function queueSettleBatch(fills: PublicKey[], margins: PublicKey[]) {
+    await refreshMarkPrice(p);
+    const computationOffset = newComputationOffset();
+    await p.program.methods
+      .queueSettleBatch(computationOffset, fills.length)
+      .accountsPartial({
+        matcher: p.wallet.publicKey,
+        config: p.config,
+        market: p.market,
+        protocolFees: p.protocolFees,
+        ...arciumAccounts(p.program, "settle_batch", computationOffset),
+      })
+      .remainingAccounts(
+        [...fills, ...margins].map((pubkey) => ({
+          pubkey,
+          isSigner: false,
+          isWritable: true,
+        })),
+      )
+      .rpc();
+  }
+
+  it("settles a matched fill into both accounts", async () => {
+    const fill = await matchedFill();
+    const buyerBefore = await p.program.account.marginAccount.fetch(
+      buyer.margin,
+    );
+    const sellerBefore = await p.program.account.marginAccount.fetch(
+      seller.margin,
+    );
+
+    await settleFill(p, buyer, seller, fill);
+
+    const fillAccount = await p.program.account.fill.fetch(fill);
+    expect(fillAccount.status).to.deep.equal({ settled: {} });
+
+    // Both balances and positions changed, so any health check is outdated
+    const buyerAfter = await p.program.account.marginAccount.fetch(
+      buyer.margin,
+    );
+    const sellerAfter = await p.program.account.marginAccount.fetch(
+      seller.margin,
+    );
+    expect(buyerAfter.stateVersion.toNumber()).to.equal(
+      buyerBefore.stateVersion.toNumber() + 1,
+    );
+    expect(sellerAfter.stateVersion.toNumber()).to.equal(
+      sellerBefore.stateVersion.toNumber() + 1,
+    );
+    expect(buyerAfter.isLiquidatable).to.be.false;
+
+    // The settlement released the fee balance lock
+    const fees = await p.program.account.protocolFees.fetch(p.protocolFees);
+    expect(fees.lockHolder.equals(PublicKey.default)).to.be.true;
+  });
+
+  it("fails a trade that leaves the seller under margin", async () => {
+    const { thinSeller, fill } = await undercollateralizedFill();
+    const sellerBefore = await p.program.account.marginAccount.fetch(
+      thinSeller.margin,
+    );
+
+    await settleFill(p, buyer, thinSeller, fill);
+
+    const fillAccount = await p.program.account.fill.fetch(fill);
+    expect(fillAccount.status).to.deep.equal({ failed: {} });
+    const sellerAfter = await p.program.account.marginAccount.fetch(
+      thinSeller.margin,
+    );
+    expect(sellerAfter.stateVersion.toNumber()).to.equal(
+      sellerBefore.stateVersion.toNumber(),
+    );
+  });
+
+  it("returns the size of a failed fill to both orders", async () => {
+    const { thinSeller, buyOrder, sellOrder, fill } =
+      await undercollateralizedFill();
+    await settleFill(p, buyer, thinSeller, fill);
+
+    const computationOffset = newComputationOffset();
+    await p.program.methods
+      .queueReturnFill(computationOffset)
+      .accountsPartial({
+        payer: p.wallet.publicKey,
+        fill,
+        buyOrder,
+        sellOrder,
+        ...arciumAccounts(p.program, "return_fill", computationOffset),
+      })
+      .rpc();
+    await awaitComputation(p.provider, p.program, computationOffset);
+
+    const fillAccount = await p.program.account.fill.fetch(fill);
+    expect(fillAccount.status).to.deep.equal({ returned: {} });
+    for (const order of [buyOrder, sellOrder]) {
+      const orderAccount = await p.program.account.order.fetch(order);
+      expect(orderAccount.status).to.deep.equal({ open: {} });
+    }
+  });
+
+  it("keeps a fill whose settlement still holds the fee lock", async () => {
+    const fill = await matchedFill();
+    await refreshMarkPrice(p);
+    const computationOffset = newComputationOffset();
+    await p.program.methods
+      .queueSettleTrade(computationOffset)
+      .accountsPartial({
+        payer: p.wallet.publicKey,
+        matcher: p.wallet.publicKey,
+        config: p.config,
+        buyerMargin: buyer.margin,
+        sellerMargin: seller.margin,
+        fill,
+        market: p.market,
+        protocolFees: p.protocolFees,
+        ...arciumAccounts(p.program, "settle_trade", computationOffset),
+      })
+      .rpc();
+
+    await expectError(
+      p.program.methods
+        .resetSettlingFill()
+        .accountsPartial({ fill, protocolFees: p.protocolFees })
+        .rpc(),
+      "SettlementInFlight",
+    );
+    await awaitComputation(p.provider, p.program, computationOffset);
+  });
+
+  it("rejects settling a fill twice", async () => {
+    const fill = await matchedFill();
+    await settleFill(p, buyer, seller, fill);
+
+    await expectError(settleFill(p, buyer, seller, fill), "InvalidFillStatus");
+  });
+
+  it("rejects a batch with a margin account no fill references", async () => {
+    const fill = await matchedFill();
+    const outsider = await createTrader(p, collateral);
+
+    await expectError(
+      queueSettleBatch([fill], [buyer.margin, seller.margin, outsider.margin]),
+      "InvalidBatchAccount",
+    );
+
+    // The rejected batch left the fill ready for settlement
+    const fillAccount = await p.program.account.fill.fetch(fill);
+    expect(fillAccount.status).to.deep.equal({ matched: {} });
+  });
+
+  it("rejects a batch listing the same fill twice", async () => {
+    const fill = await matchedFill();
+
+    await expectError(
+      queueSettleBatch([fill, fill], [buyer.margin, seller.margin]),
+      "DuplicateBatchAccount",
+    );
+  });
+
+  it("rejects a batch whose fill belongs to other accounts", async () => {
+    const fill = await matchedFill();
+    const outsider = await createTrader(p, collateral);
+
+    await expectError(
+      queueSettleBatch([fill], [buyer.margin, outsider.margin]),
+      "OrderAccountMismatch",
+    );
+  });
+});